@@ -4,19 +4,44 @@
 // license that can be found in the LICENSE file or at
 // https://opensource.org/licenses/MIT.
 
+use std::io;
 use std::path::Path;
 use std::sync::Arc;
 
+use async_trait::async_trait;
+use tokio::io::AsyncWrite;
 use tokio::sync::Mutex;
 
 use crate::output as tv;
-use crate::output::writer::{self, BufferWriter, FileWriter, StdoutWriter, WriterType};
+use crate::output::writer::{
+    self, AsyncWriteWriter, BufferOverflow, BufferWriter, BytesBufferWriter, FileWriter,
+    RecordingWriter, StdoutWriter, WriterType,
+};
 
 /// The configuration repository for the TestRun.
 pub struct Config {
     // All fields are readable for any impl inside the crate.
     pub(crate) timestamp_provider: Box<dyn TimestampProvider + Send + Sync + 'static>,
     pub(crate) writer: WriterType,
+    pub(crate) series_id_generator: Option<Arc<SeriesIdGenerator>>,
+    pub(crate) transform: Option<Box<Transform>>,
+    pub(crate) emit_summary: bool,
+    pub(crate) line_separator: Option<String>,
+    pub(crate) auto_step_timing: bool,
+    pub(crate) emit_timestamps: bool,
+    pub(crate) timestamp_precision: TimestampPrecision,
+    pub(crate) write_error_policy: WriteErrorPolicy,
+    pub(crate) sanitize_control_chars: bool,
+    pub(crate) lazy_start: bool,
+    pub(crate) inline_file_max_size: usize,
+    pub(crate) sequence_start: u64,
+    pub(crate) non_finite_policy: NonFinitePolicy,
+    pub(crate) flush_each: bool,
+    pub(crate) min_severity: tv::LogSeverity,
+    pub(crate) symptom_pattern: Option<regex::Regex>,
+    pub(crate) extension_registry: Option<tv::ExtensionRegistry>,
+    #[cfg(feature = "otel")]
+    pub(crate) otel_tracer: Option<Arc<opentelemetry::global::BoxedTracer>>,
 }
 
 impl Config {
@@ -30,12 +55,150 @@ impl Config {
     pub fn builder() -> ConfigBuilder {
         ConfigBuilder::new()
     }
+
+    /// Returns the timezone of the emitted timestamps, see [`ConfigBuilder::timezone`].
+    /// With a custom [`TimestampProvider`], this is the timezone of its current time.
+    ///
+    /// # Examples
+    /// ```rust
+    /// # use ocptv::output::*;
+    /// let config = Config::builder().timezone(chrono_tz::Europe::Rome).build();
+    /// assert_eq!(config.timezone(), chrono_tz::Europe::Rome);
+    /// ```
+    pub fn timezone(&self) -> chrono_tz::Tz {
+        self.timestamp_provider.now().timezone()
+    }
+
+    /// Turns this config into a [`SharedConfig`], so that several test runs can use
+    /// the same output, timestamp provider and options, eg. in matrix testing.
+    ///
+    /// # Examples
+    /// ```rust
+    /// # use ocptv::output::*;
+    /// let shared = Config::builder().build().shared();
+    /// let first = TestRun::builder("first", "1.0").config(shared.config()).build();
+    /// let second = TestRun::builder("second", "1.0").config(shared.config()).build();
+    /// ```
+    pub fn shared(self) -> SharedConfig {
+        SharedConfig {
+            inner: Arc::new(self),
+        }
+    }
+}
+
+/// A [`Config`] that can be used by several test runs, see [`Config::shared`].
+///
+/// Each run gets its own sequence numbers, but all of them write into the same output,
+/// so the artifacts of concurrent runs are interleaved.
+#[derive(Clone)]
+pub struct SharedConfig {
+    inner: Arc<Config>,
+}
+
+impl SharedConfig {
+    /// Returns a [`Config`] for a single test run, backed by this shared config.
+    pub fn config(&self) -> Config {
+        let writer = match &self.inner.writer {
+            // artifacts are recorded before serialization, so this can't go through a `Writer`
+            WriterType::Recording(recorder) => WriterType::Recording(recorder.clone()),
+            _ => WriterType::Shared(Arc::clone(&self.inner)),
+        };
+
+        let transform = self.inner.transform.as_ref().map(|_| {
+            let config = Arc::clone(&self.inner);
+            Box::new(move |value: &mut tv::Value| {
+                if let Some(transform) = &config.transform {
+                    transform(value);
+                }
+            }) as Box<Transform>
+        });
+
+        let write_error_policy = match &self.inner.write_error_policy {
+            WriteErrorPolicy::Abort => WriteErrorPolicy::Abort,
+            WriteErrorPolicy::Ignore => WriteErrorPolicy::Ignore,
+            WriteErrorPolicy::Fallback(_) => {
+                WriteErrorPolicy::Fallback(Box::new(SharedFallbackWriter {
+                    config: Arc::clone(&self.inner),
+                }))
+            }
+        };
+
+        Config {
+            timestamp_provider: Box::new(SharedTimestampProvider {
+                config: Arc::clone(&self.inner),
+            }),
+            writer,
+            series_id_generator: self.inner.series_id_generator.clone(),
+            transform,
+            emit_summary: self.inner.emit_summary,
+            line_separator: self.inner.line_separator.clone(),
+            auto_step_timing: self.inner.auto_step_timing,
+            emit_timestamps: self.inner.emit_timestamps,
+            timestamp_precision: self.inner.timestamp_precision,
+            write_error_policy,
+            sanitize_control_chars: self.inner.sanitize_control_chars,
+            lazy_start: self.inner.lazy_start,
+            inline_file_max_size: self.inner.inline_file_max_size,
+            sequence_start: self.inner.sequence_start,
+            non_finite_policy: self.inner.non_finite_policy,
+            flush_each: self.inner.flush_each,
+            min_severity: self.inner.min_severity.clone(),
+            symptom_pattern: self.inner.symptom_pattern.clone(),
+            extension_registry: self.inner.extension_registry.clone(),
+            #[cfg(feature = "otel")]
+            otel_tracer: self.inner.otel_tracer.clone(),
+        }
+    }
+}
+
+struct SharedTimestampProvider {
+    config: Arc<Config>,
+}
+
+impl TimestampProvider for SharedTimestampProvider {
+    fn now(&self) -> chrono::DateTime<chrono_tz::Tz> {
+        self.config.timestamp_provider.now()
+    }
+}
+
+// writes into the fallback output of a `SharedConfig`
+struct SharedFallbackWriter {
+    config: Arc<Config>,
+}
+
+#[async_trait]
+impl writer::Writer for SharedFallbackWriter {
+    async fn write(&self, s: &str) -> Result<(), io::Error> {
+        match &self.config.write_error_policy {
+            WriteErrorPolicy::Fallback(fallback) => fallback.write(s).await,
+            _ => Ok(()),
+        }
+    }
 }
 
 /// The builder for the [`Config`] object.
 pub struct ConfigBuilder {
     timestamp_provider: Box<dyn TimestampProvider + Send + Sync + 'static>,
     writer: Option<WriterType>,
+    series_id_generator: Option<Arc<SeriesIdGenerator>>,
+    transform: Option<Box<Transform>>,
+    emit_summary: bool,
+    line_separator: Option<String>,
+    auto_step_timing: bool,
+    emit_timestamps: bool,
+    timestamp_precision: TimestampPrecision,
+    write_error_policy: WriteErrorPolicy,
+    sanitize_control_chars: bool,
+    lazy_start: bool,
+    inline_file_max_size: usize,
+    sequence_start: u64,
+    non_finite_policy: NonFinitePolicy,
+    flush_each: bool,
+    min_severity: tv::LogSeverity,
+    symptom_pattern: Option<regex::Regex>,
+    extension_registry: Option<tv::ExtensionRegistry>,
+    #[cfg(feature = "otel")]
+    otel_tracer: Option<Arc<opentelemetry::global::BoxedTracer>>,
 }
 
 impl ConfigBuilder {
@@ -43,15 +206,44 @@ impl ConfigBuilder {
         Self {
             timestamp_provider: Box::new(ConfiguredTzProvider { tz: chrono_tz::UTC }),
             writer: Some(WriterType::Stdout(StdoutWriter::new())),
+            series_id_generator: None,
+            transform: None,
+            emit_summary: false,
+            line_separator: None,
+            auto_step_timing: false,
+            emit_timestamps: true,
+            timestamp_precision: TimestampPrecision::default(),
+            write_error_policy: WriteErrorPolicy::Abort,
+            sanitize_control_chars: false,
+            lazy_start: false,
+            inline_file_max_size: DEFAULT_INLINE_FILE_MAX_SIZE,
+            sequence_start: 0,
+            non_finite_policy: NonFinitePolicy::default(),
+            flush_each: false,
+            min_severity: tv::LogSeverity::Debug,
+            symptom_pattern: None,
+            extension_registry: None,
+            #[cfg(feature = "otel")]
+            otel_tracer: None,
         }
     }
 
-    /// TODO: docs for all these
+    /// Sets the timezone of the emitted timestamps, UTC by default. Timestamps are
+    /// serialized in ISO8601 format with the timezone offset, eg. `+02:00`, or `Z` for UTC.
+    /// This includes the measurement element timestamps, even if set explicitly in
+    /// another timezone.
+    ///
+    /// # Examples
+    /// ```rust
+    /// # use ocptv::output::*;
+    /// let config = Config::builder().timezone(chrono_tz::Europe::Rome).build();
+    /// ```
     pub fn timezone(mut self, timezone: chrono_tz::Tz) -> Self {
         self.timestamp_provider = Box::new(ConfiguredTzProvider { tz: timezone });
         self
     }
 
+    /// TODO: docs for all these
     pub fn with_timestamp_provider(
         mut self,
         timestamp_provider: Box<dyn TimestampProvider + Send + Sync + 'static>,
@@ -65,6 +257,76 @@ impl ConfigBuilder {
         self
     }
 
+    /// Collects the output with an already constructed [`BufferWriter`], eg. one
+    /// that was subscribed to with [`BufferWriter::subscribe`].
+    ///
+    /// # Examples
+    /// ```rust
+    /// # use std::sync::Arc;
+    /// # use tokio::sync::Mutex;
+    /// # use ocptv::output::*;
+    /// let writer = BufferWriter::new(Arc::new(Mutex::new(vec![])));
+    /// let config = Config::builder().with_buffer_writer(writer).build();
+    /// ```
+    pub fn with_buffer_writer(mut self, writer: BufferWriter) -> Self {
+        self.writer = Some(WriterType::Buffer(writer));
+        self
+    }
+
+    /// Records the artifacts in memory as typed values, before they are serialized,
+    /// instead of writing any output. See [`RecordingWriter`] for details.
+    ///
+    /// # Examples
+    /// ```rust
+    /// # use ocptv::output::*;
+    /// let recorder = RecordingWriter::new();
+    /// let config = Config::builder().record_mode(recorder.clone()).build();
+    /// ```
+    pub fn record_mode(mut self, recorder: RecordingWriter) -> Self {
+        self.writer = Some(WriterType::Recording(recorder));
+        self
+    }
+
+    /// Collects the output into `buffer`, keeping at most `max_lines` artifacts.
+    /// See [`BufferWriter::bounded`] for details.
+    ///
+    /// # Examples
+    /// ```rust
+    /// # use std::sync::Arc;
+    /// # use tokio::sync::Mutex;
+    /// # use ocptv::output::*;
+    /// let buffer = Arc::new(Mutex::new(vec![]));
+    /// let config = Config::builder()
+    ///     .with_bounded_buffer_output(buffer, 1000, BufferOverflow::Error)
+    ///     .build();
+    /// ```
+    pub fn with_bounded_buffer_output(
+        mut self,
+        buffer: Arc<Mutex<Vec<String>>>,
+        max_lines: usize,
+        overflow: BufferOverflow,
+    ) -> Self {
+        self.writer = Some(WriterType::Buffer(BufferWriter::bounded(
+            buffer, max_lines, overflow,
+        )));
+        self
+    }
+
+    /// Collects the output as JSONL bytes into `buffer`, see [`BytesBufferWriter`].
+    ///
+    /// # Examples
+    /// ```rust
+    /// # use std::sync::Arc;
+    /// # use tokio::sync::Mutex;
+    /// # use ocptv::output::*;
+    /// let buffer = Arc::new(Mutex::new(vec![]));
+    /// let config = Config::builder().with_bytes_buffer_output(buffer).build();
+    /// ```
+    pub fn with_bytes_buffer_output(mut self, buffer: Arc<Mutex<Vec<u8>>>) -> Self {
+        self.writer = Some(WriterType::BytesBuffer(BytesBufferWriter::new(buffer)));
+        self
+    }
+
     pub async fn with_file_output<P: AsRef<Path>>(
         mut self,
         path: P,
@@ -73,6 +335,72 @@ impl ConfigBuilder {
         Ok(self)
     }
 
+    /// Writes the output to the file at `path`, along with an index file at `path.idx`
+    /// mapping each sequence number to the byte offset of its artifact, see
+    /// [`FileWriter::new_indexed`].
+    ///
+    /// # Examples
+    /// ```rust,no_run
+    /// # tokio_test::block_on(async {
+    /// # use ocptv::output::*;
+    /// let path = std::env::temp_dir().join("ocptv_indexed_config_example.jsonl");
+    /// let config = Config::builder()
+    ///     .with_indexed_file_output(path)
+    ///     .await?
+    ///     .build();
+    ///
+    /// # Ok::<(), OcptvError>(())
+    /// # });
+    /// ```
+    pub async fn with_indexed_file_output<P: AsRef<Path>>(
+        mut self,
+        path: P,
+    ) -> Result<Self, tv::OcptvError> {
+        self.writer = Some(WriterType::File(FileWriter::new_indexed(path).await?));
+        Ok(self)
+    }
+
+    /// Sends the output to syslog, see [`writer::SyslogWriter`].
+    ///
+    /// Requires the `syslog` feature.
+    ///
+    /// # Examples
+    /// ```rust,no_run
+    /// # use ocptv::output::*;
+    /// let writer = SyslogWriter::local(SyslogFacility::LOG_DAEMON, "diagnostic_name")?;
+    /// let config = Config::builder().with_syslog_output(writer).build();
+    ///
+    /// # Ok::<(), OcptvError>(())
+    /// ```
+    #[cfg(feature = "syslog")]
+    pub fn with_syslog_output(self, writer: writer::SyslogWriter) -> Self {
+        self.with_custom_output(Box::new(writer))
+    }
+
+    /// Opens an OpenTelemetry span with `tracer` for each test step, from its
+    /// `testStepStart` to its `testStepEnd` artifact. The spans carry the step id and
+    /// name, see [`crate::output::STEP_ID_ATTRIBUTE`], and the status the step ended with.
+    ///
+    /// Requires the `otel` feature.
+    ///
+    /// # Examples
+    /// ```rust
+    /// # use ocptv::output::*;
+    /// let tracer = opentelemetry::global::tracer("diagnostic_name");
+    /// let config = Config::builder().with_otel_tracer(tracer).build();
+    /// ```
+    #[cfg(feature = "otel")]
+    pub fn with_otel_tracer<T>(mut self, tracer: T) -> Self
+    where
+        T: opentelemetry::trace::Tracer + Send + Sync + 'static,
+        T::Span: Send + Sync + 'static,
+    {
+        self.otel_tracer = Some(Arc::new(opentelemetry::global::BoxedTracer::new(Box::new(
+            tracer,
+        ))));
+        self
+    }
+
     pub fn with_custom_output(
         mut self,
         custom: Box<dyn writer::Writer + Send + Sync + 'static>,
@@ -81,12 +409,397 @@ impl ConfigBuilder {
         self
     }
 
+    /// Routes the output to an arbitrary [`tokio::io::AsyncWrite`] sink, one artifact per line.
+    /// See [`AsyncWriteWriter`] for details.
+    ///
+    /// # Examples
+    /// ```rust
+    /// # use ocptv::output::*;
+    /// let config = Config::builder()
+    ///     .with_async_write(tokio::io::sink())
+    ///     .build();
+    /// ```
+    pub fn with_async_write<W: AsyncWrite + Unpin + Send + 'static>(mut self, sink: W) -> Self {
+        self.writer = Some(WriterType::Custom(Box::new(AsyncWriteWriter::new(sink))));
+        self
+    }
+
+    /// Sets the generator used for automatic measurement series ids.
+    /// The generator receives the series counter and returns the id to emit; series
+    /// built with an explicit [`tv::Ident::Exact`] id are not affected.
+    ///
+    /// # Examples
+    /// ```rust
+    /// # use ocptv::output::*;
+    /// let config = Config::builder()
+    ///     .series_id_generator(Box::new(|n| format!("fan_series_{}", n)))
+    ///     .build();
+    /// ```
+    pub fn series_id_generator(mut self, generator: Box<SeriesIdGenerator>) -> Self {
+        self.series_id_generator = Some(Arc::from(generator));
+        self
+    }
+
+    /// Sets a transform applied to every serialized artifact just before it is written.
+    /// The transform may rewrite the json value in place, eg. to redact serial numbers;
+    /// replacing the value with `null` drops the artifact from the output entirely.
+    ///
+    /// # Examples
+    /// ```rust
+    /// # use ocptv::output::*;
+    /// let config = Config::builder()
+    ///     .with_transform(Box::new(|value| {
+    ///         if let Some(serial) = value.pointer_mut("/testRunArtifact/testRunStart/dutInfo/metadata/serial") {
+    ///             *serial = Value::from("<redacted>");
+    ///         }
+    ///     }))
+    ///     .build();
+    /// ```
+    pub fn with_transform(mut self, transform: Box<Transform>) -> Self {
+        self.transform = Some(transform);
+        self
+    }
+
+    /// When enabled, the run tallies the diagnoses and errors it emits and reports them
+    /// in an INFO log just before `testRunEnd`. The log message is a json object like
+    /// `{"diagnoses":{"pass":2,"fail":0,"unknown":0},"errors":1}`. The summary log is
+    /// emitted even when [`ConfigBuilder::min_severity`] is above INFO.
+    ///
+    /// # Examples
+    /// ```rust
+    /// # use ocptv::output::*;
+    /// let config = Config::builder().emit_summary(true).build();
+    /// ```
+    pub fn emit_summary(mut self, value: bool) -> Self {
+        self.emit_summary = value;
+        self
+    }
+
+    /// Starts the artifact sequence numbers at `start` instead of 0, eg. to give each
+    /// process a separate range of sequence numbers when merging the output of several
+    /// processes. The schema version artifact gets `start`.
+    ///
+    /// Nothing checks that the ranges don't overlap: if a process emits more artifacts
+    /// than the gap to the next process's start, the merged output has duplicate
+    /// sequence numbers. Leave enough room between the starts.
+    ///
+    /// # Examples
+    /// ```rust
+    /// # use ocptv::output::*;
+    /// // second worker process
+    /// let config = Config::builder().sequence_start(1_000_000).build();
+    /// ```
+    pub fn sequence_start(mut self, start: u64) -> Self {
+        self.sequence_start = start;
+        self
+    }
+
+    /// Sets the maximum size, in bytes before encoding, of the files inlined with
+    /// [`tv::StartedTestStep::add_inline_file`]. Larger files are rejected with an error.
+    /// Defaults to 64 KiB.
+    ///
+    /// # Examples
+    /// ```rust
+    /// # use ocptv::output::*;
+    /// let config = Config::builder().inline_file_max_size(4096).build();
+    /// ```
+    pub fn inline_file_max_size(mut self, value: usize) -> Self {
+        self.inline_file_max_size = value;
+        self
+    }
+
+    /// When enabled, steps run through [`tv::TestStep::scope`] emit a `step_duration`
+    /// measurement, in seconds, just before they end. The duration is measured with the
+    /// configured [`TimestampProvider`], same as the artifact timestamps.
+    ///
+    /// # Examples
+    /// ```rust
+    /// # use ocptv::output::*;
+    /// let config = Config::builder().auto_step_timing(true).build();
+    /// ```
+    pub fn auto_step_timing(mut self, value: bool) -> Self {
+        self.auto_step_timing = value;
+        self
+    }
+
+    /// When disabled, the `timestamp` field is left out of every artifact, including the
+    /// measurement series elements. Enabled by default, as the spec requires timestamps.
+    ///
+    /// # Examples
+    /// ```rust
+    /// # use ocptv::output::*;
+    /// let config = Config::builder().emit_timestamps(false).build();
+    /// ```
+    pub fn emit_timestamps(mut self, value: bool) -> Self {
+        self.emit_timestamps = value;
+        self
+    }
+
+    /// When enabled, [`tv::TestRun::start`] holds back the schema version and the
+    /// `testRunStart` artifacts until the first other artifact of the run is emitted,
+    /// so they are also timestamped at that point.
+    /// A run that ends without emitting anything else produces no output at all,
+    /// not even the `testRunEnd` artifact.
+    ///
+    /// # Examples
+    /// ```rust
+    /// # use ocptv::output::*;
+    /// let config = Config::builder().lazy_start(true).build();
+    /// ```
+    pub fn lazy_start(mut self, value: bool) -> Self {
+        self.lazy_start = value;
+        self
+    }
+
+    /// When enabled, raw control characters are stripped from all the string fields of
+    /// the artifacts before they are serialized, eg. a stray bell character in a log
+    /// message. Tabs, line feeds and carriage returns are kept.
+    ///
+    /// # Examples
+    /// ```rust
+    /// # use ocptv::output::*;
+    /// let config = Config::builder().sanitize_control_chars(true).build();
+    /// ```
+    pub fn sanitize_control_chars(mut self, value: bool) -> Self {
+        self.sanitize_control_chars = value;
+        self
+    }
+
+    /// When enabled, the output is flushed after every artifact, see [`writer::Writer::flush`],
+    /// so that the artifacts emitted before a crash are not lost in a buffer. This trades
+    /// some throughput for durability. Otherwise, the output is only flushed when the run ends.
+    ///
+    /// # Examples
+    /// ```rust
+    /// # use ocptv::output::*;
+    /// let config = Config::builder().flush_each(true).build();
+    /// ```
+    pub fn flush_each(mut self, value: bool) -> Self {
+        self.flush_each = value;
+        self
+    }
+
+    /// Drops the run and step logs less severe than `severity`, eg. to suppress debug
+    /// logs in production without changing the diagnostic. Other artifacts, including
+    /// errors, are always emitted. Dropped logs don't take a sequence number, so the
+    /// output has no gaps. Defaults to [`tv::LogSeverity::Debug`], keeping all the logs.
+    ///
+    /// # Examples
+    /// ```rust
+    /// # use ocptv::output::*;
+    /// let config = Config::builder().min_severity(LogSeverity::Info).build();
+    /// ```
+    pub fn min_severity(mut self, severity: tv::LogSeverity) -> Self {
+        self.min_severity = severity;
+        self
+    }
+
+    /// Requires the symptoms of the emitted errors and the verdicts of the emitted
+    /// diagnoses to match `pattern`, eg. to enforce a naming convention. Emitting one
+    /// that doesn't match fails with an [`tv::OcptvError::IoError`] of kind
+    /// [`std::io::ErrorKind::InvalidInput`], and nothing is written.
+    ///
+    /// The pattern matches anywhere in the symptom unless anchored, see the example.
+    ///
+    /// # Examples
+    /// ```rust
+    /// # use ocptv::output::*;
+    /// // snake_case identifiers, eg. `fan_stuck`
+    /// let pattern = regex::Regex::new(r"^[a-z][a-z0-9]*(_[a-z0-9]+)*$").unwrap();
+    /// let config = Config::builder().symptom_pattern(pattern).build();
+    /// ```
+    pub fn symptom_pattern(mut self, pattern: regex::Regex) -> Self {
+        self.symptom_pattern = Some(pattern);
+        self
+    }
+
+    /// Enables strict mode for the step extensions: emitting an extension whose content
+    /// `@type` isn't in `registry`, or that has no `@type`, fails with an
+    /// [`tv::OcptvError::IoError`] of kind [`std::io::ErrorKind::InvalidInput`], and
+    /// nothing is written. See [`tv::ExtensionRegistry`].
+    ///
+    /// # Examples
+    /// ```rust
+    /// # use ocptv::output::*;
+    /// let registry = ExtensionRegistry::new().register("FanCurve/v2");
+    /// let config = Config::builder().strict_extensions(registry).build();
+    /// ```
+    pub fn strict_extensions(mut self, registry: tv::ExtensionRegistry) -> Self {
+        self.extension_registry = Some(registry);
+        self
+    }
+
+    /// Sets the number of fractional second digits in the emitted timestamps.
+    /// Defaults to [`TimestampPrecision::Millis`], eg. `1970-01-01T00:00:00.000Z`.
+    ///
+    /// # Examples
+    /// ```rust
+    /// # use ocptv::output::*;
+    /// let config = Config::builder()
+    ///     .timestamp_precision(TimestampPrecision::Micros)
+    ///     .build();
+    /// ```
+    pub fn timestamp_precision(mut self, precision: TimestampPrecision) -> Self {
+        self.timestamp_precision = precision;
+        self
+    }
+
+    /// Sets what happens when the output fails to write an artifact in the middle of a run.
+    /// Defaults to [`WriteErrorPolicy::Abort`].
+    ///
+    /// # Examples
+    /// ```rust
+    /// # use ocptv::output::*;
+    /// let config = Config::builder()
+    ///     .on_write_error(WriteErrorPolicy::Ignore)
+    ///     .build();
+    /// ```
+    pub fn on_write_error(mut self, policy: WriteErrorPolicy) -> Self {
+        self.write_error_policy = policy;
+        self
+    }
+
+    /// Sets how NaN and infinite float measurement values are serialized, since json
+    /// has no representation for them. Defaults to [`NonFinitePolicy::Null`].
+    ///
+    /// Applies to the measurement and measurement series element values given as `f32` or
+    /// `f64`, see [`tv::MeasurementValue`]. Floats already converted to a [`tv::Value`]
+    /// are `null` at that point.
+    ///
+    /// # Examples
+    /// ```rust
+    /// # use ocptv::output::*;
+    /// let config = Config::builder()
+    ///     .non_finite_policy(NonFinitePolicy::Sentinel)
+    ///     .build();
+    /// ```
+    pub fn non_finite_policy(mut self, policy: NonFinitePolicy) -> Self {
+        self.non_finite_policy = policy;
+        self
+    }
+
+    /// Sets the terminator written after each artifact by the stdout, file and bytes buffer outputs,
+    /// eg. `"\r\n"` for consumers expecting CRLF records. Defaults to `"\n"`.
+    ///
+    /// Only the default stdout output and the ones set by [`ConfigBuilder::with_file_output`],
+    /// [`ConfigBuilder::with_indexed_file_output`] and
+    /// [`ConfigBuilder::with_bytes_buffer_output`] use it. The buffer outputs store the
+    /// artifacts without a terminator, and any other [`writer::Writer`], eg. the ones set by
+    /// [`ConfigBuilder::with_custom_output`] and [`ConfigBuilder::with_async_write`] or a
+    /// [`WriteErrorPolicy::Fallback`], terminates them as it does by default.
+    ///
+    /// Returns an error if `separator` is empty, since the artifacts could no longer be told apart.
+    ///
+    /// # Examples
+    /// ```rust
+    /// # use ocptv::output::*;
+    /// let config = Config::builder().line_separator("\r\n")?.build();
+    ///
+    /// # Ok::<(), OcptvError>(())
+    /// ```
+    pub fn line_separator(mut self, separator: &str) -> Result<Self, tv::OcptvError> {
+        if separator.is_empty() {
+            return Err(tv::OcptvError::Other(
+                "line separator must not be empty".into(),
+            ));
+        }
+
+        self.line_separator = Some(separator.to_owned());
+        Ok(self)
+    }
+
     pub fn build(self) -> Config {
         Config {
             timestamp_provider: self.timestamp_provider,
             writer: self
                 .writer
                 .unwrap_or(WriterType::Stdout(StdoutWriter::new())),
+            series_id_generator: self.series_id_generator,
+            transform: self.transform,
+            emit_summary: self.emit_summary,
+            line_separator: self.line_separator,
+            auto_step_timing: self.auto_step_timing,
+            emit_timestamps: self.emit_timestamps,
+            timestamp_precision: self.timestamp_precision,
+            write_error_policy: self.write_error_policy,
+            sanitize_control_chars: self.sanitize_control_chars,
+            lazy_start: self.lazy_start,
+            inline_file_max_size: self.inline_file_max_size,
+            sequence_start: self.sequence_start,
+            non_finite_policy: self.non_finite_policy,
+            flush_each: self.flush_each,
+            min_severity: self.min_severity,
+            symptom_pattern: self.symptom_pattern,
+            extension_registry: self.extension_registry,
+            #[cfg(feature = "otel")]
+            otel_tracer: self.otel_tracer,
+        }
+    }
+}
+
+/// Default for [`ConfigBuilder::inline_file_max_size`].
+const DEFAULT_INLINE_FILE_MAX_SIZE: usize = 64 * 1024;
+
+/// Function producing a measurement series id from the series counter.
+pub type SeriesIdGenerator = dyn Fn(u64) -> String + Send + Sync + 'static;
+
+/// Function applied to each serialized artifact before it is written.
+pub type Transform = dyn Fn(&mut tv::Value) + Send + Sync + 'static;
+
+/// Behavior when the output fails to write an artifact, see [`ConfigBuilder::on_write_error`].
+#[non_exhaustive]
+pub enum WriteErrorPolicy {
+    /// Return the error to the emitting call, leaving the output stream partial.
+    Abort,
+    /// Carry on without the artifact. The dropped artifacts are counted in
+    /// [`tv::RunStats::dropped`].
+    Ignore,
+    /// Write the artifact to this writer instead. Each artifact tries the main output
+    /// first, and an error from the fallback is returned to the emitting call.
+    Fallback(Box<dyn writer::Writer + Send + Sync + 'static>),
+}
+
+/// Serialization of NaN and infinite measurement values, see [`ConfigBuilder::non_finite_policy`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub enum NonFinitePolicy {
+    /// Serialize as `null`.
+    #[default]
+    Null,
+    /// Serialize as the strings `"NaN"`, `"Infinity"` and `"-Infinity"`.
+    Sentinel,
+}
+
+impl NonFinitePolicy {
+    pub(crate) fn to_value(self, value: f64) -> tv::Value {
+        match self {
+            NonFinitePolicy::Null => tv::Value::Null,
+            NonFinitePolicy::Sentinel if value.is_nan() => "NaN".into(),
+            NonFinitePolicy::Sentinel if value > 0.0 => "Infinity".into(),
+            NonFinitePolicy::Sentinel => "-Infinity".into(),
+        }
+    }
+}
+
+/// Fractional second precision of the emitted timestamps.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub enum TimestampPrecision {
+    /// 3 digits, eg. `1970-01-01T00:00:00.000Z`.
+    #[default]
+    Millis,
+    /// 6 digits, eg. `1970-01-01T00:00:00.000000Z`.
+    Micros,
+    /// 9 digits, eg. `1970-01-01T00:00:00.000000000Z`.
+    Nanos,
+}
+
+impl TimestampPrecision {
+    pub(crate) fn seconds_format(self) -> chrono::SecondsFormat {
+        match self {
+            TimestampPrecision::Millis => chrono::SecondsFormat::Millis,
+            TimestampPrecision::Micros => chrono::SecondsFormat::Micros,
+            TimestampPrecision::Nanos => chrono::SecondsFormat::Nanos,
         }
     }
 }
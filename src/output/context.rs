@@ -0,0 +1,82 @@
+// (c) Meta Platforms, Inc. and affiliates.
+//
+// Use of this source code is governed by an MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+//! Ambient step context.
+//!
+//! Instead of passing the step handle through deep call stacks, the code running inside
+//! [`with_current_step`] can emit artifacts against that step with the free functions
+//! in this module.
+//!
+//! The current step is a tokio task-local, so it is not inherited by tasks spawned from
+//! inside the scope; pass the step explicitly in that case.
+
+use std::future::Future;
+use std::sync::Arc;
+
+use crate::output as tv;
+use crate::spec;
+
+tokio::task_local! {
+    static CURRENT_STEP: Arc<tv::StartedTestStep>;
+}
+
+/// Runs `fut` with `step` as the current step for the free functions in this module.
+/// Scopes may be nested, the innermost step is the current one.
+///
+/// # Examples
+///
+/// ```rust
+/// # tokio_test::block_on(async {
+/// # use ocptv::output::*;
+/// let dut = DutInfo::new("my_dut");
+/// let run = TestRun::new("diagnostic_name", "1.0").start(dut).await?;
+/// let step = run.add_step("step_name").start().await?;
+///
+/// async fn check_fans() -> Result<(), OcptvError> {
+///     context::log_info("checking fans").await?;
+///     context::add_measurement("fan_speed", 1600).await
+/// }
+///
+/// context::with_current_step(&step, check_fans()).await?;
+/// step.end(TestStatus::Complete).await?;
+///
+/// # Ok::<(), OcptvError>(())
+/// # });
+/// ```
+pub async fn with_current_step<F: Future>(step: &tv::StartedTestStep, fut: F) -> F::Output {
+    CURRENT_STEP.scope(Arc::new(step.clone_handle()), fut).await
+}
+
+/// Emits a Log message against the current step.
+/// Returns an error if called outside of [`with_current_step`].
+///
+/// See [`tv::StartedTestStep::add_log`].
+pub async fn log(severity: spec::LogSeverity, msg: &str) -> Result<(), tv::OcptvError> {
+    current_step()?.add_log(severity, msg).await
+}
+
+/// Emits a Log message with INFO severity against the current step.
+/// Returns an error if called outside of [`with_current_step`].
+pub async fn log_info(msg: &str) -> Result<(), tv::OcptvError> {
+    log(spec::LogSeverity::Info, msg).await
+}
+
+/// Emits a Measurement against the current step.
+/// Returns an error if called outside of [`with_current_step`].
+///
+/// See [`tv::StartedTestStep::add_measurement`].
+pub async fn add_measurement<V: Into<tv::MeasurementValue>>(
+    name: &str,
+    value: V,
+) -> Result<(), tv::OcptvError> {
+    current_step()?.add_measurement(name, value).await
+}
+
+fn current_step() -> Result<Arc<tv::StartedTestStep>, tv::OcptvError> {
+    CURRENT_STEP
+        .try_with(Arc::clone)
+        .map_err(|_| tv::OcptvError::Other("no current step in this task".into()))
+}
@@ -8,6 +8,9 @@ use crate::output as tv;
 use crate::spec;
 use tv::dut;
 
+/// Message prefix of the diagnoses built with [`Diagnosis::skip`].
+const SKIP_MESSAGE_PREFIX: &str = "skipped: ";
+
 /// This structure represents a Diagnosis message.
 ///
 /// ref: <https://github.com/opencomputeproject/ocp-diag-core/tree/main/json_spec#diagnosis>
@@ -38,7 +41,7 @@ use tv::dut;
 ///     .source("file.rs", 1)
 ///     .build();
 /// ```
-#[derive(Default)]
+#[derive(Debug, Clone, Default)]
 pub struct Diagnosis {
     verdict: String,
     diagnosis_type: spec::DiagnosisType,
@@ -66,6 +69,25 @@ impl Diagnosis {
         }
     }
 
+    /// Builds a Diagnosis object for a check that was skipped, so skips are represented
+    /// uniformly. The spec has no neutral diagnosis type for this, so by convention the
+    /// type is `UNKNOWN` and the message is the reason prefixed by `skipped: `.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ocptv::output::*;
+    /// let diagnosis = Diagnosis::skip("ecc-check", "no ECC capable DIMMs");
+    /// ```
+    pub fn skip(verdict: &str, reason: &str) -> Self {
+        Diagnosis {
+            verdict: verdict.to_owned(),
+            diagnosis_type: spec::DiagnosisType::Unknown,
+            message: Some(format!("{}{}", SKIP_MESSAGE_PREFIX, reason)),
+            ..Default::default()
+        }
+    }
+
     /// Builds a new Diagnosis object using [`DiagnosisBuilder`].
     ///
     /// # Examples
@@ -111,6 +133,18 @@ impl Diagnosis {
             source_location: self.source_location.clone(),
         }
     }
+
+    // rebuilds the diagnosis from an emitted artifact, see `tv::RecordingWriter`
+    pub(crate) fn from_spec(diagnosis: spec::Diagnosis) -> Self {
+        Diagnosis {
+            verdict: diagnosis.verdict,
+            diagnosis_type: diagnosis.diagnosis_type,
+            message: diagnosis.message,
+            hardware_info: diagnosis.hardware_info.map(dut::DutHardwareInfo::from_spec),
+            subcomponent: diagnosis.subcomponent.map(dut::Subcomponent::from_spec),
+            source_location: diagnosis.source_location,
+        }
+    }
 }
 
 /// This structure builds a [`Diagnosis`] object.
@@ -264,6 +298,25 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_diagnosis_skip() -> Result<()> {
+        let artifact = Diagnosis::skip("verdict", "reason").to_artifact();
+
+        assert_eq!(
+            artifact,
+            spec::Diagnosis {
+                verdict: "verdict".to_owned(),
+                diagnosis_type: spec::DiagnosisType::Unknown,
+                message: Some("skipped: reason".to_owned()),
+                hardware_info: None,
+                subcomponent: None,
+                source_location: None,
+            }
+        );
+
+        Ok(())
+    }
+
     #[test]
     fn test_diagnosis_builder_as_test_step_descendant_to_artifact() -> Result<()> {
         let mut dut = DutInfo::new("dut0");
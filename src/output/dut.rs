@@ -4,7 +4,9 @@
 // license that can be found in the LICENSE file or at
 // https://opensource.org/licenses/MIT.
 
-use std::collections::BTreeMap;
+use std::fmt;
+
+use serde_json::Map;
 
 use crate::output as tv;
 use crate::output::trait_ext::{MapExt, VecExt};
@@ -28,7 +30,15 @@ pub struct DutInfo {
     software_infos: Vec<DutSoftwareInfo>,
     hardware_infos: Vec<DutHardwareInfo>,
 
-    metadata: BTreeMap<String, tv::Value>,
+    // when set, an empty infos list is emitted as `[]` instead of being omitted
+    emit_empty_software_infos: bool,
+    emit_empty_hardware_infos: bool,
+
+    // declared computer systems, used to validate the `computer_system` references
+    computer_systems: Vec<String>,
+    strict_computer_systems: bool,
+
+    metadata: Map<String, tv::Value>,
 }
 
 impl DutInfo {
@@ -62,6 +72,20 @@ impl DutInfo {
         info
     }
 
+    /// Adds a [`PlatformInfo`] to an already built [`DutInfo`], eg. for platform details
+    /// probed after the DUT was described but before the test run is started.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use ocptv::output::*;
+    /// let mut dut = DutInfo::new("dut0");
+    /// dut.add_platform_info(PlatformInfo::new("bios_version=1.2.3"));
+    /// ```
+    pub fn add_platform_info(&mut self, info: PlatformInfo) {
+        self.platform_infos.push(info);
+    }
+
     pub fn software_info(&self, id: &str) -> Option<&DutSoftwareInfo> {
         self.software_infos.iter().find(|si| si.id == id)
     }
@@ -70,13 +94,154 @@ impl DutInfo {
         self.hardware_infos.iter().find(|si| si.id == id)
     }
 
+    /// Reconstructs a [`DutInfo`] from its json representation, as emitted in the
+    /// `dutInfo` field of the `testRunStart` artifact. This allows starting a run
+    /// with a DUT description captured earlier, producing the same `dutInfo` output.
+    ///
+    /// Returns [`tv::OcptvError::Format`] if the json doesn't match the `dutInfo` spec
+    /// object, eg. when a required field is missing or has the wrong type.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use ocptv::output::*;
+    /// let json = serde_json::json!({
+    ///     "dutInfoId": "dut0",
+    ///     "hardwareInfos": [{ "hardwareInfoId": "hw0", "name": "fan" }]
+    /// });
+    /// let dut = DutInfo::from_json(json)?;
+    /// assert!(dut.hardware_info("hw0").is_some());
+    ///
+    /// # Ok::<(), OcptvError>(())
+    /// ```
+    pub fn from_json(value: tv::Value) -> Result<DutInfo, tv::OcptvError> {
+        let info = serde_json::from_value::<spec::DutInfo>(value)?;
+        Ok(DutInfo::from_spec(info))
+    }
+
+    fn from_spec(info: spec::DutInfo) -> DutInfo {
+        let software_infos = info.software_infos.as_ref().map(|infos| {
+            infos
+                .iter()
+                .map(|si| DutSoftwareInfo {
+                    id: si.id.clone(),
+                    source: SoftwareInfo {
+                        id: Ident::Exact(si.id.clone()),
+                        name: si.name.clone(),
+                        version: si.version.clone(),
+                        revision: si.revision.clone(),
+                        software_type: si.software_type.clone(),
+                        computer_system: si.computer_system.clone(),
+                    },
+                })
+                .collect::<Vec<_>>()
+        });
+        let hardware_infos = info.hardware_infos.as_ref().map(|infos| {
+            infos
+                .iter()
+                .map(|hi| DutHardwareInfo {
+                    id: hi.id.clone(),
+                    source: HardwareInfo {
+                        id: Ident::Exact(hi.id.clone()),
+                        name: hi.name.clone(),
+                        version: hi.version.clone(),
+                        revision: hi.revision.clone(),
+                        location: hi.location.clone(),
+                        serial_no: hi.serial_no.clone(),
+                        part_no: hi.part_no.clone(),
+                        manufacturer: hi.manufacturer.clone(),
+                        manufacturer_part_no: hi.manufacturer_part_no.clone(),
+                        odata_id: hi.odata_id.clone(),
+                        computer_system: hi.computer_system.clone(),
+                        manager: hi.manager.clone(),
+                    },
+                })
+                .collect::<Vec<_>>()
+        });
+
+        DutInfo {
+            id: info.id,
+            name: info.name,
+            platform_infos: info
+                .platform_infos
+                .unwrap_or_default()
+                .into_iter()
+                .map(|pi| PlatformInfo { info: pi.info })
+                .collect(),
+            // keep explicitly empty lists, so the dut is emitted exactly as it was loaded
+            emit_empty_software_infos: software_infos.as_ref().is_some_and(Vec::is_empty),
+            emit_empty_hardware_infos: hardware_infos.as_ref().is_some_and(Vec::is_empty),
+            software_infos: software_infos.unwrap_or_default(),
+            hardware_infos: hardware_infos.unwrap_or_default(),
+            metadata: info.metadata.unwrap_or_default(),
+            ..Default::default()
+        }
+    }
+
+    // Checks the `computer_system` references of the software and hardware infos against
+    // the systems declared with `DutInfoBuilder::add_computer_system`. Returns a message for
+    // each dangling reference, or an error if there are any and strict mode is enabled.
+    // No validation is done when no systems were declared.
+    pub(crate) fn check_computer_systems(&self) -> Result<Vec<String>, tv::OcptvError> {
+        if self.computer_systems.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let software_refs = self.software_infos.iter().filter_map(|si| {
+            si.source
+                .computer_system
+                .as_ref()
+                .map(|cs| ("software", &si.id, cs))
+        });
+        let hardware_refs = self.hardware_infos.iter().filter_map(|hi| {
+            hi.source
+                .computer_system
+                .as_ref()
+                .map(|cs| ("hardware", &hi.id, cs))
+        });
+
+        let dangling = software_refs
+            .chain(hardware_refs)
+            .filter(|(_, _, cs)| !self.computer_systems.contains(cs))
+            .map(|(kind, id, cs)| {
+                format!(
+                    "{} info '{}' references undeclared computer system '{}'",
+                    kind, id, cs
+                )
+            })
+            .collect::<Vec<_>>();
+
+        if self.strict_computer_systems && !dangling.is_empty() {
+            return Err(tv::OcptvError::Other(dangling.join("; ").into()));
+        }
+        Ok(dangling)
+    }
+
     pub(crate) fn to_spec(&self) -> spec::DutInfo {
         spec::DutInfo {
             id: self.id.clone(),
             name: self.name.clone(),
             platform_infos: self.platform_infos.map_option(PlatformInfo::to_spec),
-            software_infos: self.software_infos.map_option(DutSoftwareInfo::to_spec),
-            hardware_infos: self.hardware_infos.map_option(DutHardwareInfo::to_spec),
+            software_infos: if self.emit_empty_software_infos {
+                Some(
+                    self.software_infos
+                        .iter()
+                        .map(DutSoftwareInfo::to_spec)
+                        .collect(),
+                )
+            } else {
+                self.software_infos.map_option(DutSoftwareInfo::to_spec)
+            },
+            hardware_infos: if self.emit_empty_hardware_infos {
+                Some(
+                    self.hardware_infos
+                        .iter()
+                        .map(DutHardwareInfo::to_spec)
+                        .collect(),
+                )
+            } else {
+                self.hardware_infos.map_option(DutHardwareInfo::to_spec)
+            },
             metadata: self.metadata.option(),
         }
     }
@@ -88,7 +253,11 @@ pub struct DutInfoBuilder {
     id: String,
     name: Option<String>,
     platform_infos: Vec<PlatformInfo>,
-    metadata: BTreeMap<String, tv::Value>,
+    emit_empty_software_infos: bool,
+    emit_empty_hardware_infos: bool,
+    computer_systems: Vec<String>,
+    strict_computer_systems: bool,
+    metadata: Map<String, tv::Value>,
 }
 
 impl DutInfoBuilder {
@@ -109,6 +278,76 @@ impl DutInfoBuilder {
         self
     }
 
+    /// Emit the `softwareInfos` field as an explicit empty list when no software
+    /// infos are added to the [`DutInfo`], instead of omitting it.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use ocptv::output::*;
+    /// let dut = DutInfo::builder("dut0").with_empty_software_infos().build();
+    /// ```
+    pub fn with_empty_software_infos(mut self) -> Self {
+        self.emit_empty_software_infos = true;
+        self
+    }
+
+    /// Emit the `hardwareInfos` field as an explicit empty list when no hardware
+    /// infos are added to the [`DutInfo`], instead of omitting it.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use ocptv::output::*;
+    /// let dut = DutInfo::builder("dut0").with_empty_hardware_infos().build();
+    /// ```
+    pub fn with_empty_hardware_infos(mut self) -> Self {
+        self.emit_empty_hardware_infos = true;
+        self
+    }
+
+    /// Declares a computer system of the DUT. Once any system is declared, the
+    /// `computer_system` references of the software and hardware infos are checked
+    /// against the declared ones when the test run starts, and each dangling reference
+    /// emits a run log with `WARNING` severity.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use ocptv::output::*;
+    /// let mut dut = DutInfo::builder("dut0")
+    ///     .add_computer_system("host0")
+    ///     .build();
+    /// dut.add_software_info(SoftwareInfo::builder("bmc").computer_system("host0").build());
+    /// ```
+    pub fn add_computer_system(mut self, name: &str) -> Self {
+        self.computer_systems.push(name.to_string());
+        self
+    }
+
+    /// Makes dangling `computer_system` references fail the test run start with
+    /// an error, instead of emitting warnings. See [`DutInfoBuilder::add_computer_system`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # tokio_test::block_on(async {
+    /// # use ocptv::output::*;
+    /// let mut dut = DutInfo::builder("dut0")
+    ///     .add_computer_system("host0")
+    ///     .strict_computer_systems()
+    ///     .build();
+    /// dut.add_software_info(SoftwareInfo::builder("bmc").computer_system("host1").build());
+    ///
+    /// let run = TestRun::new("diagnostic_name", "1.0");
+    /// assert!(run.start(dut).await.is_err());
+    /// # });
+    /// ```
+    pub fn strict_computer_systems(mut self) -> Self {
+        self.strict_computer_systems = true;
+        self
+    }
+
     pub fn add_metadata<V: Into<tv::Value>>(mut self, key: &str, value: V) -> Self {
         self.metadata.insert(key.to_string(), value.into());
         self
@@ -119,12 +358,67 @@ impl DutInfoBuilder {
             id: self.id,
             name: self.name,
             platform_infos: self.platform_infos,
+            emit_empty_software_infos: self.emit_empty_software_infos,
+            emit_empty_hardware_infos: self.emit_empty_hardware_infos,
+            computer_systems: self.computer_systems,
+            strict_computer_systems: self.strict_computer_systems,
             metadata: self.metadata,
             ..Default::default()
         }
     }
 }
 
+/// Typed location of a hardware component or subcomponent, rendered to the conventional
+/// string for its scheme. Used with [`SubcomponentBuilder::location_typed`] and
+/// [`HardwareInfoBuilder::location_typed`]; for other schemes, use the string setters.
+///
+/// # Examples
+///
+/// ```rust
+/// # use ocptv::output::*;
+/// assert_eq!(Location::pci(0, 0x3b, 0, 1).to_string(), "0000:3b:00.1");
+/// assert_eq!(Location::dimm_slot(0, 'A', 1).to_string(), "CPU0/DIMM_A1");
+/// assert_eq!(Location::cpu_core(0, 3).to_string(), "CPU0/Core3");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Location(String);
+
+impl Location {
+    /// PCI device address, in the `domain:bus:device.function` format, eg. `0000:3b:00.1`.
+    pub fn pci(domain: u16, bus: u8, device: u8, function: u8) -> Self {
+        Location(format!(
+            "{:04x}:{:02x}:{:02x}.{:x}",
+            domain, bus, device, function
+        ))
+    }
+
+    /// Memory slot on the given CPU socket, eg. `CPU0/DIMM_A1` for channel `A`, slot 1.
+    pub fn dimm_slot(socket: u32, channel: char, slot: u32) -> Self {
+        Location(format!(
+            "CPU{}/DIMM_{}{}",
+            socket,
+            channel.to_ascii_uppercase(),
+            slot
+        ))
+    }
+
+    /// CPU socket, eg. `CPU0`.
+    pub fn cpu(socket: u32) -> Self {
+        Location(format!("CPU{}", socket))
+    }
+
+    /// Core of the CPU on the given socket, eg. `CPU0/Core3`.
+    pub fn cpu_core(socket: u32, core: u32) -> Self {
+        Location(format!("CPU{}/Core{}", socket, core))
+    }
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
 /// TODO: docs
 #[derive(Debug, Clone)]
 pub struct Subcomponent {
@@ -148,6 +442,16 @@ impl Subcomponent {
             revision: self.revision.clone(),
         }
     }
+
+    pub(crate) fn from_spec(subcomponent: spec::Subcomponent) -> Self {
+        Subcomponent {
+            subcomponent_type: subcomponent.subcomponent_type,
+            name: subcomponent.name,
+            location: subcomponent.location,
+            version: subcomponent.version,
+            revision: subcomponent.revision,
+        }
+    }
 }
 
 /// TODO: docs
@@ -182,6 +486,20 @@ impl SubcomponentBuilder {
         self.location = Some(value.to_string());
         self
     }
+    /// Sets the location from a typed [`Location`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use ocptv::output::*;
+    /// let sub = Subcomponent::builder("core")
+    ///     .location_typed(Location::cpu_core(0, 3))
+    ///     .build();
+    /// ```
+    pub fn location_typed(mut self, value: Location) -> Self {
+        self.location = Some(value.0);
+        self
+    }
     pub fn revision(mut self, value: &str) -> Self {
         self.revision = Some(value.to_string());
         self
@@ -277,6 +595,21 @@ impl DutSoftwareInfo {
             computer_system: src.computer_system.clone(),
         }
     }
+
+    // rebuilds the reference from an emitted artifact, see `tv::RecordingWriter`
+    pub(crate) fn from_spec(info: spec::SoftwareInfo) -> Self {
+        DutSoftwareInfo {
+            source: SoftwareInfo {
+                id: Ident::Exact(info.id.clone()),
+                name: info.name,
+                version: info.version,
+                revision: info.revision,
+                software_type: info.software_type,
+                computer_system: info.computer_system,
+            },
+            id: info.id,
+        }
+    }
 }
 
 impl PartialEq for DutSoftwareInfo {
@@ -393,6 +726,27 @@ impl DutHardwareInfo {
             manager: src.manager.clone(),
         }
     }
+
+    // rebuilds the reference from an emitted artifact, see `tv::RecordingWriter`
+    pub(crate) fn from_spec(info: spec::HardwareInfo) -> Self {
+        DutHardwareInfo {
+            source: HardwareInfo {
+                id: Ident::Exact(info.id.clone()),
+                name: info.name,
+                version: info.version,
+                revision: info.revision,
+                location: info.location,
+                serial_no: info.serial_no,
+                part_no: info.part_no,
+                manufacturer: info.manufacturer,
+                manufacturer_part_no: info.manufacturer_part_no,
+                odata_id: info.odata_id,
+                computer_system: info.computer_system,
+                manager: info.manager,
+            },
+            id: info.id,
+        }
+    }
 }
 
 impl PartialEq for DutHardwareInfo {
@@ -448,6 +802,21 @@ impl HardwareInfoBuilder {
         self
     }
 
+    /// Sets the location from a typed [`Location`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use ocptv::output::*;
+    /// let info = HardwareInfo::builder("nic")
+    ///     .location_typed(Location::pci(0, 0x3b, 0, 0))
+    ///     .build();
+    /// ```
+    pub fn location_typed(mut self, value: Location) -> Self {
+        self.location = Some(value.0);
+        self
+    }
+
     pub fn serial_no(mut self, value: &str) -> Self {
         self.serial_no = Some(value.to_string());
         self
@@ -483,6 +852,28 @@ impl HardwareInfoBuilder {
         self
     }
 
+    /// Sets the Redfish related fields at once, as they typically come together from
+    /// a Redfish query. Same as calling [`HardwareInfoBuilder::odata_id`],
+    /// [`HardwareInfoBuilder::computer_system`] and [`HardwareInfoBuilder::manager`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use ocptv::output::*;
+    /// let info = HardwareInfo::builder("fan")
+    ///     .redfish(
+    ///         "/redfish/v1/Chassis/1/Thermal#/Fans/0",
+    ///         "/redfish/v1/Systems/1",
+    ///         "/redfish/v1/Managers/1",
+    ///     )
+    ///     .build();
+    /// ```
+    pub fn redfish(self, odata_id: &str, computer_system: &str, manager: &str) -> Self {
+        self.odata_id(odata_id)
+            .computer_system(computer_system)
+            .manager(manager)
+    }
+
     pub fn build(self) -> HardwareInfo {
         HardwareInfo {
             id: self.id,
@@ -506,6 +897,8 @@ mod tests {
     use super::*;
     use crate::spec;
     use anyhow::{bail, Result};
+    use assert_json_diff::assert_json_eq;
+    use serde_json::json;
 
     #[test]
     fn test_dut_creation_from_builder_with_defaults() -> Result<()> {
@@ -581,6 +974,118 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_dut_infos_omitted_when_empty() -> Result<()> {
+        let dut = DutInfo::builder("1234").build();
+
+        let actual = serde_json::to_value(dut.to_spec())?;
+        assert_json_eq!(actual, json!({ "dutInfoId": "1234" }));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_dut_infos_with_empty_lists() -> Result<()> {
+        let dut = DutInfo::builder("1234")
+            .with_empty_software_infos()
+            .with_empty_hardware_infos()
+            .build();
+
+        let actual = serde_json::to_value(dut.to_spec())?;
+        assert_json_eq!(
+            actual,
+            json!({
+                "dutInfoId": "1234",
+                "softwareInfos": [],
+                "hardwareInfos": []
+            })
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_dut_infos_with_empty_lists_after_add() -> Result<()> {
+        let mut dut = DutInfo::builder("1234").with_empty_software_infos().build();
+        dut.add_software_info(
+            SoftwareInfo::builder("name")
+                .id(Ident::Exact("software_id".to_owned()))
+                .build(),
+        );
+
+        let actual = serde_json::to_value(dut.to_spec())?;
+        assert_json_eq!(
+            actual,
+            json!({
+                "dutInfoId": "1234",
+                "softwareInfos": [{
+                    "softwareInfoId": "software_id",
+                    "name": "name"
+                }]
+            })
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_dut_computer_systems_consistent() -> Result<()> {
+        let mut dut = DutInfo::builder("1234")
+            .add_computer_system("host0")
+            .strict_computer_systems()
+            .build();
+        dut.add_software_info(SoftwareInfo::builder("os").computer_system("host0").build());
+        dut.add_hardware_info(
+            HardwareInfo::builder("cpu")
+                .computer_system("host0")
+                .build(),
+        );
+
+        assert!(dut.check_computer_systems()?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_dut_computer_systems_dangling() -> Result<()> {
+        let mut dut = DutInfo::builder("1234")
+            .add_computer_system("host0")
+            .build();
+        dut.add_software_info(SoftwareInfo::builder("os").computer_system("host0").build());
+        dut.add_hardware_info(
+            HardwareInfo::builder("cpu")
+                .id(Ident::Exact("cpu0".to_owned()))
+                .computer_system("host1")
+                .build(),
+        );
+
+        assert_eq!(
+            dut.check_computer_systems()?,
+            vec!["hardware info 'cpu0' references undeclared computer system 'host1'"]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_dut_computer_systems_dangling_strict() -> Result<()> {
+        let mut dut = DutInfo::builder("1234")
+            .add_computer_system("host0")
+            .strict_computer_systems()
+            .build();
+        dut.add_software_info(SoftwareInfo::builder("os").computer_system("host1").build());
+
+        assert!(dut.check_computer_systems().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_dut_computer_systems_not_declared() -> Result<()> {
+        let mut dut = DutInfo::builder("1234").strict_computer_systems().build();
+        dut.add_software_info(SoftwareInfo::builder("os").computer_system("host1").build());
+
+        assert!(dut.check_computer_systems()?.is_empty());
+        Ok(())
+    }
+
     #[test]
     fn test_hardware_info() -> Result<()> {
         let mut dut = DutInfo::new("dut0");
@@ -624,6 +1129,27 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_hardware_info_redfish() -> Result<()> {
+        let mut dut = DutInfo::new("dut0");
+        let info = dut.add_hardware_info(
+            HardwareInfo::builder("hardware_name")
+                .redfish("odata_id", "computer_system", "manager")
+                .build(),
+        );
+
+        let spec_hwinfo = info.to_spec();
+
+        assert_eq!(spec_hwinfo.odata_id, Some("odata_id".to_owned()));
+        assert_eq!(
+            spec_hwinfo.computer_system,
+            Some("computer_system".to_owned())
+        );
+        assert_eq!(spec_hwinfo.manager, Some("manager".to_owned()));
+
+        Ok(())
+    }
+
     #[test]
     fn test_software_info() -> Result<()> {
         let mut dut = DutInfo::new("dut0");
@@ -689,6 +1215,47 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_location_pci() -> Result<()> {
+        assert_eq!(Location::pci(0, 0x3b, 0, 1).to_string(), "0000:3b:00.1");
+        assert_eq!(
+            Location::pci(0x10, 0xff, 0x1f, 7).to_string(),
+            "0010:ff:1f.7"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_location_dimm_slot() -> Result<()> {
+        assert_eq!(Location::dimm_slot(1, 'b', 2).to_string(), "CPU1/DIMM_B2");
+        Ok(())
+    }
+
+    #[test]
+    fn test_location_cpu() -> Result<()> {
+        assert_eq!(Location::cpu(1).to_string(), "CPU1");
+        assert_eq!(Location::cpu_core(0, 3).to_string(), "CPU0/Core3");
+        Ok(())
+    }
+
+    #[test]
+    fn test_location_typed() -> Result<()> {
+        let sub = Subcomponent::builder("sub_name")
+            .location_typed(Location::cpu_core(0, 3))
+            .build();
+        assert_eq!(sub.to_spec().location, Some("CPU0/Core3".to_owned()));
+
+        let info = HardwareInfo::builder("hw_name")
+            .location_typed(Location::pci(0, 0x3b, 0, 0))
+            .build();
+        let dut = DutHardwareInfo {
+            id: "hw0".to_owned(),
+            source: info,
+        };
+        assert_eq!(dut.to_spec().location, Some("0000:3b:00.0".to_owned()));
+        Ok(())
+    }
+
     /// 100% coverage test, since there's no way to exclude code
     #[test]
     fn test_infos_eq() -> Result<()> {
@@ -8,10 +8,12 @@ use std::io;
 use std::sync::atomic::{self, Ordering};
 use std::sync::Arc;
 
-use unwrap_infallible::UnwrapInfallible;
+use tokio::sync::Mutex;
 
+#[cfg(feature = "otel")]
+use crate::output::otel;
 use crate::output::{
-    config,
+    self as tv, config, step,
     writer::{self, WriterType},
 };
 use crate::spec;
@@ -19,7 +21,30 @@ use crate::spec;
 pub struct JsonEmitter {
     timestamp_provider: Box<dyn config::TimestampProvider + Send + Sync + 'static>,
     writer: writer::WriterType,
+    transform: Option<Box<config::Transform>>,
+    summary: Option<SummaryCounters>,
+    line_separator: String,
+    emit_timestamps: bool,
+    sanitize_control_chars: bool,
+    flush_each: bool,
+    min_severity: spec::LogSeverity,
+    symptom_pattern: Option<regex::Regex>,
+    extension_registry: Option<tv::ExtensionRegistry>,
+    #[cfg(feature = "otel")]
+    step_spans: Option<otel::StepSpans>,
+    timestamp_precision: config::TimestampPrecision,
+    write_error_policy: config::WriteErrorPolicy,
+    non_finite_policy: config::NonFinitePolicy,
     seqno: Arc<atomic::AtomicU64>,
+    sequence_start: u64,
+    // held while emitting, so that a group of artifacts is written contiguously
+    emit_lock: Mutex<()>,
+    // artifact held back until the first emitted one, see `defer`
+    deferred: Mutex<Option<spec::RootImpl>>,
+
+    artifact_count: atomic::AtomicU64,
+    byte_count: atomic::AtomicU64,
+    dropped_count: atomic::AtomicU64,
 }
 
 impl JsonEmitter {
@@ -30,54 +55,587 @@ impl JsonEmitter {
         JsonEmitter {
             timestamp_provider,
             writer,
+            transform: None,
+            summary: None,
+            line_separator: "\n".to_owned(),
+            emit_timestamps: true,
+            sanitize_control_chars: false,
+            flush_each: false,
+            min_severity: spec::LogSeverity::Debug,
+            symptom_pattern: None,
+            extension_registry: None,
+            #[cfg(feature = "otel")]
+            step_spans: None,
+            timestamp_precision: config::TimestampPrecision::default(),
+            write_error_policy: config::WriteErrorPolicy::Abort,
+            non_finite_policy: config::NonFinitePolicy::default(),
             seqno: Arc::new(atomic::AtomicU64::new(0)),
+            sequence_start: 0,
+            emit_lock: Mutex::new(()),
+            deferred: Mutex::new(None),
+            artifact_count: atomic::AtomicU64::new(0),
+            byte_count: atomic::AtomicU64::new(0),
+            dropped_count: atomic::AtomicU64::new(0),
         }
     }
 
+    /// Applies `transform` to every serialized artifact before it's written.
+    pub fn with_transform(mut self, transform: Box<config::Transform>) -> Self {
+        self.transform = Some(transform);
+        self
+    }
+
+    /// Tallies the diagnoses and errors going through this emitter, see [`JsonEmitter::summary`].
+    pub fn with_summary(mut self) -> Self {
+        self.summary = Some(SummaryCounters::default());
+        self
+    }
+
+    /// Terminates each artifact with `separator` instead of a newline, for the stdout, file
+    /// and bytes buffer outputs. The other writers don't get it, see
+    /// [`config::ConfigBuilder::line_separator`].
+    pub fn with_line_separator(mut self, separator: String) -> Self {
+        self.line_separator = separator;
+        self
+    }
+
+    /// Omits the `timestamp` fields from all the artifacts, including the measurement
+    /// series elements.
+    pub fn without_timestamps(mut self) -> Self {
+        self.emit_timestamps = false;
+        self
+    }
+
+    /// Strips raw control characters, other than tabs, line feeds and carriage returns,
+    /// from all the string fields of the artifacts.
+    pub fn with_sanitized_control_chars(mut self) -> Self {
+        self.sanitize_control_chars = true;
+        self
+    }
+
+    /// Flushes the output after every artifact.
+    pub fn with_flush_each(mut self) -> Self {
+        self.flush_each = true;
+        self
+    }
+
+    /// Drops the logs less severe than `severity`.
+    pub fn with_min_severity(mut self, severity: spec::LogSeverity) -> Self {
+        self.min_severity = severity;
+        self
+    }
+
+    /// Rejects the errors and diagnoses whose symptom or verdict doesn't match `pattern`.
+    pub fn with_symptom_pattern(mut self, pattern: regex::Regex) -> Self {
+        self.symptom_pattern = Some(pattern);
+        self
+    }
+
+    /// Rejects the step extensions whose `@type` isn't in `registry`.
+    pub fn with_extension_registry(mut self, registry: tv::ExtensionRegistry) -> Self {
+        self.extension_registry = Some(registry);
+        self
+    }
+
+    /// Opens an OpenTelemetry span for each test step, see [`otel::StepSpans`].
+    #[cfg(feature = "otel")]
+    pub fn with_otel_tracer(mut self, tracer: Arc<opentelemetry::global::BoxedTracer>) -> Self {
+        self.step_spans = Some(otel::StepSpans::new(tracer));
+        self
+    }
+
+    /// Formats the timestamps with the given fractional second precision.
+    pub fn with_timestamp_precision(mut self, precision: config::TimestampPrecision) -> Self {
+        self.timestamp_precision = precision;
+        self
+    }
+
+    /// Starts numbering the artifacts from `start` instead of 0. The schema version still
+    /// gets the first sequence number.
+    pub fn with_sequence_start(mut self, start: u64) -> Self {
+        self.seqno = Arc::new(atomic::AtomicU64::new(start));
+        self.sequence_start = start;
+        self
+    }
+
+    /// Sets what happens to an artifact when the output fails to write it.
+    pub fn with_write_error_policy(mut self, policy: config::WriteErrorPolicy) -> Self {
+        self.write_error_policy = policy;
+        self
+    }
+
+    /// Sets how NaN and infinite float measurement values are serialized.
+    pub fn with_non_finite_policy(mut self, policy: config::NonFinitePolicy) -> Self {
+        self.non_finite_policy = policy;
+        self
+    }
+
+    // note: only called under the emit lock, see `emit`
     fn incr_seqno(&self) -> u64 {
         self.seqno.fetch_add(1, Ordering::AcqRel)
     }
 
     async fn emit_version(&self) -> Result<(), io::Error> {
-        let s = self.serialize(&spec::RootImpl::SchemaVersion(
-            spec::SchemaVersion::default(),
-        ));
-
-        self.write(s).await
+        let result = self
+            .emit_artifact(
+                &spec::RootImpl::SchemaVersion(spec::SchemaVersion::default()),
+                None,
+            )
+            .await;
+        // the version wasn't written, so give back its sequence number, so that the next
+        // emit tries again instead of starting the output without it
+        if result.is_err() {
+            self.seqno.store(self.sequence_start, Ordering::Release);
+        }
+        result
     }
 
-    fn serialize(&self, root: &spec::RootImpl) -> String {
+    async fn emit_artifact(
+        &self,
+        root: &spec::RootImpl,
+        timestamp: Option<chrono::DateTime<chrono_tz::Tz>>,
+    ) -> Result<(), io::Error> {
+        // explicit timestamps are converted to the configured timezone, same as the
+        // measurement series elements
+        let now = self.timestamp_provider.now();
         let root = spec::Root {
             artifact: root.clone(),
-            timestamp: self.timestamp_provider.now(),
+            timestamp: timestamp.map_or(now, |ts| ts.with_timezone(&now.timezone())),
             seqno: self.incr_seqno(),
         };
 
-        serde_json::json!(root).to_string()
+        if let WriterType::Recording(recorder) = &self.writer {
+            recorder.record(&root.artifact).await;
+            self.artifact_count.fetch_add(1, Ordering::AcqRel);
+            return Ok(());
+        }
+
+        let Some(value) = self.to_value(&root) else {
+            return Ok(());
+        };
+
+        let s = match self.writer.format() {
+            writer::OutputFormat::Pretty => format!("{:#}", value),
+            _ => value.to_string(),
+        };
+        self.write(s).await
     }
 
-    async fn write(&self, s: String) -> Result<(), io::Error> {
-        match &self.writer {
-            WriterType::File(file) => file.write(&s).await?,
-            WriterType::Stdout(stdout) => stdout.write(&s).await.unwrap_infallible(),
-            WriterType::Buffer(buffer) => buffer.write(&s).await.unwrap_infallible(),
+    /// Converts the artifact to json and applies the configured transform, if any.
+    /// Returns `None` when the transform dropped the artifact by replacing it with `null`.
+    fn to_value(&self, root: &spec::Root) -> Option<tv::Value> {
+        let mut value = serde_json::json!(root);
+        // the spec serializer always uses millis, so only reformat for the other precisions
+        if self.timestamp_precision != config::TimestampPrecision::Millis {
+            format_timestamps(&mut value, root, self.timestamp_precision);
+        }
+        if !self.emit_timestamps {
+            strip_timestamps(&mut value);
+        }
+        if self.sanitize_control_chars {
+            strip_control_chars(&mut value);
+        }
+        if let Some(transform) = &self.transform {
+            transform(&mut value);
+        }
 
-            WriterType::Custom(custom) => custom.write(&s).await?,
+        match value {
+            tv::Value::Null => None,
+            value => Some(value),
         }
+    }
+
+    async fn write(&self, s: String) -> Result<(), io::Error> {
+        let written = match self.write_output(&s).await {
+            Ok(written) => written,
+            Err(e) => match &self.write_error_policy {
+                config::WriteErrorPolicy::Abort => return Err(e),
+                config::WriteErrorPolicy::Ignore => {
+                    self.dropped_count.fetch_add(1, Ordering::AcqRel);
+                    return Ok(());
+                }
+                // the fallback gets the artifact without a terminator
+                config::WriteErrorPolicy::Fallback(fallback) => {
+                    fallback.write(&s).await?;
+                    s.len()
+                }
+            },
+        };
+
+        self.artifact_count.fetch_add(1, Ordering::AcqRel);
+        self.byte_count.fetch_add(written as u64, Ordering::AcqRel);
 
         Ok(())
     }
 
+    async fn write_output(&self, s: &str) -> Result<usize, io::Error> {
+        let written = self
+            .writer
+            .write_terminated(s, &self.line_separator)
+            .await?;
+
+        if self.flush_each {
+            self.writer.flush().await?;
+        }
+
+        Ok(written)
+    }
+
+    /// Flushes the output writer, see [`writer::Writer::flush`]. Nothing to do when the
+    /// writer is already flushed after each artifact.
+    pub async fn flush(&self) -> Result<(), io::Error> {
+        if self.flush_each {
+            return Ok(());
+        }
+        self.writer.flush().await
+    }
+
+    /// Number of artifacts successfully written so far.
+    pub fn artifact_count(&self) -> u64 {
+        self.artifact_count.load(Ordering::Acquire)
+    }
+
+    /// Number of bytes successfully written so far, including line terminators.
+    pub fn byte_count(&self) -> u64 {
+        self.byte_count.load(Ordering::Acquire)
+    }
+
+    /// Number of artifacts dropped after an output error, see
+    /// [`config::WriteErrorPolicy::Ignore`].
+    pub fn dropped_count(&self) -> u64 {
+        self.dropped_count.load(Ordering::Acquire)
+    }
+
     pub fn timestamp_provider(&self) -> &(dyn config::TimestampProvider + Send + Sync + 'static) {
         &*self.timestamp_provider
     }
 
+    /// How the measurement values are serialized if NaN or infinite, see
+    /// [`tv::MeasurementValue::to_value`].
+    pub fn non_finite_policy(&self) -> config::NonFinitePolicy {
+        self.non_finite_policy
+    }
+
+    /// Returns the tally of diagnoses and errors emitted so far, if enabled.
+    pub fn summary(&self) -> Option<tv::Value> {
+        self.summary.as_ref().map(SummaryCounters::to_value)
+    }
+
+    /// Emits the artifact with the next sequence number.
+    ///
+    /// Sequence numbers are assigned and the artifacts written while holding the emit lock,
+    /// so even with concurrent callers (eg. several steps emitting from different tasks)
+    /// the output has strictly increasing sequence numbers without duplicates. There are
+    /// no gaps unless artifacts are dropped by a transform or lost to write errors.
     pub async fn emit(&self, root: &spec::RootImpl) -> Result<(), io::Error> {
-        if self.seqno.load(Ordering::Acquire) == 0 {
+        self.emit_at(root, None).await
+    }
+
+    /// Emits the artifact like [`JsonEmitter::emit`], but with the given `timestamp`
+    /// instead of the current time from the timestamp provider, if any.
+    pub async fn emit_at(
+        &self,
+        root: &spec::RootImpl,
+        timestamp: Option<chrono::DateTime<chrono_tz::Tz>>,
+    ) -> Result<(), io::Error> {
+        let _guard = self.emit_lock.lock().await;
+        self.emit_locked(root, timestamp).await
+    }
+
+    /// Emits the artifact like [`JsonEmitter::emit`], then calls `after` before releasing
+    /// the emit lock, so that eg. a counter read in `after` follows the output order.
+    /// `after` isn't called if the artifact fails to be written.
+    pub async fn emit_then<R>(
+        &self,
+        root: &spec::RootImpl,
+        after: impl FnOnce() -> R,
+    ) -> Result<R, io::Error> {
+        let _guard = self.emit_lock.lock().await;
+        self.emit_locked(root, None).await?;
+
+        Ok(after())
+    }
+
+    /// Emits the artifact like [`JsonEmitter::emit`], but without the output checks and
+    /// the severity filter, for the artifacts added by the library itself, eg. the run
+    /// summary.
+    pub async fn emit_unfiltered(&self, root: &spec::RootImpl) -> Result<(), io::Error> {
+        let _guard = self.emit_lock.lock().await;
+        self.emit_passed(root, None).await
+    }
+
+    /// Emits all the artifacts in order, without any other artifact in between them.
+    pub async fn emit_all(&self, roots: &[spec::RootImpl]) -> Result<(), io::Error> {
+        let _guard = self.emit_lock.lock().await;
+        for root in roots {
+            self.emit_locked(root, None).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Holds `root` back until another artifact is emitted, and then writes it first,
+    /// right after the schema version.
+    pub async fn defer(&self, root: spec::RootImpl) {
+        let _guard = self.emit_lock.lock().await;
+        *self.deferred.lock().await = Some(root);
+    }
+
+    /// Drops the deferred artifact, if any. Returns whether there was one, meaning that
+    /// nothing was emitted since it was deferred.
+    pub async fn discard_deferred(&self) -> bool {
+        let _guard = self.emit_lock.lock().await;
+        self.deferred.lock().await.take().is_some()
+    }
+
+    // note: the raw artifacts are checked as well, through the fields of their json body
+    fn is_filtered(&self, root: &spec::RootImpl) -> bool {
+        let severity = match root {
+            spec::RootImpl::TestRunArtifact(spec::TestRunArtifact {
+                artifact: spec::TestRunArtifactImpl::Log(log),
+            }) => log.severity.clone(),
+            spec::RootImpl::TestStepArtifact(spec::TestStepArtifact {
+                artifact: spec::TestStepArtifactImpl::Log(log),
+                ..
+            }) => log.severity.clone(),
+            spec::RootImpl::Raw(raw) => {
+                let severity = raw_field(raw, "testRunArtifact", "/log/severity")
+                    .or_else(|| raw_field(raw, "testStepArtifact", "/log/severity"));
+                match severity
+                    .and_then(tv::Value::as_str)
+                    .and_then(parse_severity)
+                {
+                    Some(severity) => severity,
+                    None => return false,
+                }
+            }
+            _ => return false,
+        };
+
+        severity < self.min_severity
+    }
+
+    fn check_symptom(&self, root: &spec::RootImpl) -> Result<(), io::Error> {
+        let Some(pattern) = &self.symptom_pattern else {
+            return Ok(());
+        };
+
+        let symptom = match root {
+            spec::RootImpl::TestRunArtifact(spec::TestRunArtifact {
+                artifact: spec::TestRunArtifactImpl::Error(error),
+            }) => error.symptom.as_str(),
+            spec::RootImpl::TestStepArtifact(spec::TestStepArtifact { artifact, .. }) => {
+                match artifact {
+                    spec::TestStepArtifactImpl::Error(error) => error.symptom.as_str(),
+                    spec::TestStepArtifactImpl::Diagnosis(diagnosis) => diagnosis.verdict.as_str(),
+                    _ => return Ok(()),
+                }
+            }
+            spec::RootImpl::Raw(raw) => {
+                let symptom = raw_field(raw, "testRunArtifact", "/error/symptom")
+                    .or_else(|| raw_field(raw, "testStepArtifact", "/error/symptom"))
+                    .or_else(|| raw_field(raw, "testStepArtifact", "/diagnosis/verdict"));
+                match symptom.and_then(tv::Value::as_str) {
+                    Some(symptom) => symptom,
+                    None => return Ok(()),
+                }
+            }
+            _ => return Ok(()),
+        };
+
+        match pattern.is_match(symptom) {
+            true => Ok(()),
+            false => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("symptom '{}' doesn't match '{}'", symptom, pattern),
+            )),
+        }
+    }
+
+    fn check_extension_type(&self, root: &spec::RootImpl) -> Result<(), io::Error> {
+        let Some(registry) = &self.extension_registry else {
+            return Ok(());
+        };
+
+        let (name, content) = match root {
+            spec::RootImpl::TestStepArtifact(spec::TestStepArtifact {
+                artifact: spec::TestStepArtifactImpl::Extension(ext),
+                ..
+            }) => (ext.name.as_str(), Some(&ext.content)),
+            spec::RootImpl::Raw(raw) => match raw_field(raw, "testStepArtifact", "/extension") {
+                Some(ext) => (
+                    ext.get("name")
+                        .and_then(tv::Value::as_str)
+                        .unwrap_or_default(),
+                    ext.get("content"),
+                ),
+                None => return Ok(()),
+            },
+            _ => return Ok(()),
+        };
+
+        let type_tag = content
+            .and_then(|content| content.get(step::EXTENSION_TYPE_FIELD))
+            .and_then(tv::Value::as_str);
+        match type_tag {
+            Some(type_tag) if registry.contains(type_tag) => Ok(()),
+            Some(type_tag) => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("extension {} has unknown type '{}'", name, type_tag),
+            )),
+            None => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("extension {} has no type", name),
+            )),
+        }
+    }
+
+    async fn emit_locked(
+        &self,
+        root: &spec::RootImpl,
+        timestamp: Option<chrono::DateTime<chrono_tz::Tz>>,
+    ) -> Result<(), io::Error> {
+        self.check_symptom(root)?;
+        self.check_extension_type(root)?;
+        // filter before anything else, so dropped logs don't take a sequence number
+        if self.is_filtered(root) {
+            return Ok(());
+        }
+
+        self.emit_passed(root, timestamp).await
+    }
+
+    // emits an artifact that passed the checks and the severity filter
+    async fn emit_passed(
+        &self,
+        root: &spec::RootImpl,
+        timestamp: Option<chrono::DateTime<chrono_tz::Tz>>,
+    ) -> Result<(), io::Error> {
+        if self.seqno.load(Ordering::Acquire) == self.sequence_start {
             self.emit_version().await?;
         }
+        if let Some(deferred) = self.deferred.lock().await.take() {
+            self.emit_artifact(&deferred, None).await?;
+        }
+
+        if let Some(summary) = &self.summary {
+            summary.observe(root);
+        }
+        #[cfg(feature = "otel")]
+        if let Some(step_spans) = &self.step_spans {
+            step_spans.observe(root);
+        }
+
+        self.emit_artifact(root, timestamp).await
+    }
+}
+
+// looks up `pointer` in the `artifact` body of a raw artifact, eg. "/error/symptom"
+// in "testRunArtifact"
+fn raw_field<'a>(
+    raw: &'a serde_json::Map<String, tv::Value>,
+    artifact: &str,
+    pointer: &str,
+) -> Option<&'a tv::Value> {
+    raw.get(artifact)?.pointer(pointer)
+}
+
+fn parse_severity(severity: &str) -> Option<spec::LogSeverity> {
+    use spec::LogSeverity as LS;
+
+    [LS::Debug, LS::Info, LS::Warning, LS::Error, LS::Fatal]
+        .into_iter()
+        .find(|known| serde_json::json!(known) == severity)
+}
+
+fn format_timestamps(
+    value: &mut tv::Value,
+    root: &spec::Root,
+    precision: config::TimestampPrecision,
+) {
+    let format = |ts: &chrono::DateTime<chrono_tz::Tz>| {
+        tv::Value::from(ts.to_rfc3339_opts(precision.seconds_format(), true))
+    };
+
+    if let Some(ts) = value.get_mut("timestamp") {
+        *ts = format(&root.timestamp);
+    }
+    if let spec::RootImpl::TestStepArtifact(spec::TestStepArtifact {
+        artifact: spec::TestStepArtifactImpl::MeasurementSeriesElement(element),
+        ..
+    }) = &root.artifact
+    {
+        if let Some(ts) = value.pointer_mut("/testStepArtifact/measurementSeriesElement/timestamp")
+        {
+            *ts = format(&element.timestamp);
+        }
+    }
+}
+
+fn strip_timestamps(value: &mut tv::Value) {
+    if let Some(root) = value.as_object_mut() {
+        root.remove("timestamp");
+    }
+    if let Some(tv::Value::Object(element)) =
+        value.pointer_mut("/testStepArtifact/measurementSeriesElement")
+    {
+        element.remove("timestamp");
+    }
+}
+
+fn strip_control_chars(value: &mut tv::Value) {
+    match value {
+        tv::Value::String(s) => s.retain(|c| !is_stripped_control(c)),
+        tv::Value::Array(items) => items.iter_mut().for_each(strip_control_chars),
+        tv::Value::Object(map) => map.values_mut().for_each(strip_control_chars),
+        _ => {}
+    }
+}
+
+fn is_stripped_control(c: char) -> bool {
+    c.is_control() && !matches!(c, '\t' | '\n' | '\r')
+}
+
+#[derive(Default)]
+struct SummaryCounters {
+    pass: atomic::AtomicU64,
+    fail: atomic::AtomicU64,
+    unknown: atomic::AtomicU64,
+    errors: atomic::AtomicU64,
+}
 
-        self.write(self.serialize(root)).await
+impl SummaryCounters {
+    fn observe(&self, root: &spec::RootImpl) {
+        let counter = match root {
+            spec::RootImpl::TestRunArtifact(spec::TestRunArtifact {
+                artifact: spec::TestRunArtifactImpl::Error(_),
+            }) => &self.errors,
+            spec::RootImpl::TestStepArtifact(spec::TestStepArtifact { artifact, .. }) => {
+                match artifact {
+                    spec::TestStepArtifactImpl::Error(_) => &self.errors,
+                    spec::TestStepArtifactImpl::Diagnosis(d) => match d.diagnosis_type {
+                        spec::DiagnosisType::Pass => &self.pass,
+                        spec::DiagnosisType::Fail => &self.fail,
+                        spec::DiagnosisType::Unknown => &self.unknown,
+                    },
+                    _ => return,
+                }
+            }
+            _ => return,
+        };
+
+        counter.fetch_add(1, Ordering::AcqRel);
+    }
+
+    fn to_value(&self) -> tv::Value {
+        serde_json::json!({
+            "diagnoses": {
+                "pass": self.pass.load(Ordering::Acquire),
+                "fail": self.fail.load(Ordering::Acquire),
+                "unknown": self.unknown.load(Ordering::Acquire),
+            },
+            "errors": self.errors.load(Ordering::Acquire),
+        })
     }
 }
 
@@ -136,6 +694,90 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_sequence_number_starts_at_configured_offset() -> Result<()> {
+        let buffer = Arc::new(Mutex::new(vec![]));
+        let writer = writer::BufferWriter::new(buffer.clone());
+        let emitter = JsonEmitter::new(
+            Box::new(NullTimestampProvider {}),
+            writer::WriterType::Buffer(writer),
+        )
+        .with_sequence_start(1000);
+
+        let version = spec::RootImpl::SchemaVersion(spec::SchemaVersion::default());
+        emitter.emit(&version).await?;
+        emitter.emit(&version).await?;
+
+        let seqnos = buffer
+            .lock()
+            .await
+            .iter()
+            .map(|entry| serde_json::from_str::<serde_json::Value>(entry))
+            .collect::<Result<Vec<_>, _>>()?
+            .into_iter()
+            .map(|value| value["sequenceNumber"].clone())
+            .collect::<Vec<_>>();
+        // the automatic schema version gets the start, and is only emitted once
+        assert_eq!(seqnos, vec![json!(1000), json!(1001), json!(1002)]);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_schema_version_retried_after_write_error() -> Result<()> {
+        use std::sync::atomic::AtomicBool;
+
+        // fails the first write only
+        struct FlakyWriter {
+            failed: AtomicBool,
+            buffer: Arc<Mutex<Vec<String>>>,
+        }
+
+        #[async_trait::async_trait]
+        impl writer::Writer for FlakyWriter {
+            async fn write(&self, s: &str) -> Result<(), io::Error> {
+                if !self.failed.swap(true, Ordering::AcqRel) {
+                    return Err(io::Error::other("flaky"));
+                }
+                self.buffer.lock().await.push(s.to_owned());
+                Ok(())
+            }
+        }
+
+        let buffer = Arc::new(Mutex::new(vec![]));
+        let emitter = JsonEmitter::new(
+            Box::new(NullTimestampProvider {}),
+            writer::WriterType::Custom(Box::new(FlakyWriter {
+                failed: AtomicBool::new(false),
+                buffer: buffer.clone(),
+            })),
+        )
+        .with_sequence_start(10);
+
+        let log = spec::RootImpl::TestRunArtifact(spec::TestRunArtifact {
+            artifact: spec::TestRunArtifactImpl::Log(spec::Log {
+                severity: spec::LogSeverity::Info,
+                message: "message".to_owned(),
+                source_location: None,
+            }),
+        });
+        assert!(emitter.emit(&log).await.is_err());
+        emitter.emit(&log).await?;
+
+        let written = buffer
+            .lock()
+            .await
+            .iter()
+            .map(|entry| serde_json::from_str::<serde_json::Value>(entry))
+            .collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(written.len(), 2);
+        assert!(written[0]["schemaVersion"].is_object());
+        assert_eq!(written[0]["sequenceNumber"], 10);
+        assert_eq!(written[1]["sequenceNumber"], 11);
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_sequence_number_increments_at_each_call() -> Result<()> {
         let expected_1 = json!({
@@ -178,4 +820,198 @@ mod tests {
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_stats_count_written_artifacts() -> Result<()> {
+        let buffer = Arc::new(Mutex::new(vec![]));
+        let writer = writer::BufferWriter::new(buffer.clone());
+        let emitter = JsonEmitter::new(
+            Box::new(NullTimestampProvider {}),
+            writer::WriterType::Buffer(writer),
+        );
+
+        let version = spec::RootImpl::SchemaVersion(spec::SchemaVersion::default());
+        emitter.emit(&version).await?;
+        emitter.emit(&version).await?;
+
+        // the buffer output takes no line terminator
+        let expected_bytes: u64 = buffer.lock().await.iter().map(|s| s.len() as u64).sum();
+        assert_eq!(emitter.artifact_count(), 3);
+        assert_eq!(emitter.byte_count(), expected_bytes);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_emit_uses_writer_format() -> Result<()> {
+        struct PrettyWriter {
+            buffer: Arc<Mutex<Vec<String>>>,
+        }
+
+        #[async_trait::async_trait]
+        impl writer::Writer for PrettyWriter {
+            async fn write(&self, s: &str) -> Result<(), io::Error> {
+                self.buffer.lock().await.push(s.to_owned());
+                Ok(())
+            }
+
+            fn format(&self) -> writer::OutputFormat {
+                writer::OutputFormat::Pretty
+            }
+        }
+
+        let compact_buffer = Arc::new(Mutex::new(vec![]));
+        let compact = JsonEmitter::new(
+            Box::new(NullTimestampProvider {}),
+            writer::WriterType::Buffer(writer::BufferWriter::new(compact_buffer.clone())),
+        );
+        let pretty_buffer = Arc::new(Mutex::new(vec![]));
+        let pretty = JsonEmitter::new(
+            Box::new(NullTimestampProvider {}),
+            writer::WriterType::Custom(Box::new(PrettyWriter {
+                buffer: pretty_buffer.clone(),
+            })),
+        );
+
+        let version = spec::RootImpl::SchemaVersion(spec::SchemaVersion::default());
+        compact.emit(&version).await?;
+        pretty.emit(&version).await?;
+
+        let compact_output = compact_buffer.lock().await[0].clone();
+        let pretty_output = pretty_buffer.lock().await[0].clone();
+        assert!(!compact_output.contains('\n'));
+        assert!(pretty_output.contains("\n  \""));
+        assert_ne!(compact_output, pretty_output);
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&compact_output)?,
+            serde_json::from_str::<serde_json::Value>(&pretty_output)?
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_transform_drops_null_artifacts() -> Result<()> {
+        let buffer = Arc::new(Mutex::new(vec![]));
+        let writer = writer::BufferWriter::new(buffer.clone());
+        let emitter = JsonEmitter::new(
+            Box::new(NullTimestampProvider {}),
+            writer::WriterType::Buffer(writer),
+        )
+        .with_transform(Box::new(|value: &mut serde_json::Value| {
+            if value["sequenceNumber"] == 1 {
+                *value = serde_json::Value::Null;
+            }
+        }));
+
+        let version = spec::RootImpl::SchemaVersion(spec::SchemaVersion::default());
+        emitter.emit(&version).await?;
+        emitter.emit(&version).await?;
+
+        let buffer = buffer.lock().await;
+        assert_eq!(buffer.len(), 2);
+        let deserialized = serde_json::from_str::<serde_json::Value>(&buffer[1])?;
+        assert_eq!(deserialized["sequenceNumber"], 2);
+
+        Ok(())
+    }
+
+    /// Accepts the first `fail_at - 1` writes, then fails every write after that.
+    struct FailingWriter {
+        fail_at: usize,
+        written: Arc<Mutex<Vec<String>>>,
+    }
+
+    #[async_trait::async_trait]
+    impl writer::Writer for FailingWriter {
+        async fn write(&self, s: &str) -> Result<(), io::Error> {
+            let mut written = self.written.lock().await;
+            if written.len() + 1 >= self.fail_at {
+                return Err(io::Error::new(io::ErrorKind::BrokenPipe, "output gone"));
+            }
+
+            written.push(s.to_owned());
+            Ok(())
+        }
+    }
+
+    fn failing_emitter(
+        fail_at: usize,
+        policy: config::WriteErrorPolicy,
+    ) -> (JsonEmitter, Arc<Mutex<Vec<String>>>) {
+        let written = Arc::new(Mutex::new(vec![]));
+        let emitter = JsonEmitter::new(
+            Box::new(NullTimestampProvider {}),
+            writer::WriterType::Custom(Box::new(FailingWriter {
+                fail_at,
+                written: written.clone(),
+            })),
+        )
+        .with_write_error_policy(policy);
+
+        (emitter, written)
+    }
+
+    #[tokio::test]
+    async fn test_write_error_abort_returns_error() -> Result<()> {
+        let (emitter, written) = failing_emitter(3, config::WriteErrorPolicy::Abort);
+
+        // first emit also writes the schema version artifact
+        let version = spec::RootImpl::SchemaVersion(spec::SchemaVersion::default());
+        emitter.emit(&version).await?;
+        assert!(emitter.emit(&version).await.is_err());
+        assert_eq!(written.lock().await.len(), 2);
+        assert_eq!(emitter.artifact_count(), 2);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_write_error_ignore_drops_artifact() -> Result<()> {
+        let (emitter, written) = failing_emitter(3, config::WriteErrorPolicy::Ignore);
+
+        let version = spec::RootImpl::SchemaVersion(spec::SchemaVersion::default());
+        for _ in 0..3 {
+            emitter.emit(&version).await?;
+        }
+        assert_eq!(written.lock().await.len(), 2);
+        assert_eq!(emitter.artifact_count(), 2);
+        assert_eq!(emitter.dropped_count(), 2);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_write_error_fallback_receives_artifact() -> Result<()> {
+        let fallback_buffer = Arc::new(Mutex::new(vec![]));
+        let fallback = FailingWriter {
+            fail_at: usize::MAX,
+            written: fallback_buffer.clone(),
+        };
+        let (emitter, written) =
+            failing_emitter(3, config::WriteErrorPolicy::Fallback(Box::new(fallback)));
+
+        let version = spec::RootImpl::SchemaVersion(spec::SchemaVersion::default());
+        for _ in 0..4 {
+            emitter.emit(&version).await?;
+        }
+        assert_eq!(written.lock().await.len(), 2);
+        assert_eq!(emitter.artifact_count(), 5);
+
+        let fallback_buffer = fallback_buffer.lock().await;
+        assert_eq!(fallback_buffer.len(), 3);
+        assert_eq!(emitter.dropped_count(), 0);
+
+        let written_bytes = written.lock().await.iter().map(String::len).sum::<usize>();
+        let fallback_bytes = fallback_buffer.iter().map(String::len).sum::<usize>();
+        assert_eq!(
+            emitter.byte_count(),
+            (written_bytes + fallback_bytes) as u64
+        );
+
+        let deserialized = serde_json::from_str::<serde_json::Value>(&fallback_buffer[0])?;
+        assert_eq!(deserialized["sequenceNumber"], 2);
+
+        Ok(())
+    }
 }
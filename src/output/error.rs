@@ -6,13 +6,15 @@
 
 use crate::output as tv;
 use crate::spec;
-use tv::{dut, trait_ext::VecExt, DutSoftwareInfo};
+use tv::trait_ext::VecExt;
+use tv::{dut, DutSoftwareInfo};
 
 /// TODO: docs
-#[derive(Clone)]
+#[derive(Debug, Clone)]
 pub struct Error {
     symptom: String,
     message: Option<String>,
+    code: Option<String>,
     software_infos: Vec<dut::DutSoftwareInfo>,
     source_location: Option<spec::SourceLocation>,
 }
@@ -25,11 +27,32 @@ impl Error {
     pub fn to_artifact(&self) -> spec::Error {
         spec::Error {
             symptom: self.symptom.clone(),
-            message: self.message.clone(),
+            message: match (&self.code, &self.message) {
+                (Some(code), Some(message)) => Some(format!("[{}] {}", code, message)),
+                (Some(code), None) => Some(format!("[{}]", code)),
+                (None, message) => message.clone(),
+            },
             software_infos: self.software_infos.map_option(DutSoftwareInfo::to_spec),
             source_location: self.source_location.clone(),
         }
     }
+
+    // rebuilds the error from an emitted artifact, see `tv::RecordingWriter`; the code
+    // is already part of the emitted message
+    pub(crate) fn from_spec(error: spec::Error) -> Self {
+        Error {
+            symptom: error.symptom,
+            message: error.message,
+            code: None,
+            software_infos: error
+                .software_infos
+                .unwrap_or_default()
+                .into_iter()
+                .map(DutSoftwareInfo::from_spec)
+                .collect(),
+            source_location: error.source_location,
+        }
+    }
 }
 
 /// TODO: docs
@@ -37,6 +60,7 @@ impl Error {
 pub struct ErrorBuilder {
     symptom: String,
     message: Option<String>,
+    code: Option<String>,
     software_infos: Vec<dut::DutSoftwareInfo>,
     source_location: Option<spec::SourceLocation>,
 }
@@ -67,10 +91,39 @@ impl ErrorBuilder {
         self
     }
 
+    /// Adds references to all the given software infos, in order.
+    /// They are emitted by id, in the `softwareInfoIds` list.
+    pub fn add_software_infos(mut self, software_infos: &[&dut::DutSoftwareInfo]) -> Self {
+        self.software_infos
+            .extend(software_infos.iter().map(|&info| info.clone()));
+        self
+    }
+
+    /// Sets a stable, machine-readable code for the error, eg. for triage tooling that
+    /// shouldn't depend on the free-form symptom.
+    /// The spec `error` object has no field for it, so it's emitted in brackets at the
+    /// start of the message, eg. `[E1042] fan0 stuck at 0 rpm`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use ocptv::output::*;
+    /// let error = Error::builder("fan-stuck")
+    ///     .code("E1042")
+    ///     .message("fan0 stuck at 0 rpm")
+    ///     .build();
+    /// assert_eq!(error.to_artifact().message.unwrap(), "[E1042] fan0 stuck at 0 rpm");
+    /// ```
+    pub fn code(mut self, code: &str) -> Self {
+        self.code = Some(code.to_owned());
+        self
+    }
+
     pub fn build(self) -> Error {
         Error {
             symptom: self.symptom,
             message: self.message,
+            code: self.code,
             source_location: self.source_location,
             software_infos: self.software_infos,
         }
@@ -187,4 +240,50 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_error_with_multiple_software_infos() -> Result<()> {
+        let mut dut = dut::DutInfo::new("dut0");
+        let bmc = dut.add_software_info(
+            dut::SoftwareInfo::builder("bmc")
+                .id(Ident::Exact("sw0".to_owned()))
+                .build(),
+        );
+        let bios = dut.add_software_info(
+            dut::SoftwareInfo::builder("bios")
+                .id(Ident::Exact("sw1".to_owned()))
+                .build(),
+        );
+
+        let error = Error::builder("symptom")
+            .add_software_infos(&[&bmc, &bios])
+            .build();
+
+        let actual = serde_json::to_value(error.to_artifact())?;
+        assert_json_eq!(
+            actual,
+            json!({
+                "symptom": "symptom",
+                "softwareInfoIds": ["sw0", "sw1"]
+            })
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_error_with_code() -> Result<()> {
+        let error = Error::builder("fan-stuck").code("E1042").build();
+
+        let actual = serde_json::to_value(error.to_artifact())?;
+        assert_json_eq!(
+            actual,
+            json!({
+                "symptom": "fan-stuck",
+                "message": "[E1042]"
+            })
+        );
+
+        Ok(())
+    }
 }
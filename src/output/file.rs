@@ -4,9 +4,9 @@
 // license that can be found in the LICENSE file or at
 // https://opensource.org/licenses/MIT.
 
-use std::collections::BTreeMap;
-
+use base64::prelude::*;
 use mime;
+use serde_json::Map;
 
 use crate::output::{self as tv, trait_ext::MapExt};
 use crate::spec;
@@ -38,13 +38,14 @@ use crate::spec;
 ///     .add_metadata("key", "value")
 ///     .build();
 /// ```
+#[derive(Debug, Clone)]
 pub struct File {
     name: String,
     uri: tv::Uri,
     is_snapshot: bool,
     description: Option<String>,
     content_type: Option<mime::Mime>,
-    metadata: BTreeMap<String, tv::Value>,
+    metadata: Map<String, tv::Value>,
 }
 
 impl File {
@@ -64,10 +65,36 @@ impl File {
             is_snapshot: false,
             description: None,
             content_type: None,
-            metadata: BTreeMap::new(),
+            metadata: Map::new(),
         }
     }
 
+    /// Builds a new File object with the content inlined in the uri, as a base64 encoded
+    /// `data:` uri, eg. for small register dumps that aren't worth a separate file.
+    /// The content type is set to `content_type`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ocptv::output::*;
+    /// let file = File::inline("regs", &[0xde, 0xad], mime::APPLICATION_OCTET_STREAM)?;
+    /// # Ok::<(), OcptvError>(())
+    /// ```
+    pub fn inline(
+        name: &str,
+        content: &[u8],
+        content_type: mime::Mime,
+    ) -> Result<Self, tv::OcptvError> {
+        let uri = format!(
+            "data:{};base64,{}",
+            content_type.essence_str(),
+            BASE64_STANDARD.encode(content)
+        );
+        let uri = tv::Uri::parse(&uri).map_err(|e| tv::OcptvError::Format(Box::new(e)))?;
+
+        Ok(File::builder(name, uri).content_type(content_type).build())
+    }
+
     /// Builds a new File object using [`FileBuilder`].
     ///
     /// # Examples
@@ -106,6 +133,18 @@ impl File {
             metadata: self.metadata.option(),
         }
     }
+
+    // rebuilds the file from an emitted artifact, see `tv::RecordingWriter`
+    pub(crate) fn from_spec(file: spec::File) -> Self {
+        File {
+            name: file.name,
+            uri: tv::Uri::parse(&file.uri).expect("file uri was serialized from a valid uri"),
+            is_snapshot: file.is_snapshot,
+            description: file.description,
+            content_type: file.content_type.and_then(|ct| ct.parse().ok()),
+            metadata: file.metadata.unwrap_or_default(),
+        }
+    }
 }
 
 /// This structure builds a [`File`] object.
@@ -129,7 +168,7 @@ pub struct FileBuilder {
     description: Option<String>,
     content_type: Option<mime::Mime>,
 
-    metadata: BTreeMap<String, tv::Value>,
+    metadata: Map<String, tv::Value>,
 }
 
 impl FileBuilder {
@@ -140,7 +179,7 @@ impl FileBuilder {
             is_snapshot: false,
             description: None,
             content_type: None,
-            metadata: BTreeMap::new(),
+            metadata: Map::new(),
         }
     }
 
@@ -235,8 +274,6 @@ mod tests {
     use crate::output as tv;
     use crate::spec;
     use anyhow::Result;
-    use maplit::btreemap;
-    use maplit::convert_args;
 
     #[test]
     fn test_file_as_test_step_descendant_to_artifact() -> Result<()> {
@@ -271,9 +308,7 @@ mod tests {
         let content_type = mime::TEXT_PLAIN;
         let meta_key = "key";
         let meta_value = tv::Value::from("value");
-        let metadata = convert_args!(btreemap!(
-            meta_key => meta_value.clone(),
-        ));
+        let metadata = Map::from_iter([(meta_key.to_owned(), meta_value.clone())]);
 
         let file = File::builder(&name, uri.clone())
             .is_snapshot(is_snapshot)
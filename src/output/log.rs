@@ -7,10 +7,12 @@
 use crate::spec;
 
 /// TODO: docs
+#[derive(Debug, Clone)]
 pub struct Log {
     severity: spec::LogSeverity,
     message: String,
     source_location: Option<spec::SourceLocation>,
+    timestamp: Option<chrono::DateTime<chrono_tz::Tz>>,
 }
 
 impl Log {
@@ -18,6 +20,11 @@ impl Log {
         LogBuilder::new(message)
     }
 
+    // explicit timestamp of the artifact, see `LogBuilder::timestamp`
+    pub(crate) fn timestamp(&self) -> Option<chrono::DateTime<chrono_tz::Tz>> {
+        self.timestamp
+    }
+
     pub fn to_artifact(&self) -> spec::Log {
         spec::Log {
             severity: self.severity.clone(),
@@ -25,6 +32,16 @@ impl Log {
             source_location: self.source_location.clone(),
         }
     }
+
+    // rebuilds the log from an emitted artifact, see `tv::RecordingWriter`
+    pub(crate) fn from_spec(log: spec::Log) -> Self {
+        Log {
+            severity: log.severity,
+            message: log.message,
+            source_location: log.source_location,
+            timestamp: None,
+        }
+    }
 }
 
 /// TODO: docs
@@ -33,6 +50,7 @@ pub struct LogBuilder {
     severity: spec::LogSeverity,
     message: String,
     source_location: Option<spec::SourceLocation>,
+    timestamp: Option<chrono::DateTime<chrono_tz::Tz>>,
 }
 
 impl LogBuilder {
@@ -41,6 +59,7 @@ impl LogBuilder {
             severity: spec::LogSeverity::Info,
             message: message.to_string(),
             source_location: None,
+            timestamp: None,
         }
     }
 
@@ -57,11 +76,30 @@ impl LogBuilder {
         self
     }
 
+    /// Sets the timestamp of the log artifact, eg. when replaying captured logs, instead
+    /// of the current time from the configured [`crate::output::TimestampProvider`]. The timestamp
+    /// is converted to the configured timezone.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use ocptv::output::*;
+    /// let captured_at = chrono::DateTime::from_timestamp(1_700_000_000, 0)
+    ///     .unwrap()
+    ///     .with_timezone(&chrono_tz::UTC);
+    /// let log = Log::builder("fan0 spun up").timestamp(captured_at).build();
+    /// ```
+    pub fn timestamp(mut self, value: chrono::DateTime<chrono_tz::Tz>) -> Self {
+        self.timestamp = Some(value);
+        self
+    }
+
     pub fn build(self) -> Log {
         Log {
             severity: self.severity,
             message: self.message,
             source_location: self.source_location,
+            timestamp: self.timestamp,
         }
     }
 }
@@ -72,6 +72,54 @@ macro_rules! ocptv_error {
     };
 }
 
+/// Emit an artifact of type Measurement.
+///
+/// ref: <https://github.com/opencomputeproject/ocp-diag-core/tree/main/json_spec#measurement>
+///
+/// Equivalent to the [`crate::output::StartedTestStep::add_measurement_detail`] method.
+///
+/// It accepts the measurement name and value.
+/// Since the measurement spec has no source location field, the source file and line number
+/// are automatically added to the measurement metadata, under the `sourceLocation` key.
+///
+/// # Examples
+///
+/// ```rust
+/// # tokio_test::block_on(async {
+/// # use ocptv::output::*;
+/// use ocptv::ocptv_measurement;
+///
+/// let dut = DutInfo::new("my_dut");
+/// let run = TestRun::new("run_name", "1.0").start(dut).await?;
+///
+/// let step = run.add_step("step_name").start().await?;
+/// ocptv_measurement!(step, "fan_speed", 1600).await?;
+/// step.end(TestStatus::Complete).await?;
+///
+/// run.end(TestStatus::Complete, TestResult::Pass).await?;
+///
+/// # Ok::<(), OcptvError>(())
+/// # });
+/// ```
+#[macro_export]
+macro_rules! ocptv_measurement {
+    ($artifact:expr, $name:expr, $value:expr) => {
+        $artifact.add_measurement_detail(
+            $crate::output::Measurement::builder($name, $value)
+                .add_metadata(
+                    "sourceLocation",
+                    [
+                        ("file", $crate::output::Value::from(file!())),
+                        ("line", $crate::output::Value::from(line!())),
+                    ]
+                    .into_iter()
+                    .collect::<$crate::output::Value>(),
+                )
+                .build(),
+        )
+    };
+}
+
 macro_rules! ocptv_log {
     ($name:ident, $severity:path) => {
         /// Emit an artifact of type Log.
@@ -4,17 +4,36 @@
 // license that can be found in the LICENSE file or at
 // https://opensource.org/licenses/MIT.
 
-use std::collections::BTreeMap;
+use std::borrow::Cow;
 use std::future::Future;
 use std::sync::atomic::{self, Ordering};
 use std::sync::Arc;
+use std::time::Duration;
 
+use base64::prelude::*;
 use delegate::delegate;
+use serde_json::Map;
+use tokio::sync::Mutex;
+use tokio_stream::{Stream, StreamExt};
 
 use crate::output as tv;
+use crate::output::config::NonFinitePolicy;
 use crate::output::trait_ext::{MapExt, VecExt};
 use crate::spec;
-use tv::{dut, step, Ident};
+use tv::{dut, log, step, Ident};
+
+/// Metadata key describing how a non-native measurement value was encoded.
+const ENCODING_METADATA_KEY: &str = "encoding";
+
+/// Metadata key naming the logical group of a measurement.
+const GROUP_METADATA_KEY: &str = "group";
+
+/// Metadata keys of a measurement relative to a baseline.
+const BASELINE_METADATA_KEY: &str = "baseline";
+const DELTA_METADATA_KEY: &str = "delta";
+
+/// Metadata key of a series element holding whether it satisfies the series validators.
+const PASSED_METADATA_KEY: &str = "passed";
 
 /// The measurement series.
 /// A Measurement Series is a time-series list of measurements.
@@ -25,6 +44,8 @@ pub struct MeasurementSeries {
     detail: MeasurementSeriesDetail,
 
     emitter: Arc<step::StepEmitter>,
+    // element counter, shared by all the steps adding to a `RunMeasurementSeries`
+    seqno: Arc<atomic::AtomicU64>,
 }
 
 impl MeasurementSeries {
@@ -39,6 +60,7 @@ impl MeasurementSeries {
             id: series_id.to_owned(),
             detail: info,
             emitter,
+            seqno: Arc::new(atomic::AtomicU64::new(0)),
         }
     }
 
@@ -81,9 +103,21 @@ impl MeasurementSeries {
             .emit(&spec::TestStepArtifactImpl::MeasurementSeriesStart(start))
             .await?;
 
+        let stats = match self.detail.summary_stats {
+            true => Some(Mutex::new(SeriesStats::default())),
+            false => None,
+        };
+        let coalescer = self
+            .detail
+            .min_interval
+            .map(|interval| Mutex::new(Coalescer::new(interval, self.detail.coalesce_mode)));
+
         Ok(StartedMeasurementSeries {
+            seqno: Arc::clone(&self.seqno),
             parent: self,
-            seqno: Arc::new(atomic::AtomicU64::new(0)),
+            element_failed: atomic::AtomicBool::new(false),
+            stats,
+            coalescer,
         })
     }
 
@@ -138,6 +172,9 @@ pub struct StartedMeasurementSeries {
     parent: MeasurementSeries,
 
     seqno: Arc<atomic::AtomicU64>,
+    element_failed: atomic::AtomicBool,
+    stats: Option<Mutex<SeriesStats>>,
+    coalescer: Option<Mutex<Coalescer>>,
 }
 
 impl StartedMeasurementSeries {
@@ -148,9 +185,16 @@ impl StartedMeasurementSeries {
     // note: keep the self-consuming method for crate api, but use this one internally,
     // since `StartedMeasurementSeries::end` only needs to take ownership for syntactic reasons
     async fn end_impl(&self) -> Result<(), tv::OcptvError> {
+        if let Some(coalescer) = &self.coalescer {
+            if let Some((element, timestamp)) = coalescer.lock().await.flush() {
+                self.emit_element(element, timestamp).await?;
+            }
+        }
+
+        let total_count = self.seqno.load(Ordering::Acquire);
         let end = spec::MeasurementSeriesEnd {
             series_id: self.parent.id.clone(),
-            total_count: self.seqno.load(Ordering::Acquire),
+            total_count,
         };
 
         self.parent
@@ -158,6 +202,29 @@ impl StartedMeasurementSeries {
             .emit(&spec::TestStepArtifactImpl::MeasurementSeriesEnd(end))
             .await?;
 
+        if total_count == 0 && self.parent.detail.require_elements {
+            let log = log::Log::builder(&format!(
+                "measurement series '{}' ({}) ended without elements",
+                self.parent.detail.name, self.parent.id
+            ))
+            .severity(spec::LogSeverity::Warning)
+            .build();
+
+            self.parent
+                .emitter
+                .emit(&spec::TestStepArtifactImpl::Log(log.to_artifact()))
+                .await?;
+        }
+
+        if let Some(stats) = &self.stats {
+            if let Some(summary) = stats.lock().await.to_measurement(&self.parent) {
+                self.parent
+                    .emitter
+                    .emit(&spec::TestStepArtifactImpl::Measurement(summary))
+                    .await?;
+            }
+        }
+
         Ok(())
     }
 
@@ -203,7 +270,7 @@ impl StartedMeasurementSeries {
     /// # Ok::<(), OcptvError>(())
     /// # });
     /// ```
-    pub async fn add_measurement<V: Into<tv::Value>>(
+    pub async fn add_measurement<V: Into<MeasurementValue>>(
         &self,
         value: V,
     ) -> Result<(), tv::OcptvError> {
@@ -239,14 +306,116 @@ impl StartedMeasurementSeries {
         &self,
         element: MeasurementElementDetail,
     ) -> Result<(), tv::OcptvError> {
+        let timestamp = self.element_timestamp(&element);
+
+        match &self.coalescer {
+            Some(coalescer) => {
+                // keep the lock while emitting, so the elements stay in order
+                let mut coalescer = coalescer.lock().await;
+                if let Some((element, timestamp)) = coalescer.push(element, timestamp) {
+                    self.emit_element(element, timestamp).await?;
+                }
+                Ok(())
+            }
+            None => self.emit_element(element, timestamp).await,
+        }
+    }
+
+    /// Adds a measurement element with an explicit `index`, eg. when reconstructing a series
+    /// from sparse data. The following elements without an explicit index continue after the
+    /// highest index used so far, and the `totalCount` of the series is that index plus one,
+    /// so it counts the gaps as well.
+    ///
+    /// Nothing checks the indices: reusing an index, or going back below an index already
+    /// emitted, produces a series with duplicate or out of order indices. The element is
+    /// also never coalesced, see [`MeasurementSeriesDetailBuilder::min_interval`].
+    ///
+    /// ref: <https://github.com/opencomputeproject/ocp-diag-core/tree/main/json_spec#measurementserieselement>
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # tokio_test::block_on(async {
+    /// # use ocptv::output::*;
+    /// let dut = DutInfo::new("my_dut");
+    /// let run = TestRun::new("diagnostic_name", "1.0").start(dut).await?;
+    /// let step = run.add_step("step_name").start().await?;
+    ///
+    /// let series = step.add_measurement_series("name").start().await?;
+    /// series.add_measurement(60).await?;
+    /// // samples 1 to 4 were lost
+    /// series.add_measurement_at_index(5, 65).await?;
+    /// series.end().await?;
+    ///
+    /// # Ok::<(), OcptvError>(())
+    /// # });
+    /// ```
+    pub async fn add_measurement_at_index<V: Into<MeasurementValue>>(
+        &self,
+        index: u64,
+        value: V,
+    ) -> Result<(), tv::OcptvError> {
+        let element = MeasurementElementDetail {
+            value: value.into(),
+            ..Default::default()
+        };
+        let timestamp = self.element_timestamp(&element);
+
+        self.seqno
+            .fetch_max(index.saturating_add(1), Ordering::AcqRel);
+        self.emit_element_at(index, element, timestamp).await
+    }
+
+    fn element_timestamp(
+        &self,
+        element: &MeasurementElementDetail,
+    ) -> chrono::DateTime<chrono_tz::Tz> {
+        // explicit timestamps are converted to the configured timezone, so that all the
+        // timestamps in the output use the same offset
+        let now = self.parent.emitter.timestamp_provider().now();
+        element
+            .timestamp
+            .map_or(now, |ts| ts.with_timezone(&now.timezone()))
+    }
+
+    async fn emit_element(
+        &self,
+        element: MeasurementElementDetail,
+        timestamp: chrono::DateTime<chrono_tz::Tz>,
+    ) -> Result<(), tv::OcptvError> {
+        self.emit_element_at(self.incr_seqno(), element, timestamp)
+            .await
+    }
+
+    async fn emit_element_at(
+        &self,
+        index: u64,
+        element: MeasurementElementDetail,
+        timestamp: chrono::DateTime<chrono_tz::Tz>,
+    ) -> Result<(), tv::OcptvError> {
+        // only observe the emitted elements, ie. after coalescing, so the summary agrees
+        // with the `totalCount` of the series
+        if let Some(stats) = &self.stats {
+            stats.lock().await.observe(&element.value.json);
+        }
+
+        let mut metadata = element.metadata;
+        let validators = &self.parent.detail.validators;
+        if !validators.is_empty() {
+            let passed = validators.iter().all(|v| v.evaluate(&element.value.json));
+            if !passed {
+                self.element_failed.store(true, Ordering::Release);
+            }
+            metadata.insert(PASSED_METADATA_KEY.to_owned(), passed.into());
+        }
+
+        let policy = self.parent.emitter.non_finite_policy();
         let element = spec::MeasurementSeriesElement {
-            index: self.incr_seqno(),
-            value: element.value,
-            timestamp: element
-                .timestamp
-                .unwrap_or(self.parent.emitter.timestamp_provider().now()),
+            index,
+            value: element.value.to_value(policy),
+            timestamp,
             series_id: self.parent.id.clone(),
-            metadata: element.metadata.option(),
+            metadata: metadata.option(),
         };
 
         self.parent
@@ -258,6 +427,211 @@ impl StartedMeasurementSeries {
 
         Ok(())
     }
+
+    /// Returns whether all the elements emitted so far satisfy the series validators,
+    /// see [`MeasurementSeriesDetailBuilder::add_validator`]. Always true for a series
+    /// without validators.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # tokio_test::block_on(async {
+    /// # use ocptv::output::*;
+    /// let dut = DutInfo::new("my_dut");
+    /// let run = TestRun::new("diagnostic_name", "1.0").start(dut).await?;
+    /// let step = run.add_step("step_name").start().await?;
+    ///
+    /// let detail = MeasurementSeriesDetail::builder("temperature")
+    ///     .add_validator(Validator::builder(ValidatorType::LessThan, 80).build())
+    ///     .build();
+    /// let series = step.add_measurement_series_detail(detail).start().await?;
+    /// series.add_measurement(60).await?;
+    /// series.add_measurement(95).await?;
+    /// assert!(!series.all_elements_passed());
+    /// series.end().await?;
+    ///
+    /// # Ok::<(), OcptvError>(())
+    /// # });
+    /// ```
+    pub fn all_elements_passed(&self) -> bool {
+        !self.element_failed.load(Ordering::Acquire)
+    }
+
+    /// Drains `stream`, adding each item as a measurement element, in order. Returns when
+    /// the stream ends, leaving the series open, so more elements can still be added
+    /// before calling [`StartedMeasurementSeries::end`].
+    ///
+    /// Useful for sensors exposed as an async stream of readings.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # tokio_test::block_on(async {
+    /// # use ocptv::output::*;
+    /// let dut = DutInfo::new("my_dut");
+    /// let run = TestRun::new("diagnostic_name", "1.0").start(dut).await?;
+    /// let step = run.add_step("step_name").start().await?;
+    ///
+    /// let series = step.add_measurement_series("fan_speed").start().await?;
+    /// let readings = futures::stream::iter([1000.0, 1200.0, 1100.0]);
+    /// series.consume_stream(readings).await?;
+    /// series.end().await?;
+    ///
+    /// # Ok::<(), OcptvError>(())
+    /// # });
+    /// ```
+    pub async fn consume_stream<S, V>(&self, stream: S) -> Result<(), tv::OcptvError>
+    where
+        S: Stream<Item = V>,
+        V: Into<MeasurementValue>,
+    {
+        let mut stream = std::pin::pin!(stream);
+        while let Some(value) = stream.next().await {
+            self.add_measurement(value).await?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Running statistics over the numeric elements of a series, see
+/// [`MeasurementSeriesDetailBuilder::with_summary_stats`].
+#[derive(Default)]
+struct SeriesStats {
+    count: u64,
+    sum: f64,
+    min: Option<(f64, tv::Value)>,
+    max: Option<(f64, tv::Value)>,
+    non_numeric: bool,
+}
+
+impl SeriesStats {
+    fn observe(&mut self, value: &tv::Value) {
+        let Some(x) = value.as_f64() else {
+            self.non_numeric = true;
+            return;
+        };
+
+        self.count += 1;
+        self.sum += x;
+        // keep the original values, so integer series report integer extremes
+        let (new_min, new_max) = match (&self.min, &self.max) {
+            (Some((min, _)), Some((max, _))) => (x < *min, x > *max),
+            _ => (true, true),
+        };
+        if new_min {
+            self.min = Some((x, value.clone()));
+        }
+        if new_max {
+            self.max = Some((x, value.clone()));
+        }
+    }
+
+    fn to_measurement(&self, series: &MeasurementSeries) -> Option<spec::Measurement> {
+        if self.non_numeric {
+            return None;
+        }
+        let (_, min) = self.min.as_ref()?;
+        let (_, max) = self.max.as_ref()?;
+
+        let detail = &series.detail;
+        let mean = self.sum / self.count as f64;
+
+        let mut metadata = Map::new();
+        metadata.insert("measurementSeriesId".to_owned(), series.id.clone().into());
+        metadata.insert("count".to_owned(), self.count.into());
+        metadata.insert("min".to_owned(), min.clone());
+        metadata.insert("max".to_owned(), max.clone());
+        metadata.insert("mean".to_owned(), mean.into());
+
+        Some(spec::Measurement {
+            name: format!("{}_summary", detail.name),
+            unit: detail.unit.clone(),
+            value: mean.into(),
+            validators: None,
+            hardware_info: detail
+                .hardware_info
+                .as_ref()
+                .map(dut::DutHardwareInfo::to_spec),
+            subcomponent: detail.subcomponent.as_ref().map(dut::Subcomponent::to_spec),
+            metadata: Some(metadata),
+        })
+    }
+}
+
+/// How the elements of a rate limited series are combined, see
+/// [`MeasurementSeriesDetailBuilder::min_interval`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum CoalesceMode {
+    /// Emit the latest element of each interval, dropping the others.
+    #[default]
+    Latest,
+    /// Emit the mean of the values in each interval, with the timestamp and metadata
+    /// of the latest element. If any value is not a number, the latest one is used instead.
+    Average,
+}
+
+/// Groups the elements of a series into consecutive intervals, each starting at the
+/// first element that didn't fit the previous one, and keeps one element per interval.
+struct Coalescer {
+    interval: chrono::Duration,
+    mode: CoalesceMode,
+
+    interval_start: Option<chrono::DateTime<chrono_tz::Tz>>,
+    latest: Option<(MeasurementElementDetail, chrono::DateTime<chrono_tz::Tz>)>,
+    values: Vec<tv::Value>,
+}
+
+impl Coalescer {
+    fn new(interval: Duration, mode: CoalesceMode) -> Self {
+        Coalescer {
+            interval: chrono::Duration::from_std(interval).unwrap_or(chrono::Duration::max_value()),
+            mode,
+            interval_start: None,
+            latest: None,
+            values: vec![],
+        }
+    }
+
+    /// Adds an element, returning the element to emit for the previous interval, if
+    /// this one starts a new interval.
+    fn push(
+        &mut self,
+        element: MeasurementElementDetail,
+        timestamp: chrono::DateTime<chrono_tz::Tz>,
+    ) -> Option<(MeasurementElementDetail, chrono::DateTime<chrono_tz::Tz>)> {
+        let flushed = match self.interval_start {
+            Some(start) if timestamp - start < self.interval => None,
+            _ => {
+                self.interval_start = Some(timestamp);
+                self.flush()
+            }
+        };
+
+        if self.mode == CoalesceMode::Average {
+            self.values.push(element.value.json.clone());
+        }
+        self.latest = Some((element, timestamp));
+        flushed
+    }
+
+    /// Returns the element to emit for the current interval, if it has any elements.
+    fn flush(&mut self) -> Option<(MeasurementElementDetail, chrono::DateTime<chrono_tz::Tz>)> {
+        let (mut element, timestamp) = self.latest.take()?;
+        let values = std::mem::take(&mut self.values);
+
+        if self.mode == CoalesceMode::Average {
+            let numbers = values
+                .iter()
+                .map(tv::Value::as_f64)
+                .collect::<Option<Vec<_>>>();
+            if let Some(numbers) = numbers {
+                element.value = (numbers.iter().sum::<f64>() / numbers.len() as f64).into();
+            }
+        }
+        Some((element, timestamp))
+    }
 }
 
 /// TODO: docs
@@ -268,41 +642,297 @@ pub struct ScopedMeasurementSeries {
 impl ScopedMeasurementSeries {
     delegate! {
         to self.series {
-            pub async fn add_measurement<V: Into<tv::Value>>(&self, value: V) -> Result<(), tv::OcptvError>;
+            pub async fn add_measurement<V: Into<MeasurementValue>>(&self, value: V) -> Result<(), tv::OcptvError>;
             pub async fn add_measurement_detail(
                 &self,
                 element: MeasurementElementDetail,
             ) -> Result<(), tv::OcptvError>;
+            pub async fn add_measurement_at_index<V: Into<MeasurementValue>>(
+                &self,
+                index: u64,
+                value: V,
+            ) -> Result<(), tv::OcptvError>;
+            pub async fn consume_stream<S, V>(&self, stream: S) -> Result<(), tv::OcptvError>
+            where
+                S: Stream<Item = V>,
+                V: Into<MeasurementValue>;
+            pub fn all_elements_passed(&self) -> bool;
         }
     }
 }
 
+/// A measurement series in the scope of the test run, for a phenomenon spanning several
+/// steps. Created by [`tv::StartedTestRun::add_measurement_series`], and added to each
+/// step with [`tv::StartedTestStep::add_run_measurement_series`].
+///
+/// Series artifacts always belong to a step, so each step adding elements emits its own
+/// `measurementSeriesStart` and `measurementSeriesEnd`, all with the same series id. The
+/// element indices continue across the steps, and the `totalCount` of each end counts
+/// all the elements of the series added so far, in any step. Steps adding to the series
+/// concurrently get interleaved indices, so consumers should order the elements by index
+/// rather than by step. The options applied per element group, eg. summary stats or
+/// coalescing, apply to each step separately.
+///
+/// The handle is cheap to clone, eg. to move into spawned steps.
+#[derive(Clone)]
+pub struct RunMeasurementSeries {
+    id: String,
+    detail: MeasurementSeriesDetail,
+
+    seqno: Arc<atomic::AtomicU64>,
+}
+
+impl RunMeasurementSeries {
+    // note: this object is crate public but users should only construct
+    // instances through the `StartedTestRun.add_measurement_series_*` apis
+    pub(crate) fn new(series_id: &str, info: MeasurementSeriesDetail) -> Self {
+        RunMeasurementSeries {
+            id: series_id.to_owned(),
+            detail: info,
+            seqno: Arc::new(atomic::AtomicU64::new(0)),
+        }
+    }
+
+    /// The series id, the same in all the steps.
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub(crate) fn in_step(&self, emitter: Arc<step::StepEmitter>) -> MeasurementSeries {
+        MeasurementSeries {
+            id: self.id.clone(),
+            detail: self.detail.clone(),
+            emitter,
+            seqno: Arc::clone(&self.seqno),
+        }
+    }
+}
+
+/// Primitive numeric types accepted by [`Measurement::from_number`].
+pub trait MeasurementNumber {
+    fn into_measurement(self, name: &str) -> Measurement;
+}
+
+macro_rules! impl_measurement_number_int {
+    ($($t:ty),*) => {
+        $(
+            impl MeasurementNumber for $t {
+                fn into_measurement(self, name: &str) -> Measurement {
+                    Measurement::new(name, tv::Value::from(self))
+                }
+            }
+        )*
+    };
+}
+
+impl_measurement_number_int!(i8, i16, i32, i64, isize, u8, u16, u32, u64, usize);
+
+impl MeasurementNumber for f32 {
+    fn into_measurement(self, name: &str) -> Measurement {
+        Measurement::float(name, f64::from(self))
+    }
+}
+
+impl MeasurementNumber for f64 {
+    fn into_measurement(self, name: &str) -> Measurement {
+        Measurement::float(name, self)
+    }
+}
+
+/// Unit of a [`Quantity`], rendered with its conventional symbol.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Unit {
+    /// Revolutions per minute, `RPM`
+    Rpm,
+    /// Degrees Celsius, `C`
+    Celsius,
+    /// `V`
+    Volt,
+    /// `A`
+    Ampere,
+    /// `W`
+    Watt,
+    /// `Hz`
+    Hertz,
+    /// `s`
+    Second,
+    /// `B`
+    Byte,
+    /// `%`
+    Percent,
+    /// Any other unit, rendered as is
+    Other(String),
+}
+
+impl Unit {
+    pub fn as_str(&self) -> &str {
+        match self {
+            Unit::Rpm => "RPM",
+            Unit::Celsius => "C",
+            Unit::Volt => "V",
+            Unit::Ampere => "A",
+            Unit::Watt => "W",
+            Unit::Hertz => "Hz",
+            Unit::Second => "s",
+            Unit::Byte => "B",
+            Unit::Percent => "%",
+            Unit::Other(unit) => unit,
+        }
+    }
+}
+
+/// A numeric value along with its unit, see [`Measurement::quantity`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Quantity {
+    pub value: f64,
+    pub unit: Unit,
+}
+
+impl Quantity {
+    pub fn new(value: f64, unit: Unit) -> Self {
+        Quantity { value, unit }
+    }
+}
+
+/// Value of a measurement or of a measurement series element.
+///
+/// Converts from the same types as a json [`tv::Value`], but keeps the NaN and infinite
+/// floats, which json has no representation for, so they can be serialized as configured
+/// with [`tv::ConfigBuilder::non_finite_policy`].
+///
+/// # Examples
+///
+/// ```rust
+/// # use ocptv::output::*;
+/// let value = MeasurementValue::from(f64::NAN);
+/// assert_eq!(value.to_value(NonFinitePolicy::Null), Value::Null);
+/// assert_eq!(value.to_value(NonFinitePolicy::Sentinel), "NaN");
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MeasurementValue {
+    // null for NaN and infinities, which are kept in `non_finite` instead
+    json: tv::Value,
+    non_finite: Option<f64>,
+}
+
+impl MeasurementValue {
+    /// Converts the value to json, serializing NaN and infinities according to `policy`.
+    pub fn to_value(&self, policy: NonFinitePolicy) -> tv::Value {
+        match self.non_finite {
+            Some(value) => policy.to_value(value),
+            None => self.json.clone(),
+        }
+    }
+}
+
+impl From<f64> for MeasurementValue {
+    fn from(value: f64) -> Self {
+        MeasurementValue {
+            json: value.into(),
+            non_finite: (!value.is_finite()).then_some(value),
+        }
+    }
+}
+
+impl From<f32> for MeasurementValue {
+    fn from(value: f32) -> Self {
+        MeasurementValue::from(f64::from(value))
+    }
+}
+
+macro_rules! impl_measurement_value_from {
+    ($($t:ty),*) => {
+        $(
+            impl From<$t> for MeasurementValue {
+                fn from(value: $t) -> Self {
+                    MeasurementValue {
+                        json: value.into(),
+                        non_finite: None,
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_measurement_value_from!(
+    bool,
+    i8,
+    i16,
+    i32,
+    i64,
+    isize,
+    u8,
+    u16,
+    u32,
+    u64,
+    usize,
+    String,
+    &str,
+    Cow<'_, str>,
+    (),
+    serde_json::Number,
+    Map<String, tv::Value>,
+    tv::Value
+);
+
+impl<T: Into<tv::Value>> From<Vec<T>> for MeasurementValue {
+    fn from(value: Vec<T>) -> Self {
+        tv::Value::from(value).into()
+    }
+}
+
+impl<T: Clone + Into<tv::Value>> From<&[T]> for MeasurementValue {
+    fn from(value: &[T]) -> Self {
+        tv::Value::from(value).into()
+    }
+}
+
+impl<T: Into<tv::Value>> From<Option<T>> for MeasurementValue {
+    fn from(value: Option<T>) -> Self {
+        tv::Value::from(value).into()
+    }
+}
+
 /// TODO: docs
-#[derive(Default)]
+#[derive(Debug, Clone, Default)]
 pub struct MeasurementElementDetail {
-    value: tv::Value,
+    value: MeasurementValue,
     timestamp: Option<chrono::DateTime<chrono_tz::Tz>>,
 
-    metadata: BTreeMap<String, tv::Value>,
+    metadata: Map<String, tv::Value>,
 }
 
 impl MeasurementElementDetail {
-    pub fn builder<V: Into<tv::Value>>(value: V) -> MeasurementElementDetailBuilder {
+    pub fn builder<V: Into<MeasurementValue>>(value: V) -> MeasurementElementDetailBuilder {
         MeasurementElementDetailBuilder::new(value.into())
     }
+
+    // rebuilds the element from an emitted artifact, see `tv::RecordingWriter`
+    pub(crate) fn from_spec(element: spec::MeasurementSeriesElement) -> Self {
+        MeasurementElementDetail {
+            value: MeasurementValue {
+                json: element.value,
+                non_finite: None,
+            },
+            timestamp: Some(element.timestamp),
+            metadata: element.metadata.unwrap_or_default(),
+        }
+    }
 }
 
 /// TODO: docs
 #[derive(Default)]
 pub struct MeasurementElementDetailBuilder {
-    value: tv::Value,
+    value: MeasurementValue,
     timestamp: Option<chrono::DateTime<chrono_tz::Tz>>,
 
-    metadata: BTreeMap<String, tv::Value>,
+    metadata: Map<String, tv::Value>,
 }
 
 impl MeasurementElementDetailBuilder {
-    fn new(value: tv::Value) -> Self {
+    fn new(value: MeasurementValue) -> Self {
         Self {
             value,
             ..Default::default()
@@ -328,13 +958,20 @@ impl MeasurementElementDetailBuilder {
     }
 }
 
+/// Metadata key holding the limit description set by [`ValidatorBuilder::description`].
+pub const VALIDATOR_DESCRIPTION_METADATA_KEY: &str = "description";
+
 /// TODO: docs
-#[derive(Clone)]
+#[derive(Debug, Clone)]
 pub struct Validator {
     name: Option<String>,
     validator_type: spec::ValidatorType,
     value: tv::Value,
-    metadata: BTreeMap<String, tv::Value>,
+    metadata: Map<String, tv::Value>,
+
+    // validators combined with `Validator::all_of`, already flattened; when set, this
+    // validator has no spec object of its own and the fields above are unused
+    all_of: Option<Vec<Validator>>,
 }
 
 impl Validator {
@@ -345,7 +982,114 @@ impl Validator {
         ValidatorBuilder::new(validator_type, value.into())
     }
 
+    /// Combines `validators` into one that passes when all of them pass, eg. for a range.
+    ///
+    /// The spec requires all the validators of a measurement to pass, so when added to a
+    /// measurement or series this is emitted as the separate spec validators it combines.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use ocptv::output::*;
+    /// let range = Validator::all_of(vec![
+    ///     Validator::builder(ValidatorType::GreaterThan, 0).build(),
+    ///     Validator::builder(ValidatorType::LessThan, 100).build(),
+    /// ]);
+    /// assert!(range.evaluate(&Value::from(60)));
+    /// assert!(!range.evaluate(&Value::from(120)));
+    ///
+    /// let measurement = Measurement::builder("fan_speed", 60)
+    ///     .add_validator(range)
+    ///     .build();
+    /// assert_eq!(measurement.to_artifact().validators.map(|v| v.len()), Some(2));
+    /// ```
+    pub fn all_of(validators: Vec<Validator>) -> Self {
+        Validator {
+            name: None,
+            validator_type: spec::ValidatorType::Equal,
+            value: tv::Value::Null,
+            metadata: Map::new(),
+            all_of: Some(validators.iter().flat_map(Validator::flatten).collect()),
+        }
+    }
+
+    // the simple validators emitted for this one, see `Validator::all_of`
+    fn flatten(&self) -> Vec<Validator> {
+        match &self.all_of {
+            Some(validators) => validators.clone(),
+            None => vec![self.clone()],
+        }
+    }
+
+    pub(crate) fn from_spec(validator: spec::Validator) -> Self {
+        Validator {
+            name: validator.name,
+            validator_type: validator.validator_type,
+            value: validator.value,
+            metadata: validator.metadata.unwrap_or_default(),
+            all_of: None,
+        }
+    }
+
+    /// Checks whether `value` satisfies this validator.
+    ///
+    /// Comparisons need both values to be numbers, and regex validators need both to be
+    /// strings; on mismatched types, or an invalid regex, the validator fails.
+    /// The set validators expect the validator value to be an array.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use ocptv::output::*;
+    /// let validator = Validator::builder(ValidatorType::LessThan, 90).build();
+    /// assert!(validator.evaluate(&Value::from(60)));
+    /// assert!(!validator.evaluate(&Value::from(95.5)));
+    /// ```
+    pub fn evaluate(&self, value: &tv::Value) -> bool {
+        use spec::ValidatorType as VT;
+
+        if let Some(validators) = &self.all_of {
+            return validators.iter().all(|v| v.evaluate(value));
+        }
+
+        let compare = |op: fn(f64, f64) -> bool| match (value.as_f64(), self.value.as_f64()) {
+            (Some(lhs), Some(rhs)) => op(lhs, rhs),
+            _ => false,
+        };
+        let is_match = || match (value.as_str(), self.value.as_str()) {
+            (Some(s), Some(pattern)) => regex::Regex::new(pattern).ok().map(|re| re.is_match(s)),
+            _ => None,
+        };
+        let in_set = || {
+            self.value
+                .as_array()
+                .map(|set| set.iter().any(|item| values_equal(item, value)))
+        };
+
+        match self.validator_type {
+            VT::Equal => values_equal(value, &self.value),
+            VT::NotEqual => !values_equal(value, &self.value),
+            VT::LessThan => compare(|lhs, rhs| lhs < rhs),
+            VT::LessThanOrEqual => compare(|lhs, rhs| lhs <= rhs),
+            VT::GreaterThan => compare(|lhs, rhs| lhs > rhs),
+            VT::GreaterThanOrEqual => compare(|lhs, rhs| lhs >= rhs),
+            VT::RegexMatch => is_match() == Some(true),
+            VT::RegexNoMatch => is_match() == Some(false),
+            VT::InSet => in_set() == Some(true),
+            VT::NotInSet => in_set() == Some(false),
+        }
+    }
+
+    /// # Panics
+    ///
+    /// Panics for a validator made with [`Validator::all_of`], which has no single spec
+    /// object; the measurement builders add the validators it combines instead.
     pub fn to_spec(&self) -> spec::Validator {
+        assert!(
+            self.all_of.is_none(),
+            "validators combined with `all_of` are emitted separately"
+        );
+
         spec::Validator {
             name: self.name.clone(),
             validator_type: self.validator_type.clone(),
@@ -355,6 +1099,14 @@ impl Validator {
     }
 }
 
+// numbers compare by value, so that eg. `30` and `30.0` are equal
+fn values_equal(lhs: &tv::Value, rhs: &tv::Value) -> bool {
+    match (lhs.as_f64(), rhs.as_f64()) {
+        (Some(lhs), Some(rhs)) => lhs == rhs,
+        _ => lhs == rhs,
+    }
+}
+
 /// TODO: docs
 #[derive(Debug)]
 pub struct ValidatorBuilder {
@@ -362,7 +1114,7 @@ pub struct ValidatorBuilder {
     validator_type: spec::ValidatorType,
     value: tv::Value,
 
-    metadata: BTreeMap<String, tv::Value>,
+    metadata: Map<String, tv::Value>,
 }
 
 impl ValidatorBuilder {
@@ -371,7 +1123,7 @@ impl ValidatorBuilder {
             validator_type,
             value,
             name: None,
-            metadata: BTreeMap::new(),
+            metadata: Map::new(),
         }
     }
 
@@ -385,12 +1137,30 @@ impl ValidatorBuilder {
         self
     }
 
+    /// Describes where the limit comes from, eg. a datasheet page or a spec clause, for
+    /// the people reviewing the results.
+    /// The spec `validator` object has no field for it, so it's emitted in the metadata,
+    /// under the [`VALIDATOR_DESCRIPTION_METADATA_KEY`] key.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use ocptv::output::*;
+    /// let validator = Validator::builder(ValidatorType::LessThan, 85)
+    ///     .description("max junction temperature, datasheet rev C p. 12")
+    ///     .build();
+    /// ```
+    pub fn description(self, description: &str) -> Self {
+        self.add_metadata(VALIDATOR_DESCRIPTION_METADATA_KEY, description)
+    }
+
     pub fn build(self) -> Validator {
         Validator {
             name: self.name,
             validator_type: self.validator_type,
             value: self.value,
             metadata: self.metadata,
+            all_of: None,
         }
     }
 }
@@ -421,37 +1191,135 @@ impl ValidatorBuilder {
 ///     .subcomponent(Subcomponent::builder("name").build())
 ///     .build();
 /// ```
-#[derive(Default)]
+#[derive(Debug, Clone, Default)]
 pub struct Measurement {
     name: String,
 
-    value: tv::Value,
+    value: MeasurementValue,
     unit: Option<String>,
     validators: Vec<Validator>,
 
     hardware_info: Option<dut::DutHardwareInfo>,
     subcomponent: Option<dut::Subcomponent>,
 
-    metadata: BTreeMap<String, tv::Value>,
-}
+    metadata: Map<String, tv::Value>,
+}
+
+impl Measurement {
+    /// Builds a new Measurement object.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ocptv::output::*;
+    /// let measurement = Measurement::new("name", 50);
+    /// ```
+    pub fn new<V: Into<MeasurementValue>>(name: &str, value: V) -> Self {
+        Measurement {
+            name: name.to_string(),
+            value: value.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Builds a new Measurement object for a boolean value, eg. a pass/fail flag.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ocptv::output::*;
+    /// let measurement = Measurement::boolean("link_up", true);
+    /// ```
+    pub fn boolean(name: &str, value: bool) -> Self {
+        Measurement::new(name, value)
+    }
+
+    /// Builds a new Measurement object for an integer value, always serialized as an
+    /// integer json number, eg. `50`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ocptv::output::*;
+    /// let measurement = Measurement::int("fan_count", 4);
+    /// ```
+    pub fn int(name: &str, value: i64) -> Self {
+        Measurement::new(name, tv::Value::from(value))
+    }
+
+    /// Builds a new Measurement object for a floating point value, always serialized as a
+    /// float json number, so integer-valued floats keep the decimal point, eg. `50.0`.
+    /// Json has no representation for NaN and infinities, so these are serialized as `null`,
+    /// unless configured otherwise with [`tv::ConfigBuilder::non_finite_policy`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ocptv::output::*;
+    /// let measurement = Measurement::float("voltage", 12.0);
+    /// ```
+    pub fn float(name: &str, value: f64) -> Self {
+        Measurement::new(name, value)
+    }
+
+    /// Builds a new Measurement object from any primitive numeric type, eg. a `u16`
+    /// sensor reading, without casting it first. Integers are serialized as integer
+    /// json numbers and floats as in [`Measurement::float`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ocptv::output::*;
+    /// let reading: u16 = 1600;
+    /// let measurement = Measurement::from_number("fan_speed", reading);
+    /// ```
+    pub fn from_number<N: MeasurementNumber>(name: &str, value: N) -> Self {
+        value.into_measurement(name)
+    }
 
-impl Measurement {
-    /// Builds a new Measurement object.
+    /// Builds a new Measurement object from a [`Quantity`], setting both the value and the
+    /// unit, so the value can't be emitted without its unit by mistake.
     ///
     /// # Examples
     ///
     /// ```
     /// # use ocptv::output::*;
-    /// let measurement = Measurement::new("name", 50);
+    /// let measurement = Measurement::quantity("fan_speed", Quantity::new(1200.0, Unit::Rpm));
     /// ```
-    pub fn new<V: Into<tv::Value>>(name: &str, value: V) -> Self {
+    pub fn quantity(name: &str, quantity: Quantity) -> Self {
         Measurement {
-            name: name.to_string(),
-            value: value.into(),
-            ..Default::default()
+            unit: Some(quantity.unit.as_str().to_owned()),
+            ..Measurement::float(name, quantity.value)
         }
     }
 
+    /// Builds a new Measurement object for a binary blob.
+    /// The bytes are base64-encoded into a string value, and the `encoding` metadata
+    /// key is set to `"base64"` so consumers know how to decode it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ocptv::output::*;
+    /// let measurement = Measurement::bytes("eeprom", &[0xde, 0xad, 0xbe, 0xef]);
+    /// ```
+    pub fn bytes(name: &str, value: &[u8]) -> Self {
+        Measurement::builder(name, BASE64_STANDARD.encode(value))
+            .add_metadata(ENCODING_METADATA_KEY, "base64")
+            .build()
+    }
+
+    // used for measurements added through a `tv::MeasurementGroup`
+    pub(crate) fn set_group(&mut self, group: &str) {
+        self.metadata
+            .insert(GROUP_METADATA_KEY.to_owned(), group.into());
+    }
+
+    // whether the value satisfies all the validators, see `Validator::evaluate`
+    pub(crate) fn passes_validators(&self) -> bool {
+        self.validators.iter().all(|v| v.evaluate(&self.value.json))
+    }
+
     /// Builds a new Measurement object using [`MeasurementBuilder`].
     ///
     /// # Examples
@@ -469,7 +1337,7 @@ impl Measurement {
     ///     .subcomponent(Subcomponent::builder("name").build())
     ///     .build();
     /// ```
-    pub fn builder<V: Into<tv::Value>>(name: &str, value: V) -> MeasurementBuilder {
+    pub fn builder<V: Into<MeasurementValue>>(name: &str, value: V) -> MeasurementBuilder {
         MeasurementBuilder::new(name, value.into())
     }
 
@@ -483,10 +1351,15 @@ impl Measurement {
     /// let _ = measurement.to_artifact();
     /// ```
     pub fn to_artifact(&self) -> spec::Measurement {
+        self.to_artifact_with(NonFinitePolicy::default())
+    }
+
+    // serializes NaN and infinite values according to `policy`, see `MeasurementValue`
+    pub(crate) fn to_artifact_with(&self, policy: NonFinitePolicy) -> spec::Measurement {
         spec::Measurement {
             name: self.name.clone(),
             unit: self.unit.clone(),
-            value: self.value.clone(),
+            value: self.value.to_value(policy),
             validators: self.validators.map_option(Validator::to_spec),
             hardware_info: self
                 .hardware_info
@@ -499,6 +1372,29 @@ impl Measurement {
             metadata: self.metadata.option(),
         }
     }
+
+    // rebuilds the measurement from an emitted artifact, see `tv::RecordingWriter`
+    pub(crate) fn from_spec(measurement: spec::Measurement) -> Self {
+        Measurement {
+            name: measurement.name,
+            value: MeasurementValue {
+                json: measurement.value,
+                non_finite: None,
+            },
+            unit: measurement.unit,
+            validators: measurement
+                .validators
+                .unwrap_or_default()
+                .into_iter()
+                .map(Validator::from_spec)
+                .collect(),
+            hardware_info: measurement
+                .hardware_info
+                .map(dut::DutHardwareInfo::from_spec),
+            subcomponent: measurement.subcomponent.map(dut::Subcomponent::from_spec),
+            metadata: measurement.metadata.unwrap_or_default(),
+        }
+    }
 }
 
 /// This structure builds a [`Measurement`] object.
@@ -521,18 +1417,18 @@ impl Measurement {
 pub struct MeasurementBuilder {
     name: String,
 
-    value: tv::Value,
+    value: MeasurementValue,
     unit: Option<String>,
     validators: Vec<Validator>,
 
     hardware_info: Option<dut::DutHardwareInfo>,
     subcomponent: Option<dut::Subcomponent>,
 
-    metadata: BTreeMap<String, tv::Value>,
+    metadata: Map<String, tv::Value>,
 }
 
 impl MeasurementBuilder {
-    fn new(name: &str, value: tv::Value) -> Self {
+    fn new(name: &str, value: MeasurementValue) -> Self {
         MeasurementBuilder {
             name: name.to_string(),
             value,
@@ -550,7 +1446,26 @@ impl MeasurementBuilder {
     ///     .add_validator(Validator::builder(ValidatorType::Equal, 30).build());
     /// ```
     pub fn add_validator(mut self, validator: Validator) -> Self {
-        self.validators.push(validator.clone());
+        self.validators.extend(validator.flatten());
+        self
+    }
+
+    /// Add a slice of [`Validator`]s to a [`MeasurementBuilder`].
+    /// Repeated calls accumulate, preserving the order in which validators were added.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ocptv::output::*;
+    /// let validators = [
+    ///     Validator::builder(ValidatorType::GreaterThan, 10).build(),
+    ///     Validator::builder(ValidatorType::LessThan, 90).build(),
+    /// ];
+    /// let builder = Measurement::builder("name", 50).add_validators(&validators);
+    /// ```
+    pub fn add_validators(mut self, validators: &[Validator]) -> Self {
+        self.validators
+            .extend(validators.iter().flat_map(Validator::flatten));
         self
     }
 
@@ -586,6 +1501,7 @@ impl MeasurementBuilder {
     }
 
     /// Add custom metadata to a [`MeasurementBuilder`].
+    /// Metadata keys are emitted in insertion order.
     ///
     /// # Examples
     ///
@@ -599,6 +1515,85 @@ impl MeasurementBuilder {
         self
     }
 
+    /// Adds all the metadata entries of `entries` to a [`MeasurementBuilder`], in order,
+    /// eg. from a map. An entry replaces any previous value of the same key, same as
+    /// [`MeasurementBuilder::add_metadata`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::collections::BTreeMap;
+    /// # use ocptv::output::*;
+    /// let labels = BTreeMap::from([("rack", "r12"), ("slot", "4")]);
+    /// let builder = Measurement::builder("name", 50)
+    ///     .add_metadata_iter(labels)
+    ///     .add_metadata("key", "value");
+    /// ```
+    pub fn add_metadata_iter<I, K, V>(mut self, entries: I) -> Self
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: Into<String>,
+        V: Into<tv::Value>,
+    {
+        self.metadata.extend(
+            entries
+                .into_iter()
+                .map(|(key, value)| (key.into(), value.into())),
+        );
+        self
+    }
+
+    /// Tags the measurement as part of a logical group, eg. `"voltage_rails"`.
+    /// The spec has no field for this, so the group is emitted in the `group` metadata key.
+    /// See also [`tv::StartedTestStep::measurement_group`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ocptv::output::*;
+    /// let measurement = Measurement::builder("rail_12v", 12.1)
+    ///     .group("voltage_rails")
+    ///     .build();
+    /// ```
+    pub fn group(self, group: &str) -> Self {
+        self.add_metadata(GROUP_METADATA_KEY, group)
+    }
+
+    /// Relates the measurement to a named baseline, eg. the same reading before a stress
+    /// test. The measurement value is unchanged; the baseline is emitted in the `baseline`
+    /// metadata key, as `{"name": .., "value": ..}`, and the difference between the value
+    /// and the baseline in the `delta` key. The delta is only emitted if both are numbers,
+    /// and is an integer if both are integers.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ocptv::output::*;
+    /// let measurement = Measurement::builder("fan_speed", 1250)
+    ///     .relative_to("fan_speed_idle", 900)
+    ///     .build();
+    /// ```
+    pub fn relative_to<V: Into<tv::Value>>(self, baseline_name: &str, baseline_value: V) -> Self {
+        let baseline = baseline_value.into();
+        let delta = match (self.value.json.as_i64(), baseline.as_i64()) {
+            (Some(value), Some(base)) => value.checked_sub(base).map(tv::Value::from),
+            _ => None,
+        }
+        .or_else(|| match (self.value.json.as_f64(), baseline.as_f64()) {
+            (Some(value), Some(base)) => Some(tv::Value::from(value - base)),
+            _ => None,
+        });
+
+        let builder = self.add_metadata(
+            BASELINE_METADATA_KEY,
+            serde_json::json!({ "name": baseline_name, "value": baseline }),
+        );
+        match delta {
+            Some(delta) => builder.add_metadata(DELTA_METADATA_KEY, delta),
+            None => builder,
+        }
+    }
+
     /// Add measurement unit to a [`MeasurementBuilder`].
     ///
     /// # Examples
@@ -635,6 +1630,7 @@ impl MeasurementBuilder {
 }
 
 /// TODO: docs
+#[derive(Clone)]
 pub struct MeasurementSeriesDetail {
     // note: this object is crate public and we need access to this field
     // when making a new series in `StartedTestStep.add_measurement_series*`
@@ -647,7 +1643,11 @@ pub struct MeasurementSeriesDetail {
     hardware_info: Option<dut::DutHardwareInfo>,
     subcomponent: Option<dut::Subcomponent>,
 
-    metadata: BTreeMap<String, tv::Value>,
+    metadata: Map<String, tv::Value>,
+    summary_stats: bool,
+    require_elements: bool,
+    min_interval: Option<Duration>,
+    coalesce_mode: CoalesceMode,
 }
 
 impl MeasurementSeriesDetail {
@@ -672,7 +1672,11 @@ pub struct MeasurementSeriesDetailBuilder {
     hardware_info: Option<dut::DutHardwareInfo>,
     subcomponent: Option<dut::Subcomponent>,
 
-    metadata: BTreeMap<String, tv::Value>,
+    metadata: Map<String, tv::Value>,
+    summary_stats: bool,
+    require_elements: bool,
+    min_interval: Option<Duration>,
+    coalesce_mode: CoalesceMode,
 }
 
 impl MeasurementSeriesDetailBuilder {
@@ -694,8 +1698,11 @@ impl MeasurementSeriesDetailBuilder {
         self
     }
 
+    /// Adds a validator to the series. Each element is evaluated against all the
+    /// validators as it's emitted, and gets a `passed` boolean in its metadata with
+    /// the outcome, see [`StartedMeasurementSeries::all_elements_passed`].
     pub fn add_validator(mut self, validator: Validator) -> Self {
-        self.validators.push(validator);
+        self.validators.extend(validator.flatten());
         self
     }
 
@@ -714,6 +1721,111 @@ impl MeasurementSeriesDetailBuilder {
         self
     }
 
+    /// Accumulates the numeric values emitted by the series, ie. after any coalescing,
+    /// and, when it ends, emits a summary as a step measurement named `{series name}_summary` right after the
+    /// `measurementSeriesEnd`. The measurement value is the mean, and its metadata holds
+    /// the `count`, `min`, `max` and `mean`, along with the `measurementSeriesId`.
+    ///
+    /// No summary is emitted for empty series, or if any element is not a number.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # tokio_test::block_on(async {
+    /// # use ocptv::output::*;
+    /// let dut = DutInfo::new("my_dut");
+    /// let run = TestRun::new("diagnostic_name", "1.0").start(dut).await?;
+    /// let step = run.add_step("step_name").start().await?;
+    ///
+    /// let detail = MeasurementSeriesDetail::builder("fan_speed")
+    ///     .unit("RPM")
+    ///     .with_summary_stats()
+    ///     .build();
+    /// let series = step.add_measurement_series_detail(detail).start().await?;
+    /// series.add_measurement(1000).await?;
+    /// series.add_measurement(1200).await?;
+    /// series.end().await?;
+    ///
+    /// # Ok::<(), OcptvError>(())
+    /// # });
+    /// ```
+    pub fn with_summary_stats(mut self) -> Self {
+        self.summary_stats = true;
+        self
+    }
+
+    /// Marks the series as expected to have elements. Ending it without any emits a
+    /// step log with `WARNING` severity right after the `measurementSeriesEnd`, to catch
+    /// series left empty by accident.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # tokio_test::block_on(async {
+    /// # use ocptv::output::*;
+    /// let dut = DutInfo::new("my_dut");
+    /// let run = TestRun::new("diagnostic_name", "1.0").start(dut).await?;
+    /// let step = run.add_step("step_name").start().await?;
+    ///
+    /// let detail = MeasurementSeriesDetail::builder("fan_speed")
+    ///     .require_elements()
+    ///     .build();
+    /// let series = step.add_measurement_series_detail(detail).start().await?;
+    /// series.end().await?; // emits the warning
+    ///
+    /// # Ok::<(), OcptvError>(())
+    /// # });
+    /// ```
+    pub fn require_elements(mut self) -> Self {
+        self.require_elements = true;
+        self
+    }
+
+    /// Limits the series to at most one element per `interval`, for very high rate sources.
+    /// The elements are grouped in consecutive intervals, each starting at the first
+    /// element that didn't fit the previous one, and only one element is emitted for each
+    /// interval, as configured with [`MeasurementSeriesDetailBuilder::coalesce_mode`].
+    /// By default, the latest element is kept.
+    ///
+    /// The element timestamps, or the configured [`tv::TimestampProvider`] for elements
+    /// without one, decide which interval an element belongs to. Since an interval only
+    /// ends when a later element arrives, its element is emitted at that point, or when
+    /// the series ends. The `totalCount` of the series counts the emitted elements.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # tokio_test::block_on(async {
+    /// # use std::time::Duration;
+    /// # use ocptv::output::*;
+    /// let dut = DutInfo::new("my_dut");
+    /// let run = TestRun::new("diagnostic_name", "1.0").start(dut).await?;
+    /// let step = run.add_step("step_name").start().await?;
+    ///
+    /// let detail = MeasurementSeriesDetail::builder("fan_speed")
+    ///     .min_interval(Duration::from_secs(1))
+    ///     .coalesce_mode(CoalesceMode::Average)
+    ///     .build();
+    /// let series = step.add_measurement_series_detail(detail).start().await?;
+    /// series.add_measurement(1000).await?;
+    /// series.add_measurement(1200).await?;
+    /// series.end().await?; // emits a single element, unless a second has passed
+    ///
+    /// # Ok::<(), OcptvError>(())
+    /// # });
+    /// ```
+    pub fn min_interval(mut self, interval: Duration) -> Self {
+        self.min_interval = Some(interval);
+        self
+    }
+
+    /// Sets how the elements in each interval are combined, see
+    /// [`MeasurementSeriesDetailBuilder::min_interval`]. Has no effect without it.
+    pub fn coalesce_mode(mut self, mode: CoalesceMode) -> Self {
+        self.coalesce_mode = mode;
+        self
+    }
+
     pub fn build(self) -> MeasurementSeriesDetail {
         MeasurementSeriesDetail {
             id: self.id,
@@ -723,6 +1835,10 @@ impl MeasurementSeriesDetailBuilder {
             hardware_info: self.hardware_info,
             subcomponent: self.subcomponent,
             metadata: self.metadata,
+            summary_stats: self.summary_stats,
+            require_elements: self.require_elements,
+            min_interval: self.min_interval,
+            coalesce_mode: self.coalesce_mode,
         }
     }
 }
@@ -732,7 +1848,6 @@ mod tests {
     use super::*;
     use crate::output as tv;
     use crate::spec;
-    use maplit::{btreemap, convert_args};
     use tv::dut::*;
     use tv::ValidatorType;
 
@@ -772,9 +1887,7 @@ mod tests {
 
         let meta_key = "key";
         let meta_value = tv::Value::from("value");
-        let metadata = convert_args!(btreemap!(
-            meta_key => meta_value.clone(),
-        ));
+        let metadata = Map::from_iter([(meta_key.to_owned(), meta_value.clone())]);
 
         let subcomponent = Subcomponent::builder("name").build();
 
@@ -805,6 +1918,299 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_measurement_metadata_insertion_order() -> Result<()> {
+        let measurement = Measurement::builder("name", 50)
+            .add_metadata("zeta", 1)
+            .add_metadata("alpha", 2)
+            .add_metadata("mu", 3)
+            .build();
+
+        let artifact = measurement.to_artifact();
+        assert_eq!(
+            serde_json::to_string(&artifact.metadata)?,
+            r#"{"zeta":1,"alpha":2,"mu":3}"#
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_measurement_builder_add_metadata_iter() -> Result<()> {
+        let labels = std::collections::HashMap::from([
+            ("rack".to_owned(), tv::Value::from("r12")),
+            ("slot".to_owned(), tv::Value::from(4)),
+        ]);
+        let measurement = Measurement::builder("name", 50)
+            .add_metadata("key", "value")
+            .add_metadata_iter(labels)
+            .add_metadata("slot", 5)
+            .build();
+
+        let metadata = measurement.to_artifact().metadata.unwrap();
+        assert_eq!(metadata.len(), 3);
+        assert_eq!(metadata["key"], "value");
+        assert_eq!(metadata["rack"], "r12");
+        // later entries replace earlier ones
+        assert_eq!(metadata["slot"], 5);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_measurement_builder_relative_to() -> Result<()> {
+        let metadata = |measurement: Measurement| measurement.to_artifact().metadata.unwrap();
+
+        let actual = metadata(
+            Measurement::builder("fan_speed", 1250)
+                .relative_to("fan_speed_idle", 900)
+                .build(),
+        );
+        assert_eq!(
+            actual["baseline"],
+            serde_json::json!({ "name": "fan_speed_idle", "value": 900 })
+        );
+        assert_eq!(actual["delta"], 350);
+        assert!(actual["delta"].is_i64());
+
+        let actual = metadata(
+            Measurement::builder("voltage", 11.5)
+                .relative_to("voltage_before", 12)
+                .build(),
+        );
+        assert_eq!(actual["delta"], -0.5);
+
+        let actual = metadata(
+            Measurement::builder("state", "on")
+                .relative_to("state_before", "off")
+                .build(),
+        );
+        assert_eq!(actual["baseline"]["value"], "off");
+        assert!(!actual.contains_key("delta"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_measurement_builder_add_validators() -> Result<()> {
+        let first = [
+            Validator::builder(spec::ValidatorType::GreaterThan, 10).build(),
+            Validator::builder(spec::ValidatorType::LessThan, 90).build(),
+        ];
+        let second = [
+            Validator::builder(spec::ValidatorType::NotEqual, 50).build(),
+            Validator::builder(spec::ValidatorType::Equal, 40).build(),
+        ];
+
+        let measurement = Measurement::builder("name", 40)
+            .add_validators(&first)
+            .add_validators(&second)
+            .build();
+
+        let artifact = measurement.to_artifact();
+        assert_eq!(
+            artifact.validators,
+            Some(
+                first
+                    .iter()
+                    .chain(second.iter())
+                    .map(Validator::to_spec)
+                    .collect()
+            )
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_measurement_boolean() -> Result<()> {
+        let measurement = Measurement::boolean("name", true);
+
+        let artifact = measurement.to_artifact();
+        assert_eq!(artifact.value, tv::Value::Bool(true));
+        assert_eq!(artifact.metadata, None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_measurement_from_number() -> Result<()> {
+        let artifact = Measurement::from_number("name", 200u8).to_artifact();
+        assert_eq!(serde_json::to_string(&artifact.value)?, "200");
+
+        let artifact = Measurement::from_number("name", -5_000_000_000i64).to_artifact();
+        assert_eq!(serde_json::to_string(&artifact.value)?, "-5000000000");
+
+        let artifact = Measurement::from_number("name", 12.5f64).to_artifact();
+        assert_eq!(serde_json::to_string(&artifact.value)?, "12.5");
+
+        let artifact = Measurement::from_number("name", f64::INFINITY).to_artifact();
+        assert_eq!(artifact.value, tv::Value::Null);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_measurement_int_and_float() -> Result<()> {
+        let int = Measurement::int("name", 50).to_artifact();
+        assert_eq!(serde_json::to_string(&int.value)?, "50");
+
+        let float = Measurement::float("name", 50.0).to_artifact();
+        assert_eq!(serde_json::to_string(&float.value)?, "50.0");
+
+        let artifact = Measurement::float("name", 50.0).to_artifact();
+        assert!(artifact.value.is_f64());
+        assert_eq!(artifact.value.as_f64(), Some(50.0));
+
+        let artifact = Measurement::float("name", f64::NAN).to_artifact();
+        assert_eq!(artifact.value, tv::Value::Null);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_measurement_quantity() -> Result<()> {
+        let artifact =
+            Measurement::quantity("name", Quantity::new(1200.0, Unit::Rpm)).to_artifact();
+        assert_eq!(artifact.value, tv::Value::from(1200.0));
+        assert_eq!(artifact.unit, Some("RPM".to_owned()));
+
+        let quantity = Quantity::new(0.5, Unit::Other("mOhm".to_owned()));
+        let artifact = Measurement::quantity("name", quantity).to_artifact();
+        assert_eq!(artifact.value, tv::Value::from(0.5));
+        assert_eq!(artifact.unit, Some("mOhm".to_owned()));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_measurement_bytes() -> Result<()> {
+        let measurement = Measurement::bytes("name", &[0xde, 0xad, 0xbe, 0xef]);
+
+        let artifact = measurement.to_artifact();
+        assert_eq!(artifact.value, tv::Value::from("3q2+7w=="));
+        assert_eq!(
+            artifact.metadata,
+            Some(Map::from_iter([(
+                "encoding".to_owned(),
+                tv::Value::from("base64")
+            )]))
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_validator_evaluate() -> Result<()> {
+        let check = |validator_type: ValidatorType, limit: tv::Value, value: tv::Value| {
+            Validator::builder(validator_type, limit)
+                .build()
+                .evaluate(&value)
+        };
+
+        assert!(check(ValidatorType::Equal, 30.into(), 30.0.into()));
+        assert!(!check(ValidatorType::Equal, 30.into(), "30".into()));
+        assert!(check(ValidatorType::NotEqual, 30.into(), 31.into()));
+        assert!(check(ValidatorType::LessThan, 30.into(), 29.5.into()));
+        assert!(!check(ValidatorType::LessThan, 30.into(), 30.into()));
+        assert!(check(ValidatorType::LessThanOrEqual, 30.into(), 30.into()));
+        assert!(check(ValidatorType::GreaterThan, 30.into(), 31.into()));
+        assert!(!check(ValidatorType::GreaterThan, 30.into(), "31".into()));
+        assert!(check(
+            ValidatorType::GreaterThanOrEqual,
+            30.into(),
+            30.into()
+        ));
+        assert!(check(
+            ValidatorType::RegexMatch,
+            "^fan[0-9]$".into(),
+            "fan1".into()
+        ));
+        assert!(!check(ValidatorType::RegexMatch, "(".into(), "fan1".into()));
+        assert!(check(
+            ValidatorType::RegexNoMatch,
+            "^fan[0-9]$".into(),
+            "psu1".into()
+        ));
+        assert!(check(ValidatorType::InSet, vec![1, 2, 3].into(), 2.into()));
+        assert!(!check(ValidatorType::InSet, vec![1, 2, 3].into(), 4.into()));
+        assert!(check(
+            ValidatorType::NotInSet,
+            vec![1, 2, 3].into(),
+            4.into()
+        ));
+        assert!(!check(ValidatorType::NotInSet, 1.into(), 4.into()));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_validator_all_of_evaluate() -> Result<()> {
+        let range = Validator::all_of(vec![
+            Validator::builder(ValidatorType::GreaterThan, 0).build(),
+            Validator::builder(ValidatorType::LessThan, 100).build(),
+        ]);
+        assert!(range.evaluate(&50.into()));
+        assert!(!range.evaluate(&0.into()));
+        assert!(!range.evaluate(&100.into()));
+
+        assert!(Validator::all_of(vec![]).evaluate(&1.into()));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_validator_all_of_flattens_into_measurement() -> Result<()> {
+        let validator = Validator::all_of(vec![
+            Validator::builder(ValidatorType::NotEqual, 0).build(),
+            Validator::all_of(vec![
+                Validator::builder(ValidatorType::GreaterThan, 1000)
+                    .name("min")
+                    .build(),
+                Validator::builder(ValidatorType::LessThan, 5000).build(),
+            ]),
+        ]);
+
+        let measurement = Measurement::builder("fan_speed", 1200)
+            .add_validator(Validator::builder(ValidatorType::InSet, vec![1200]).build())
+            .add_validator(validator)
+            .build();
+
+        assert_eq!(
+            serde_json::json!(measurement.to_artifact().validators),
+            serde_json::json!([
+                { "type": "IN_SET", "value": [1200] },
+                { "type": "NOT_EQUAL", "value": 0 },
+                { "name": "min", "type": "GREATER_THAN", "value": 1000 },
+                { "type": "LESS_THAN", "value": 5000 },
+            ])
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_validator_description() -> Result<()> {
+        let validator = Validator::builder(ValidatorType::LessThan, 85)
+            .name("tjmax")
+            .description("datasheet rev C p. 12")
+            .build();
+
+        assert_eq!(
+            serde_json::json!(validator.to_spec()),
+            serde_json::json!({
+                "name": "tjmax",
+                "type": "LESS_THAN",
+                "value": 85,
+                "metadata": {
+                    "description": "datasheet rev C p. 12"
+                }
+            })
+        );
+
+        Ok(())
+    }
+
     #[test]
     fn test_validator() -> Result<()> {
         let validator = Validator::builder(ValidatorType::Equal, 30)
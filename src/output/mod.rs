@@ -6,6 +6,7 @@
 #![deny(warnings)]
 
 mod config;
+pub mod context;
 mod diagnosis;
 mod dut;
 mod emitter;
@@ -14,8 +15,12 @@ mod file;
 mod log;
 mod macros;
 mod measure;
+#[cfg(feature = "otel")]
+mod otel;
 mod run;
 mod step;
+#[cfg(feature = "testutil")]
+pub mod testutil;
 mod trait_ext;
 mod writer;
 
@@ -23,24 +28,44 @@ pub use crate::spec::{
     DiagnosisType, LogSeverity, SoftwareType, SubcomponentType, TestResult, TestStatus,
     ValidatorType, SPEC_VERSION,
 };
-pub use config::{Config, ConfigBuilder, TimestampProvider};
+pub use config::{
+    Config, ConfigBuilder, NonFinitePolicy, SeriesIdGenerator, SharedConfig, TimestampPrecision,
+    TimestampProvider, Transform, WriteErrorPolicy,
+};
 pub use diagnosis::{Diagnosis, DiagnosisBuilder};
 pub use dut::{
     DutHardwareInfo, DutInfo, DutInfoBuilder, DutSoftwareInfo, HardwareInfo, HardwareInfoBuilder,
-    Ident, PlatformInfo, PlatformInfoBuilder, SoftwareInfo, SoftwareInfoBuilder, Subcomponent,
-    SubcomponentBuilder,
+    Ident, Location, PlatformInfo, PlatformInfoBuilder, SoftwareInfo, SoftwareInfoBuilder,
+    Subcomponent, SubcomponentBuilder,
 };
 pub use error::{Error, ErrorBuilder};
 pub use file::{File, FileBuilder};
 pub use log::{Log, LogBuilder};
 pub use measure::{
-    Measurement, MeasurementBuilder, MeasurementElementDetail, MeasurementElementDetailBuilder,
-    MeasurementSeries, MeasurementSeriesDetail, MeasurementSeriesDetailBuilder,
-    StartedMeasurementSeries, Validator, ValidatorBuilder,
+    CoalesceMode, Measurement, MeasurementBuilder, MeasurementElementDetail,
+    MeasurementElementDetailBuilder, MeasurementNumber, MeasurementSeries, MeasurementSeriesDetail,
+    MeasurementSeriesDetailBuilder, MeasurementValue, Quantity, RunMeasurementSeries,
+    StartedMeasurementSeries, Unit, Validator, ValidatorBuilder,
+    VALIDATOR_DESCRIPTION_METADATA_KEY,
+};
+#[cfg(feature = "otel")]
+pub use otel::{STEP_ID_ATTRIBUTE, STEP_NAME_ATTRIBUTE, STEP_STATUS_ATTRIBUTE};
+pub use run::{
+    Provenance, RunGuard, RunStats, ScopedTestRun, SpawnedStep, StartedTestRun, TestRun,
+    TestRunBuilder, TestRunOutcome, ABANDONED_RUN_SYMPTOM, DUT_SNAPSHOT_KEY, FAILED_RUN_SYMPTOM,
+    PROVENANCE_METADATA_KEY,
+};
+pub use step::{
+    ErrorCorrelation, Extension, ExtensionRegistry, MeasurementGroup, ScopedTestStep,
+    StartedTestStep, TestStep,
 };
-pub use run::{ScopedTestRun, StartedTestRun, TestRun, TestRunBuilder, TestRunOutcome};
-pub use step::{ScopedTestStep, StartedTestStep, TestStep};
-pub use writer::{BufferWriter, FileWriter, StdoutWriter, Writer};
+pub use writer::{
+    AsyncWriteWriter, BoundedChannelWriter, BufferOverflow, BufferWriter, BytesBufferWriter,
+    FileWriter, JsonArrayWriter, OutputFormat, PrefixWriter, RecordedArtifact, RecordingWriter,
+    RetryWriter, RingBufferWriter, StdoutWriter, TeeWriter, Writer,
+};
+#[cfg(feature = "syslog")]
+pub use writer::{SyslogFacility, SyslogSeverity, SyslogSeverityMap, SyslogSink, SyslogWriter};
 
 // re-export these as a public types we present
 pub use serde_json::Value;
@@ -59,3 +84,11 @@ pub enum OcptvError {
     #[error("other error")]
     Other(Box<dyn std::error::Error + Send + Sync + 'static>),
 }
+
+// serde failures keep the concrete `serde_json::Error` inside `Format`, so callers
+// can get it back with `downcast_ref`
+impl From<serde_json::Error> for OcptvError {
+    fn from(value: serde_json::Error) -> Self {
+        OcptvError::Format(Box::new(value))
+    }
+}
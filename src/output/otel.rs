@@ -0,0 +1,141 @@
+// (c) Meta Platforms, Inc. and affiliates.
+//
+// Use of this source code is governed by an MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+//! OpenTelemetry interop, see [`crate::output::ConfigBuilder::with_otel_tracer`].
+//!
+//! Requires the `otel` feature.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use opentelemetry::global::{BoxedSpan, BoxedTracer};
+use opentelemetry::trace::{Span, Tracer};
+use opentelemetry::KeyValue;
+
+use crate::spec;
+
+/// Span attribute holding the id of the test step, eg. `step0`.
+pub const STEP_ID_ATTRIBUTE: &str = "ocptv.step.id";
+/// Span attribute holding the name of the test step.
+pub const STEP_NAME_ATTRIBUTE: &str = "ocptv.step.name";
+/// Span attribute holding the status the test step ended with, eg. `COMPLETE`.
+pub const STEP_STATUS_ATTRIBUTE: &str = "ocptv.step.status";
+
+/// Opens a span on `testStepStart` and ends it on the matching `testStepEnd`.
+pub(crate) struct StepSpans {
+    tracer: Arc<BoxedTracer>,
+    // spans of the steps that started but didn't end yet, by step id
+    open: Mutex<HashMap<String, BoxedSpan>>,
+}
+
+impl StepSpans {
+    pub(crate) fn new(tracer: Arc<BoxedTracer>) -> Self {
+        StepSpans {
+            tracer,
+            open: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub(crate) fn observe(&self, root: &spec::RootImpl) {
+        let spec::RootImpl::TestStepArtifact(step) = root else {
+            return;
+        };
+
+        match &step.artifact {
+            spec::TestStepArtifactImpl::TestStepStart(start) => {
+                let span = self
+                    .tracer
+                    .span_builder(start.name.clone())
+                    .with_attributes([
+                        KeyValue::new(STEP_ID_ATTRIBUTE, step.id.clone()),
+                        KeyValue::new(STEP_NAME_ATTRIBUTE, start.name.clone()),
+                    ])
+                    .start(&*self.tracer);
+                self.open.lock().unwrap().insert(step.id.clone(), span);
+            }
+            spec::TestStepArtifactImpl::TestStepEnd(end) => {
+                let span = self.open.lock().unwrap().remove(&step.id);
+                if let Some(mut span) = span {
+                    if let Some(status) = serde_json::json!(end.status).as_str() {
+                        span.set_attribute(KeyValue::new(STEP_STATUS_ATTRIBUTE, status.to_owned()));
+                    }
+                    span.end();
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use anyhow::Result;
+    use opentelemetry::trace::TracerProvider as _;
+    use opentelemetry::Value;
+    use opentelemetry_sdk::testing::trace::InMemorySpanExporter;
+    use opentelemetry_sdk::trace::TracerProvider;
+    use tokio::sync::Mutex;
+
+    use super::*;
+    use crate::output as tv;
+
+    #[tokio::test]
+    async fn test_span_per_step() -> Result<()> {
+        let exporter = InMemorySpanExporter::default();
+        let provider = TracerProvider::builder()
+            .with_simple_exporter(exporter.clone())
+            .build();
+
+        let buffer = Arc::new(Mutex::new(vec![]));
+        let config = tv::Config::builder()
+            .with_buffer_output(Arc::clone(&buffer))
+            .with_otel_tracer(provider.tracer("test"))
+            .build();
+
+        let run = tv::TestRun::builder("run_name", "1.0")
+            .config(config)
+            .build()
+            .start(tv::DutInfo::new("dut_id"))
+            .await?;
+        let first = run.add_step("first step").start().await?;
+        first.end(tv::TestStatus::Complete).await?;
+        let second = run.add_step("second step").start().await?;
+        second.end(tv::TestStatus::Skip).await?;
+        run.end(tv::TestStatus::Complete, tv::TestResult::Pass)
+            .await?;
+
+        let spans = exporter.get_finished_spans()?;
+        assert_eq!(spans.len(), 2);
+
+        let attribute = |index: usize, key: &str| {
+            spans[index]
+                .attributes
+                .iter()
+                .find(|kv| kv.key.as_str() == key)
+                .map(|kv| kv.value.clone())
+        };
+        assert_eq!(spans[0].name, "first step");
+        assert_eq!(attribute(0, STEP_ID_ATTRIBUTE), Some(Value::from("step0")));
+        assert_eq!(
+            attribute(0, STEP_NAME_ATTRIBUTE),
+            Some(Value::from("first step"))
+        );
+        assert_eq!(
+            attribute(0, STEP_STATUS_ATTRIBUTE),
+            Some(Value::from("COMPLETE"))
+        );
+        assert_eq!(spans[1].name, "second step");
+        assert_eq!(attribute(1, STEP_ID_ATTRIBUTE), Some(Value::from("step1")));
+        assert_eq!(
+            attribute(1, STEP_STATUS_ATTRIBUTE),
+            Some(Value::from("SKIP"))
+        );
+
+        Ok(())
+    }
+}
@@ -4,19 +4,20 @@
 // license that can be found in the LICENSE file or at
 // https://opensource.org/licenses/MIT.
 
-use std::collections::BTreeMap;
 use std::env;
 use std::future::Future;
+use std::pin::Pin;
 use std::sync::{
     atomic::{self, Ordering},
     Arc,
 };
 
 use delegate::delegate;
+use serde_json::Map;
 
 use crate::output as tv;
 use crate::spec;
-use tv::step::TestStep;
+use tv::step::{ScopedTestStep, TestStep};
 use tv::{config, dut, emitter, error, log};
 
 use super::trait_ext::MapExt;
@@ -30,17 +31,82 @@ pub struct TestRunOutcome {
     pub result: spec::TestResult,
 }
 
+/// Symptom of the run error emitted by [`StartedTestRun::end_from_result`] on `Err`.
+pub const FAILED_RUN_SYMPTOM: &str = "run_failed";
+
+/// Symptom of the run error emitted when a [`RunGuard`] created by
+/// [`StartedTestRun::start_scope`] is dropped without being completed.
+pub const ABANDONED_RUN_SYMPTOM: &str = "run_abandoned";
+
+/// Output statistics of a [`StartedTestRun`], counting everything written so far.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RunStats {
+    /// Number of artifacts written, including the schema version
+    pub artifacts: u64,
+    /// Number of bytes written, including line terminators
+    pub bytes: u64,
+    /// Number of artifacts dropped after an output error, see [`tv::WriteErrorPolicy::Ignore`]
+    pub dropped: u64,
+}
+
+/// Build provenance of the diagnostic binary, emitted in the run metadata under the
+/// [`PROVENANCE_METADATA_KEY`] key by [`TestRunBuilder::provenance`]. Only the fields
+/// that are set are emitted.
+///
+/// The fields are usually filled at compile time, eg. from environment variables set
+/// by a build script.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Provenance {
+    /// Git commit the binary was built from, emitted as `gitSha`
+    pub git_sha: Option<String>,
+    /// Whether the working tree had uncommitted changes, emitted as `gitDirty`
+    pub git_dirty: Option<bool>,
+    /// Build time, preferably in ISO8601 format, emitted as `buildTime`
+    pub build_time: Option<String>,
+    /// Host the binary was built on, emitted as `buildHost`
+    pub build_host: Option<String>,
+}
+
+/// Run metadata key of the [`Provenance`] object.
+pub const PROVENANCE_METADATA_KEY: &str = "provenance";
+
+/// Key of the DUT description in the log message emitted by [`TestRun::emit_dut_snapshot`].
+pub const DUT_SNAPSHOT_KEY: &str = "dutInfo";
+
+impl Provenance {
+    fn to_value(&self) -> tv::Value {
+        let mut map = Map::new();
+        if let Some(git_sha) = &self.git_sha {
+            map.insert("gitSha".to_owned(), git_sha.clone().into());
+        }
+        if let Some(git_dirty) = self.git_dirty {
+            map.insert("gitDirty".to_owned(), git_dirty.into());
+        }
+        if let Some(build_time) = &self.build_time {
+            map.insert("buildTime".to_owned(), build_time.clone().into());
+        }
+        if let Some(build_host) = &self.build_host {
+            map.insert("buildHost".to_owned(), build_host.clone().into());
+        }
+        tv::Value::Object(map)
+    }
+}
+
 /// The main diag test run.
 ///
 /// This object describes a single run instance of the diag, and therefore drives the test session.
 pub struct TestRun {
     name: String,
     version: String,
-    parameters: BTreeMap<String, tv::Value>,
+    parameters: Map<String, tv::Value>,
     command_line: String,
-    metadata: BTreeMap<String, tv::Value>,
+    metadata: Map<String, tv::Value>,
 
     emitter: Arc<emitter::JsonEmitter>,
+    series_id_generator: Option<Arc<config::SeriesIdGenerator>>,
+    auto_step_timing: bool,
+    lazy_start: bool,
+    inline_file_max_size: usize,
 }
 
 impl TestRun {
@@ -68,8 +134,41 @@ impl TestRun {
         TestRunBuilder::new(name, version)
     }
 
+    /// Returns the parameters the run declares in the `testRunStart` artifact.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use ocptv::output::*;
+    /// let run = TestRun::builder("run_name", "1.0")
+    ///     .add_parameter("param1", "value1")
+    ///     .build();
+    /// assert_eq!(run.parameters()["param1"], "value1");
+    /// ```
+    pub fn parameters(&self) -> &Map<String, tv::Value> {
+        &self.parameters
+    }
+
+    /// Returns the metadata the run declares in the `testRunStart` artifact.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use ocptv::output::*;
+    /// let run = TestRun::builder("run_name", "1.0")
+    ///     .add_metadata("meta1", "value1")
+    ///     .build();
+    /// assert_eq!(run.metadata()["meta1"], "value1");
+    /// ```
+    pub fn metadata(&self) -> &Map<String, tv::Value> {
+        &self.metadata
+    }
+
     /// Starts the test run.
     ///
+    /// If the DUT declares computer systems, dangling `computer_system` references are
+    /// reported here. See [`dut::DutInfoBuilder::add_computer_system`].
+    ///
     /// ref: <https://github.com/opencomputeproject/ocp-diag-core/tree/main/json_spec#testrunstart>
     ///
     /// # Examples
@@ -85,6 +184,8 @@ impl TestRun {
     /// # });
     /// ```
     pub async fn start(self, dut: dut::DutInfo) -> Result<StartedTestRun, tv::OcptvError> {
+        let dangling_systems = dut.check_computer_systems()?;
+
         let start = spec::RootImpl::TestRunArtifact(spec::TestRunArtifact {
             artifact: spec::TestRunArtifactImpl::TestRunStart(spec::TestRunStart {
                 name: self.name.clone(),
@@ -96,9 +197,23 @@ impl TestRun {
             }),
         });
 
-        self.emitter.emit(&start).await?;
+        match self.lazy_start {
+            true => self.emitter.defer(start).await,
+            false => self.emitter.emit(&start).await?,
+        }
 
-        Ok(StartedTestRun::new(self))
+        for msg in dangling_systems {
+            let log = log::Log::builder(&msg)
+                .severity(spec::LogSeverity::Warning)
+                .build();
+            self.emitter
+                .emit(&spec::RootImpl::TestRunArtifact(spec::TestRunArtifact {
+                    artifact: spec::TestRunArtifactImpl::Log(log.to_artifact()),
+                }))
+                .await?;
+        }
+
+        Ok(StartedTestRun::new(self, dut))
     }
 
     /// Builds a scope in the [`TestRun`] object, taking care of starting and
@@ -144,6 +259,47 @@ impl TestRun {
         Ok(())
     }
 
+    /// Emits the DUT description before the run is started, eg. right after probing the
+    /// DUT, so it's published even if the run never starts.
+    ///
+    /// The spec has no artifact for the DUT outside of `testRunStart`, so this is an `INFO`
+    /// log whose message is a json object, with the `dutInfo` object as it would appear in
+    /// `testRunStart` under the [`DUT_SNAPSHOT_KEY`] key.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # tokio_test::block_on(async {
+    /// # use ocptv::output::*;
+    /// let dut = DutInfo::builder("my_dut").build();
+    /// let run = TestRun::new("diagnostic_name", "1.0");
+    /// run.emit_dut_snapshot(&dut).await?;
+    ///
+    /// let run = run.start(dut).await?;
+    /// run.end(TestStatus::Complete, TestResult::Pass).await?;
+    ///
+    /// # Ok::<(), OcptvError>(())
+    /// # });
+    /// ```
+    pub async fn emit_dut_snapshot(&self, dut: &dut::DutInfo) -> Result<(), tv::OcptvError> {
+        let mut snapshot = Map::new();
+        snapshot.insert(
+            DUT_SNAPSHOT_KEY.to_owned(),
+            serde_json::to_value(dut.to_spec())?,
+        );
+        let log = log::Log::builder(&tv::Value::Object(snapshot).to_string())
+            .severity(spec::LogSeverity::Info)
+            .build();
+
+        self.emitter
+            .emit(&spec::RootImpl::TestRunArtifact(spec::TestRunArtifact {
+                artifact: spec::TestRunArtifactImpl::Log(log.to_artifact()),
+            }))
+            .await?;
+
+        Ok(())
+    }
+
     /// Emits a Error message.
     ///
     /// This operation is useful in such cases when there is an error before starting the test.
@@ -193,11 +349,11 @@ impl TestRun {
 pub struct TestRunBuilder {
     name: String,
     version: String,
-    parameters: BTreeMap<String, tv::Value>,
+    parameters: Map<String, tv::Value>,
     command_line: String,
 
     config: Option<config::Config>,
-    metadata: BTreeMap<String, tv::Value>,
+    metadata: Map<String, tv::Value>,
 }
 
 impl TestRunBuilder {
@@ -205,7 +361,7 @@ impl TestRunBuilder {
         Self {
             name: name.to_string(),
             version: version.to_string(),
-            parameters: BTreeMap::new(),
+            parameters: Map::new(),
             command_line: env::args().collect::<Vec<_>>()[1..].join(" "),
             ..Default::default()
         }
@@ -226,6 +382,71 @@ impl TestRunBuilder {
         self
     }
 
+    /// Adds a user defined parameter to the future [`TestRun`] object, like
+    /// [`TestRunBuilder::add_parameter`], but only accepts flat scalars: strings,
+    /// numbers, booleans and null. Returns an error for arrays and objects, for
+    /// consumers that can't handle nested parameters.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use ocptv::output::*;
+    /// let run = TestRun::builder("run_name", "1.0")
+    ///     .add_parameter_checked("param1", "value1")?
+    ///     .add_parameter_checked("param2", 42)?
+    ///     .build();
+    ///
+    /// let nested = TestRun::builder("run_name", "1.0").add_parameter_checked("param", vec![1, 2]);
+    /// assert!(nested.is_err());
+    ///
+    /// # Ok::<(), OcptvError>(())
+    /// ```
+    pub fn add_parameter_checked<V: Into<tv::Value>>(
+        self,
+        key: &str,
+        value: V,
+    ) -> Result<Self, tv::OcptvError> {
+        let value = value.into();
+        if value.is_array() || value.is_object() {
+            return Err(tv::OcptvError::Other(
+                format!("parameter `{}` is not a scalar value", key).into(),
+            ));
+        }
+
+        Ok(self.add_parameter(key, value))
+    }
+
+    /// Adds the environment variables whose names start with `prefix` as parameters of
+    /// the future [`TestRun`] object, eg. for capturing CI job details. The prefix is
+    /// stripped from the parameter keys, and the values are strings.
+    ///
+    /// The parameters are added sorted by key. Variables with names or values that are
+    /// not valid unicode are skipped.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use ocptv::output::*;
+    /// // eg. CI_JOB_ID=1234 is added as the "JOB_ID" parameter
+    /// let run = TestRun::builder("run_name", "1.0")
+    ///     .add_parameters_from_env("CI_")
+    ///     .build();
+    /// ```
+    pub fn add_parameters_from_env(mut self, prefix: &str) -> Self {
+        let mut vars = env::vars_os()
+            .filter_map(|(k, v)| {
+                let key = k.to_str()?.strip_prefix(prefix)?.to_owned();
+                Some((key, v.into_string().ok()?))
+            })
+            .collect::<Vec<_>>();
+        vars.sort();
+
+        for (key, value) in vars {
+            self.parameters.insert(key, value.into());
+        }
+        self
+    }
+
     /// Adds the command line used to run the test session to the future
     /// [`TestRun`] object.
     ///
@@ -273,9 +494,88 @@ impl TestRunBuilder {
         self
     }
 
+    /// Adds the build provenance of the diagnostic binary to the run metadata, under
+    /// the [`PROVENANCE_METADATA_KEY`] key. See [`Provenance`] for the emitted fields.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use ocptv::output::*;
+    /// let run = TestRun::builder("run_name", "1.0")
+    ///     .provenance(Provenance {
+    ///         git_sha: option_env!("GIT_SHA").map(str::to_owned),
+    ///         build_time: Some("2024-05-01T12:00:00Z".to_owned()),
+    ///         ..Default::default()
+    ///     })
+    ///     .build();
+    /// ```
+    pub fn provenance(self, provenance: Provenance) -> Self {
+        self.add_metadata(PROVENANCE_METADATA_KEY, provenance.to_value())
+    }
+
+    /// Returns the parameters added so far.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use ocptv::output::*;
+    /// let builder = TestRun::builder("run_name", "1.0").add_parameter("param1", "value1");
+    /// assert_eq!(builder.parameters()["param1"], "value1");
+    /// ```
+    pub fn parameters(&self) -> &Map<String, tv::Value> {
+        &self.parameters
+    }
+
+    /// Returns the metadata added so far.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use ocptv::output::*;
+    /// let builder = TestRun::builder("run_name", "1.0").add_metadata("meta1", "value1");
+    /// assert_eq!(builder.metadata()["meta1"], "value1");
+    /// ```
+    pub fn metadata(&self) -> &Map<String, tv::Value> {
+        &self.metadata
+    }
+
     pub fn build(self) -> TestRun {
         let config = self.config.unwrap_or(config::Config::builder().build());
-        let emitter = emitter::JsonEmitter::new(config.timestamp_provider, config.writer);
+        let mut emitter = emitter::JsonEmitter::new(config.timestamp_provider, config.writer);
+        if let Some(transform) = config.transform {
+            emitter = emitter.with_transform(transform);
+        }
+        if config.emit_summary {
+            emitter = emitter.with_summary();
+        }
+        if let Some(separator) = config.line_separator {
+            emitter = emitter.with_line_separator(separator);
+        }
+        if !config.emit_timestamps {
+            emitter = emitter.without_timestamps();
+        }
+        if config.sanitize_control_chars {
+            emitter = emitter.with_sanitized_control_chars();
+        }
+        if config.flush_each {
+            emitter = emitter.with_flush_each();
+        }
+        emitter = emitter
+            .with_timestamp_precision(config.timestamp_precision)
+            .with_write_error_policy(config.write_error_policy)
+            .with_sequence_start(config.sequence_start)
+            .with_non_finite_policy(config.non_finite_policy)
+            .with_min_severity(config.min_severity);
+        if let Some(pattern) = config.symptom_pattern {
+            emitter = emitter.with_symptom_pattern(pattern);
+        }
+        if let Some(registry) = config.extension_registry {
+            emitter = emitter.with_extension_registry(registry);
+        }
+        #[cfg(feature = "otel")]
+        if let Some(tracer) = config.otel_tracer {
+            emitter = emitter.with_otel_tracer(tracer);
+        }
 
         TestRun {
             name: self.name,
@@ -285,6 +585,10 @@ impl TestRunBuilder {
             metadata: self.metadata,
 
             emitter: Arc::new(emitter),
+            series_id_generator: config.series_id_generator,
+            auto_step_timing: config.auto_step_timing,
+            lazy_start: config.lazy_start,
+            inline_file_max_size: config.inline_file_max_size,
         }
     }
 }
@@ -294,15 +598,21 @@ impl TestRunBuilder {
 /// ref: <https://github.com/opencomputeproject/ocp-diag-core/tree/main/json_spec#testrunstart>
 pub struct StartedTestRun {
     run: TestRun,
+    dut: dut::DutInfo,
 
     step_seqno: atomic::AtomicU64,
+    series_seqno: Arc<atomic::AtomicU64>,
+    step_errored: Arc<atomic::AtomicBool>,
 }
 
 impl StartedTestRun {
-    fn new(run: TestRun) -> StartedTestRun {
+    fn new(run: TestRun, dut: dut::DutInfo) -> StartedTestRun {
         StartedTestRun {
             run,
+            dut,
             step_seqno: atomic::AtomicU64::new(0),
+            series_seqno: Arc::new(atomic::AtomicU64::new(0)),
+            step_errored: Arc::new(atomic::AtomicBool::new(false)),
         }
     }
 
@@ -313,15 +623,36 @@ impl StartedTestRun {
         status: spec::TestStatus,
         result: spec::TestResult,
     ) -> Result<(), tv::OcptvError> {
+        // with lazy start, a run that emitted nothing leaves no output at all
+        if self.run.emitter.discard_deferred().await {
+            return Ok(());
+        }
+
+        // the summary was asked for explicitly, so it isn't dropped by the severity filter
+        if let Some(summary) = self.run.emitter.summary() {
+            let log = log::Log::builder(&summary.to_string())
+                .severity(spec::LogSeverity::Info)
+                .build();
+            let artifact = spec::TestRunArtifact {
+                artifact: spec::TestRunArtifactImpl::Log(log.to_artifact()),
+            };
+            self.run
+                .emitter
+                .emit_unfiltered(&spec::RootImpl::TestRunArtifact(artifact))
+                .await?;
+        }
+
         let end = spec::RootImpl::TestRunArtifact(spec::TestRunArtifact {
             artifact: spec::TestRunArtifactImpl::TestRunEnd(spec::TestRunEnd { status, result }),
         });
 
         self.run.emitter.emit(&end).await?;
+        // nothing else is emitted after the run end, so don't leave it buffered
+        self.run.emitter.flush().await?;
         Ok(())
     }
 
-    /// Ends the test run.
+    /// Ends the test run, then flushes the output writer.
     ///
     /// ref: <https://github.com/opencomputeproject/ocp-diag-core/tree/main/json_spec#testrunend>
     ///
@@ -345,6 +676,50 @@ impl StartedTestRun {
         self.end_impl(status, result).await
     }
 
+    /// Ends the test run from the result of the diagnostic: on `Ok`, with a `COMPLETE`
+    /// status and a `PASS` result; on `Err`, an error artifact with the
+    /// [`FAILED_RUN_SYMPTOM`] symptom and the error's [`Display`] as message is emitted
+    /// first, then the run ends with a `COMPLETE` status and a `FAIL` result.
+    ///
+    /// The run is ended even if emitting the error fails; the first error is returned.
+    ///
+    /// [`Display`]: std::fmt::Display
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # tokio_test::block_on(async {
+    /// # use ocptv::output::*;
+    /// fn check_fans() -> Result<(), String> {
+    ///     Err("fan-stuck".to_owned())
+    /// }
+    ///
+    /// let dut = DutInfo::builder("my_dut").build();
+    /// let run = TestRun::new("diagnostic_name", "1.0").start(dut).await?;
+    /// run.end_from_result(check_fans()).await?;
+    ///
+    /// # Ok::<(), OcptvError>(())
+    /// # });
+    /// ```
+    pub async fn end_from_result<E: std::fmt::Display>(
+        self,
+        result: Result<(), E>,
+    ) -> Result<(), tv::OcptvError> {
+        match result {
+            Ok(()) => {
+                self.end_impl(spec::TestStatus::Complete, spec::TestResult::Pass)
+                    .await
+            }
+            Err(e) => {
+                let error = self.add_error_msg(FAILED_RUN_SYMPTOM, &e.to_string()).await;
+                let end = self
+                    .end_impl(spec::TestStatus::Complete, spec::TestResult::Fail)
+                    .await;
+                error.and(end)
+            }
+        }
+    }
+
     /// Emits a Log message.
     /// This method accepts a [`tv::LogSeverity`] to define the severity
     /// and a [`String`] for the message.
@@ -414,12 +789,46 @@ impl StartedTestRun {
         };
         self.run
             .emitter
-            .emit(&spec::RootImpl::TestRunArtifact(artifact))
+            .emit_at(&spec::RootImpl::TestRunArtifact(artifact), log.timestamp())
             .await?;
 
         Ok(())
     }
 
+    /// Emits a Log message with the given `timestamp` instead of the current time,
+    /// eg. when replaying captured logs. See [`log::LogBuilder::timestamp`].
+    ///
+    /// ref: <https://github.com/opencomputeproject/ocp-diag-core/tree/main/json_spec#log>
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # tokio_test::block_on(async {
+    /// # use ocptv::output::*;
+    /// let dut = DutInfo::new("my_dut");
+    /// let run = TestRun::new("diagnostic_name", "1.0").start(dut).await?;
+    ///
+    /// let captured_at = chrono::DateTime::from_timestamp(1_700_000_000, 0)
+    ///     .unwrap()
+    ///     .with_timezone(&chrono_tz::UTC);
+    /// run.add_log_at(LogSeverity::Info, "bmc rebooted", captured_at).await?;
+    ///
+    /// # Ok::<(), OcptvError>(())
+    /// # });
+    /// ```
+    pub async fn add_log_at(
+        &self,
+        severity: spec::LogSeverity,
+        msg: &str,
+        timestamp: chrono::DateTime<chrono_tz::Tz>,
+    ) -> Result<(), tv::OcptvError> {
+        let log = log::Log::builder(msg)
+            .severity(severity)
+            .timestamp(timestamp)
+            .build();
+        self.add_log_detail(log).await
+    }
+
     /// Emits a Error message.
     /// This method accepts a [`String`] to define the symptom.
     ///
@@ -510,11 +919,572 @@ impl StartedTestRun {
         Ok(())
     }
 
-    /// Create a new step for this test run.
-    /// TODO: docs + example
+    /// Returns the [`dut::DutInfo`] this run was started with, eg. to look up the
+    /// registered hardware or software infos by id.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # tokio_test::block_on(async {
+    /// # use ocptv::output::*;
+    /// let mut dut = DutInfo::new("my_dut");
+    /// dut.add_software_info(SoftwareInfo::builder("bmc").id(Ident::Exact("sw0".to_owned())).build());
+    /// let run = TestRun::new("diagnostic_name", "1.0").start(dut).await?;
+    ///
+    /// let sw_info = run.dut().software_info("sw0");
+    /// assert!(sw_info.is_some());
+    ///
+    /// # Ok::<(), OcptvError>(())
+    /// # });
+    /// ```
+    pub fn dut(&self) -> &dut::DutInfo {
+        &self.dut
+    }
+
+    /// Returns the parameters this run declared. See [`TestRun::parameters`].
+    pub fn parameters(&self) -> &Map<String, tv::Value> {
+        self.run.parameters()
+    }
+
+    /// Returns the metadata this run declared. See [`TestRun::metadata`].
+    pub fn metadata(&self) -> &Map<String, tv::Value> {
+        self.run.metadata()
+    }
+
+    /// Emits a pre-serialized artifact, eg. one produced by another OCPTV library,
+    /// interleaved with the rest of the output. The value is the artifact body, like
+    /// `{"testRunArtifact": {...}}`; it gets the next sequence number and the current
+    /// timestamp, replacing any such fields already present.
+    ///
+    /// The artifact contents are not checked against the spec, only that it's a json object.
+    /// The configured output checks still look at the fields they know of, eg. a raw error
+    /// must match [`tv::ConfigBuilder::symptom_pattern`], and a raw log below
+    /// [`tv::ConfigBuilder::min_severity`] is dropped.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # tokio_test::block_on(async {
+    /// # use ocptv::output::*;
+    /// use serde_json::json;
+    ///
+    /// let dut = DutInfo::new("my_dut");
+    /// let run = TestRun::new("diagnostic_name", "1.0").start(dut).await?;
+    /// run.emit_raw(json!({
+    ///     "testRunArtifact": {
+    ///         "log": { "severity": "INFO", "message": "from another library" }
+    ///     }
+    /// })).await?;
+    /// run.end(TestStatus::Complete, TestResult::Pass).await?;
+    ///
+    /// # Ok::<(), OcptvError>(())
+    /// # });
+    /// ```
+    pub async fn emit_raw(&self, value: tv::Value) -> Result<(), tv::OcptvError> {
+        let tv::Value::Object(mut artifact) = value else {
+            return Err(tv::OcptvError::Format(
+                "raw artifact must be a json object".into(),
+            ));
+        };
+        artifact.remove("sequenceNumber");
+        artifact.remove("timestamp");
+
+        self.run
+            .emitter
+            .emit(&spec::RootImpl::Raw(artifact))
+            .await?;
+
+        Ok(())
+    }
+
+    /// Returns the number of artifacts and bytes this run has written so far.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # tokio_test::block_on(async {
+    /// # use ocptv::output::*;
+    /// let dut = DutInfo::new("my_dut");
+    /// let run = TestRun::new("diagnostic_name", "1.0").start(dut).await?;
+    ///
+    /// let stats = run.stats();
+    /// // schemaVersion and testRunStart
+    /// assert_eq!(stats.artifacts, 2);
+    ///
+    /// # Ok::<(), OcptvError>(())
+    /// # });
+    /// ```
+    pub fn stats(&self) -> RunStats {
+        RunStats {
+            artifacts: self.run.emitter.artifact_count(),
+            bytes: self.run.emitter.byte_count(),
+            dropped: self.run.emitter.dropped_count(),
+        }
+    }
+
+    /// Creates a new step for this test run. The step gets the next step id, but nothing
+    /// is emitted until it's started with [`TestStep::start`], see [`TestStep`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # tokio_test::block_on(async {
+    /// # use ocptv::output::*;
+    /// let dut = DutInfo::new("my_dut");
+    /// let run = TestRun::new("diagnostic_name", "1.0").start(dut).await?;
+    ///
+    /// let step = run.add_step("step_name"); // infallible, emits nothing
+    /// let step = step.start().await?; // emits the testStepStart artifact
+    /// step.end(TestStatus::Complete).await?;
+    ///
+    /// # Ok::<(), OcptvError>(())
+    /// # });
+    /// ```
     pub fn add_step(&self, name: &str) -> TestStep {
         let step_id = format!("step{}", self.step_seqno.fetch_add(1, Ordering::AcqRel));
-        TestStep::new(&step_id, name, Arc::clone(&self.run.emitter))
+        TestStep::new(
+            &step_id,
+            name,
+            Arc::clone(&self.run.emitter),
+            Arc::clone(&self.series_seqno),
+            self.run.series_id_generator.clone(),
+            Arc::clone(&self.step_errored),
+            self.run.auto_step_timing,
+            self.run.inline_file_max_size,
+        )
+    }
+
+    /// Returns the run result derived from the statuses the steps ended with so far:
+    /// `FAIL` if any step ended with an `ERROR` status, `PASS` otherwise. Steps that
+    /// didn't end yet aren't considered.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # tokio_test::block_on(async {
+    /// # use ocptv::output::*;
+    /// let dut = DutInfo::new("my_dut");
+    /// let run = TestRun::new("diagnostic_name", "1.0").start(dut).await?;
+    ///
+    /// let step = run.add_step("step_name").start().await?;
+    /// step.end(TestStatus::Error).await?;
+    ///
+    /// let result = run.result_from_steps();
+    /// assert_eq!(result, TestResult::Fail);
+    /// run.end(TestStatus::Complete, result).await?;
+    ///
+    /// # Ok::<(), OcptvError>(())
+    /// # });
+    /// ```
+    pub fn result_from_steps(&self) -> spec::TestResult {
+        match self.step_errored.load(Ordering::Acquire) {
+            true => spec::TestResult::Fail,
+            false => spec::TestResult::Pass,
+        }
+    }
+
+    /// Creates a measurement series in the scope of the run, whose elements can be added
+    /// from any step, see [`tv::RunMeasurementSeries`].
+    ///
+    /// ref: <https://github.com/opencomputeproject/ocp-diag-core/tree/main/json_spec#measurementseriesstart>
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # tokio_test::block_on(async {
+    /// # use ocptv::output::*;
+    /// let dut = DutInfo::new("my_dut");
+    /// let run = TestRun::new("diagnostic_name", "1.0").start(dut).await?;
+    /// let series = run.add_measurement_series("temperature");
+    /// assert_eq!(series.id(), "run_series0");
+    ///
+    /// # Ok::<(), OcptvError>(())
+    /// # });
+    /// ```
+    pub fn add_measurement_series(&self, name: &str) -> tv::RunMeasurementSeries {
+        self.add_measurement_series_detail(tv::MeasurementSeriesDetail::new(name))
+    }
+
+    /// Creates a measurement series in the scope of the run, see
+    /// [`StartedTestRun::add_measurement_series`].
+    /// This method accepts a [`tv::MeasurementSeriesDetail`] object.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # tokio_test::block_on(async {
+    /// # use ocptv::output::*;
+    /// let dut = DutInfo::new("my_dut");
+    /// let run = TestRun::new("diagnostic_name", "1.0").start(dut).await?;
+    /// let series = run.add_measurement_series_detail(
+    ///     MeasurementSeriesDetail::builder("temperature").unit("C").build(),
+    /// );
+    ///
+    /// # Ok::<(), OcptvError>(())
+    /// # });
+    /// ```
+    pub fn add_measurement_series_detail(
+        &self,
+        detail: tv::MeasurementSeriesDetail,
+    ) -> tv::RunMeasurementSeries {
+        // same counter as the step series, so that the ids stay unique in the run scope
+        let series_id = match &detail.id {
+            tv::Ident::Auto => {
+                let seqno = self.series_seqno.fetch_add(1, Ordering::AcqRel);
+                match &self.run.series_id_generator {
+                    Some(generator) => generator(seqno),
+                    None => format!("run_series{}", seqno),
+                }
+            }
+            tv::Ident::Exact(value) => value.to_owned(),
+        };
+
+        tv::RunMeasurementSeries::new(&series_id, detail)
+    }
+
+    /// Runs independent steps concurrently, each in its own tokio task, taking care of
+    /// starting and ending them, see [`TestStep::scope`]. Returns once all the steps have
+    /// ended, with the first error returned by any of them, if any.
+    ///
+    /// The step ids are assigned in the order of `steps`. The artifacts of the steps are
+    /// interleaved in the output, but each one is written whole, with strictly increasing
+    /// sequence numbers. If a step panics, it's ended with the `ERROR` status and the
+    /// panic is returned as an [`tv::OcptvError::Other`] error.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # tokio_test::block_on(async {
+    /// # use ocptv::output::*;
+    /// let dut = DutInfo::new("my_dut");
+    /// let run = TestRun::new("diagnostic_name", "1.0").start(dut).await?;
+    ///
+    /// run.spawn_steps(vec![
+    ///     SpawnedStep::new("fan", |s| async move {
+    ///         s.add_measurement("fan_speed", 1200).await?;
+    ///         Ok(TestStatus::Complete)
+    ///     }),
+    ///     SpawnedStep::new("memory", |s| async move {
+    ///         s.add_log(LogSeverity::Info, "no ecc errors").await?;
+    ///         Ok(TestStatus::Complete)
+    ///     }),
+    /// ])
+    /// .await?;
+    ///
+    /// run.end(TestStatus::Complete, TestResult::Pass).await?;
+    ///
+    /// # Ok::<(), OcptvError>(())
+    /// # });
+    /// ```
+    pub async fn spawn_steps(&self, steps: Vec<SpawnedStep>) -> Result<(), tv::OcptvError> {
+        let tasks = steps
+            .into_iter()
+            .map(|SpawnedStep { name, body }| {
+                let step = self.add_step(&name);
+                (step.detached(), tokio::spawn(step.scope(body)))
+            })
+            .collect::<Vec<_>>();
+
+        // wait for all the steps, even after an error, so none is left running
+        let mut result = Ok(());
+        for (step, task) in tasks {
+            let r = match task.await {
+                Ok(r) => r,
+                Err(e) => {
+                    // the step panicked inside its scope, after it was started, so it
+                    // still has to be ended; the panic is the error to report
+                    let _ = step.end(tv::TestStatus::Error).await;
+                    Err(tv::OcptvError::Other(Box::new(e)))
+                }
+            };
+            if result.is_ok() {
+                result = r;
+            }
+        }
+        result
+    }
+
+    /// Wraps the started run in a [`RunGuard`] which emits the `testRunEnd` artifact
+    /// with the given default status and result, if the run is dropped without being ended.
+    ///
+    /// See [`RunGuard`] for details.
+    ///
+    /// # Panics
+    ///
+    /// Panics if not called from inside a multi-threaded tokio runtime.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # tokio::runtime::Runtime::new().unwrap().block_on(async {
+    /// # use ocptv::output::*;
+    /// let dut = DutInfo::builder("my_dut").build();
+    /// let run = TestRun::new("diagnostic_name", "1.0")
+    ///     .start(dut)
+    ///     .await?
+    ///     .guard(TestStatus::Error, TestResult::Fail);
+    ///
+    /// run.add_log(LogSeverity::Info, "message").await?;
+    /// run.end(TestStatus::Complete, TestResult::Pass).await?;
+    ///
+    /// # Ok::<(), OcptvError>(())
+    /// # });
+    /// ```
+    #[cfg(feature = "run-guard")]
+    pub fn guard(
+        self,
+        default_status: spec::TestStatus,
+        default_result: spec::TestResult,
+    ) -> RunGuard {
+        let handle = tokio::runtime::Handle::current();
+        assert!(
+            handle.runtime_flavor() != tokio::runtime::RuntimeFlavor::CurrentThread,
+            "run guard requires a multi-threaded tokio runtime"
+        );
+
+        RunGuard {
+            run: Some(self),
+            default_status,
+            default_result,
+            abandoned_symptom: None,
+            handle: Some(handle),
+        }
+    }
+
+    /// Wraps the started run in a [`RunGuard`] that must be explicitly completed with
+    /// [`RunGuard::complete`]. With the `run-guard` feature, if the guard is dropped
+    /// instead, the run is recorded as abandoned: a run error with the
+    /// [`ABANDONED_RUN_SYMPTOM`] symptom is emitted, then the run ends with an `ERROR`
+    /// status and a `FAIL` result.
+    ///
+    /// Unlike `StartedTestRun::guard`, this works on any tokio runtime. See [`RunGuard`]
+    /// for details.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # tokio_test::block_on(async {
+    /// # use ocptv::output::*;
+    /// let dut = DutInfo::builder("my_dut").build();
+    /// let run = TestRun::new("diagnostic_name", "1.0")
+    ///     .start(dut)
+    ///     .await?
+    ///     .start_scope();
+    ///
+    /// run.add_log(LogSeverity::Info, "message").await?;
+    /// run.complete(TestStatus::Complete, TestResult::Pass).await?;
+    ///
+    /// # Ok::<(), OcptvError>(())
+    /// # });
+    /// ```
+    pub fn start_scope(self) -> RunGuard {
+        RunGuard {
+            run: Some(self),
+            #[cfg(feature = "run-guard")]
+            default_status: spec::TestStatus::Error,
+            #[cfg(feature = "run-guard")]
+            default_result: spec::TestResult::Fail,
+            #[cfg(feature = "run-guard")]
+            abandoned_symptom: Some(ABANDONED_RUN_SYMPTOM),
+            #[cfg(feature = "run-guard")]
+            handle: tokio::runtime::Handle::try_current().ok(),
+        }
+    }
+
+    /// Waits for `token` to be cancelled, then ends the run with the given status and result.
+    /// This keeps the output stream well-formed when a long run is aborted by the harness.
+    ///
+    /// Any in-flight step should be ended before the token is cancelled, otherwise its
+    /// artifacts may follow the `testRunEnd` artifact. The run must not be used after
+    /// cancellation. If the run ends normally instead, drop the returned future.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # tokio_test::block_on(async {
+    /// # use std::sync::Arc;
+    /// # use ocptv::output::*;
+    /// use tokio_util::sync::CancellationToken;
+    ///
+    /// let dut = DutInfo::builder("my_dut").build();
+    /// let run = Arc::new(TestRun::new("diagnostic_name", "1.0").start(dut).await?);
+    ///
+    /// let token = CancellationToken::new();
+    /// let ender = tokio::spawn({
+    ///     let run = Arc::clone(&run);
+    ///     let token = token.clone();
+    ///     async move {
+    ///         run.end_on_cancel(token, TestStatus::Skip, TestResult::NotApplicable)
+    ///             .await
+    ///     }
+    /// });
+    ///
+    /// // harness aborts the run
+    /// token.cancel();
+    /// ender.await.unwrap()?;
+    ///
+    /// # Ok::<(), OcptvError>(())
+    /// # });
+    /// ```
+    #[cfg(feature = "tokio-util")]
+    pub async fn end_on_cancel(
+        &self,
+        token: tokio_util::sync::CancellationToken,
+        status: spec::TestStatus,
+        result: spec::TestResult,
+    ) -> Result<(), tv::OcptvError> {
+        token.cancelled().await;
+        self.end_impl(status, result).await
+    }
+}
+
+/// A started test run that emits the `testRunEnd` artifact when dropped, unless
+/// [`RunGuard::end`] was called. This keeps the output stream well-formed when the
+/// run is not ended explicitly, eg. on early returns or panics.
+///
+/// Since there is no async drop, the guard holds a handle to the tokio runtime it was
+/// created in, and the drop blocks the current thread until the end artifact is written.
+/// Blocking needs a multi-threaded runtime: on a current-thread runtime the write couldn't
+/// make progress from a drop, so `StartedTestRun::guard` panics there, and a guard
+/// made by [`StartedTestRun::start_scope`] spawns the write onto the runtime instead,
+/// which only happens if the runtime keeps running after the drop.
+///
+/// Writing from the drop requires the `run-guard` feature; without it, a dropped guard
+/// doesn't emit anything, and only [`StartedTestRun::start_scope`] is available.
+pub struct RunGuard {
+    run: Option<StartedTestRun>,
+
+    #[cfg(feature = "run-guard")]
+    default_status: spec::TestStatus,
+    #[cfg(feature = "run-guard")]
+    default_result: spec::TestResult,
+    // if set, an error with this symptom is emitted before the end on drop
+    #[cfg(feature = "run-guard")]
+    abandoned_symptom: Option<&'static str>,
+
+    // runtime to write from on drop, if the guard was created in one
+    #[cfg(feature = "run-guard")]
+    handle: Option<tokio::runtime::Handle>,
+}
+
+impl RunGuard {
+    // note: the run is only taken out of the guard when consuming it in `end` or `drop`
+    fn run(&self) -> &StartedTestRun {
+        self.run.as_ref().expect("run guard was already ended")
+    }
+
+    /// Ends the test run, disarming the guard.
+    /// See [`StartedTestRun::end`] for details.
+    pub async fn end(
+        mut self,
+        status: spec::TestStatus,
+        result: spec::TestResult,
+    ) -> Result<(), tv::OcptvError> {
+        match self.run.take() {
+            Some(run) => run.end(status, result).await,
+            None => Ok(()),
+        }
+    }
+
+    /// Completes a run scope started with [`StartedTestRun::start_scope`], disarming
+    /// the guard. Same as [`RunGuard::end`].
+    pub async fn complete(
+        self,
+        status: spec::TestStatus,
+        result: spec::TestResult,
+    ) -> Result<(), tv::OcptvError> {
+        self.end(status, result).await
+    }
+
+    delegate! {
+        to self.run() {
+            pub async fn add_log(&self, severity: spec::LogSeverity, msg: &str) -> Result<(), tv::OcptvError>;
+            pub async fn add_log_detail(&self, log: log::Log) -> Result<(), tv::OcptvError>;
+            pub async fn add_log_at(
+                &self,
+                severity: spec::LogSeverity,
+                msg: &str,
+                timestamp: chrono::DateTime<chrono_tz::Tz>,
+            ) -> Result<(), tv::OcptvError>;
+
+            pub async fn add_error(&self, symptom: &str) -> Result<(), tv::OcptvError>;
+            pub async fn add_error_msg(&self, symptom: &str, msg: &str) -> Result<(), tv::OcptvError>;
+            pub async fn add_error_detail(&self, error: error::Error) -> Result<(), tv::OcptvError>;
+
+            pub fn add_step(&self, name: &str) -> TestStep;
+            pub fn add_measurement_series(&self, name: &str) -> tv::RunMeasurementSeries;
+            pub fn add_measurement_series_detail(
+                &self,
+                detail: tv::MeasurementSeriesDetail,
+            ) -> tv::RunMeasurementSeries;
+            pub async fn spawn_steps(&self, steps: Vec<SpawnedStep>) -> Result<(), tv::OcptvError>;
+
+            pub fn dut(&self) -> &dut::DutInfo;
+            pub fn parameters(&self) -> &Map<String, tv::Value>;
+            pub fn metadata(&self) -> &Map<String, tv::Value>;
+            pub async fn emit_raw(&self, value: tv::Value) -> Result<(), tv::OcptvError>;
+            pub fn stats(&self) -> RunStats;
+            pub fn result_from_steps(&self) -> spec::TestResult;
+        }
+    }
+}
+
+#[cfg(feature = "run-guard")]
+impl Drop for RunGuard {
+    fn drop(&mut self) {
+        let Some(run) = self.run.take() else {
+            return;
+        };
+
+        let status = self.default_status.clone();
+        let result = self.default_result.clone();
+        let abandoned_symptom = self.abandoned_symptom;
+
+        // errors are ignored here, there's nowhere to report them from a drop; the end
+        // is attempted even if the abandoned error couldn't be emitted
+        let end = async move {
+            if let Some(symptom) = abandoned_symptom {
+                let _ = run
+                    .add_error_msg(symptom, "test run was dropped without being completed")
+                    .await;
+            }
+            run.end_impl(status, result).await
+        };
+
+        let Some(handle) = self.handle.clone() else {
+            return;
+        };
+        if handle.runtime_flavor() == tokio::runtime::RuntimeFlavor::CurrentThread {
+            // can't block here without stalling the only thread that could do the write
+            handle.spawn(end);
+            return;
+        }
+        tokio::task::block_in_place(move || {
+            let _ = handle.block_on(end);
+        });
+    }
+}
+
+type StepFuture = Pin<Box<dyn Future<Output = Result<tv::TestStatus, tv::OcptvError>> + Send>>;
+
+/// Step to be run concurrently with others by [`StartedTestRun::spawn_steps`].
+/// The body is boxed, so the steps can have different bodies.
+pub struct SpawnedStep {
+    name: String,
+    body: Box<dyn FnOnce(ScopedTestStep) -> StepFuture + Send>,
+}
+
+impl SpawnedStep {
+    /// Builds a step named `name` which runs `func`, like in [`TestStep::scope`].
+    pub fn new<F, R>(name: &str, func: F) -> Self
+    where
+        R: Future<Output = Result<tv::TestStatus, tv::OcptvError>> + Send + 'static,
+        F: FnOnce(ScopedTestStep) -> R + Send + 'static,
+    {
+        SpawnedStep {
+            name: name.to_owned(),
+            body: Box::new(move |s| Box::pin(func(s))),
+        }
     }
 }
 
@@ -528,12 +1498,31 @@ impl ScopedTestRun {
         to self.run {
             pub async fn add_log(&self, severity: spec::LogSeverity, msg: &str) -> Result<(), tv::OcptvError>;
             pub async fn add_log_detail(&self, log: log::Log) -> Result<(), tv::OcptvError>;
+            pub async fn add_log_at(
+                &self,
+                severity: spec::LogSeverity,
+                msg: &str,
+                timestamp: chrono::DateTime<chrono_tz::Tz>,
+            ) -> Result<(), tv::OcptvError>;
 
             pub async fn add_error(&self, symptom: &str) -> Result<(), tv::OcptvError>;
             pub async fn add_error_msg(&self, symptom: &str, msg: &str) -> Result<(), tv::OcptvError>;
             pub async fn add_error_detail(&self, error: error::Error) -> Result<(), tv::OcptvError>;
 
             pub fn add_step(&self, name: &str) -> TestStep;
+            pub fn add_measurement_series(&self, name: &str) -> tv::RunMeasurementSeries;
+            pub fn add_measurement_series_detail(
+                &self,
+                detail: tv::MeasurementSeriesDetail,
+            ) -> tv::RunMeasurementSeries;
+            pub async fn spawn_steps(&self, steps: Vec<SpawnedStep>) -> Result<(), tv::OcptvError>;
+
+            pub fn dut(&self) -> &dut::DutInfo;
+            pub fn parameters(&self) -> &Map<String, tv::Value>;
+            pub fn metadata(&self) -> &Map<String, tv::Value>;
+            pub async fn emit_raw(&self, value: tv::Value) -> Result<(), tv::OcptvError>;
+            pub fn stats(&self) -> RunStats;
+            pub fn result_from_steps(&self) -> spec::TestResult;
         }
     }
 }
@@ -4,41 +4,79 @@
 // license that can be found in the LICENSE file or at
 // https://opensource.org/licenses/MIT.
 
+use std::collections::HashSet;
 use std::future::Future;
 use std::io;
 use std::sync::atomic::{self, Ordering};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
 use delegate::delegate;
 
 use crate::output as tv;
 use crate::spec::{self, TestStepArtifactImpl};
-use tv::OcptvError;
 use tv::{config, diagnosis, emitter, error, file, log, measure, Ident};
 
+/// Name of the measurement emitted by [`TestStep::scope`] with automatic step timing.
+const STEP_DURATION_MEASUREMENT: &str = "step_duration";
+
+/// Content field holding the type tag of an [`Extension`].
+pub(crate) const EXTENSION_TYPE_FIELD: &str = "@type";
+
+/// Message of the logs emitted by [`StartedTestStep::with_heartbeat`].
+const HEARTBEAT_MESSAGE: &str = "heartbeat";
+
 /// A single test step in the scope of a [`tv::TestRun`].
 ///
+/// Steps are created in two phases. [`tv::StartedTestRun::add_step`] only allocates the
+/// step id and never touches the output, so it can't fail. The `testStepStart` artifact
+/// is emitted by [`TestStep::start`] (or [`TestStep::scope`]), which is where any output
+/// error surfaces. A step that is dropped before being started emits nothing.
+///
 /// ref: <https://github.com/opencomputeproject/ocp-diag-core/tree/main/json_spec#test-step-artifacts>
 pub struct TestStep {
     name: String,
 
     emitter: Arc<StepEmitter>,
+    // shared by all steps in the run, so that series ids are unique in the run scope
+    series_seqno: Arc<atomic::AtomicU64>,
+    series_id_generator: Option<Arc<config::SeriesIdGenerator>>,
+    // shared by all steps in the run, set once any of them ends with an ERROR status
+    step_errored: Arc<atomic::AtomicBool>,
+    auto_timing: bool,
+    inline_file_max_size: usize,
 }
 
 impl TestStep {
     // note: this object is crate public but users should only construct
     // instances through the `StartedTestRun.add_step` api
-    pub(crate) fn new(id: &str, name: &str, run_emitter: Arc<emitter::JsonEmitter>) -> Self {
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn new(
+        id: &str,
+        name: &str,
+        run_emitter: Arc<emitter::JsonEmitter>,
+        series_seqno: Arc<atomic::AtomicU64>,
+        series_id_generator: Option<Arc<config::SeriesIdGenerator>>,
+        step_errored: Arc<atomic::AtomicBool>,
+        auto_timing: bool,
+        inline_file_max_size: usize,
+    ) -> Self {
         TestStep {
             name: name.to_owned(),
             emitter: Arc::new(StepEmitter {
                 step_id: id.to_owned(),
                 emitter: run_emitter,
             }),
+            series_seqno,
+            series_id_generator,
+            step_errored,
+            auto_timing,
+            inline_file_max_size,
         }
     }
 
-    /// Starts the test step.
+    /// Starts the test step, emitting the `testStepStart` artifact. Returns an error if
+    /// the artifact could not be written.
     ///
     /// ref: <https://github.com/opencomputeproject/ocp-diag-core/tree/main/json_spec#teststepstart>
     ///
@@ -63,10 +101,31 @@ impl TestStep {
 
         Ok(StartedTestStep {
             step: self,
+            measurement_failed: Arc::new(atomic::AtomicBool::new(false)),
             measurement_seqno: Arc::new(atomic::AtomicU64::new(0)),
+            heartbeat: Arc::new(Mutex::new(None)),
         })
     }
 
+    // handle for ending this step from outside of its scope, eg. after the scope panicked;
+    // doesn't emit the `testStepStart` artifact. See `StartedTestRun::spawn_steps`
+    pub(crate) fn detached(&self) -> StartedTestStep {
+        StartedTestStep {
+            step: TestStep {
+                name: self.name.clone(),
+                emitter: Arc::clone(&self.emitter),
+                series_seqno: Arc::clone(&self.series_seqno),
+                series_id_generator: self.series_id_generator.clone(),
+                step_errored: Arc::clone(&self.step_errored),
+                auto_timing: self.auto_timing,
+                inline_file_max_size: self.inline_file_max_size,
+            },
+            measurement_failed: Arc::new(atomic::AtomicBool::new(false)),
+            measurement_seqno: Arc::new(atomic::AtomicU64::new(0)),
+            heartbeat: Arc::new(Mutex::new(None)),
+        }
+    }
+
     /// Builds a scope in the [`TestStep`] object, taking care of starting and
     /// ending it. View [`TestStep::start`] and [`StartedTestStep::end`] methods.
     /// After the scope is constructed, additional objects may be added to it.
@@ -74,6 +133,9 @@ impl TestStep {
     /// all the messages are emitted between the start and end messages, the order
     /// is respected and no messages is lost.
     ///
+    /// When [`tv::ConfigBuilder::auto_step_timing`] is enabled, a `step_duration` measurement
+    /// is emitted just before the step ends.
+    ///
     /// # Examples
     ///
     /// ```rust
@@ -103,10 +165,27 @@ impl TestStep {
         F: FnOnce(ScopedTestStep) -> R + Send + 'static,
     {
         let step = Arc::new(self.start().await?);
+        let started_at = step.step.emitter.timestamp_provider().now();
+
         let status = func(ScopedTestStep {
             step: Arc::clone(&step),
         })
         .await?;
+
+        if step.step.auto_timing {
+            // use the timestamp provider rather than a monotonic clock, so the duration
+            // agrees with the timestamps of the surrounding artifacts
+            let elapsed = step.step.emitter.timestamp_provider().now() - started_at;
+            // microseconds only overflow after ~292k years, so fall back to millis then
+            let seconds = match elapsed.num_microseconds() {
+                Some(micros) => micros as f64 / 1e6,
+                None => elapsed.num_milliseconds() as f64 / 1e3,
+            };
+            let duration = measure::Measurement::builder(STEP_DURATION_MEASUREMENT, seconds)
+                .unit("s")
+                .build();
+            step.add_measurement_detail(duration).await?;
+        }
         step.end_impl(status).await?;
 
         Ok(())
@@ -116,13 +195,36 @@ impl TestStep {
 /// TODO: docs
 pub struct StartedTestStep {
     step: TestStep,
+
+    // set once any measurement in this step fails its validators;
+    // shared by all the handles returned by `clone_handle`
+    measurement_failed: Arc<atomic::AtomicBool>,
+    // ordinal of the next measurement in this step, see `add_measurement_with_ordinal`
     measurement_seqno: Arc<atomic::AtomicU64>,
+
+    // background task started by `with_heartbeat`, stopped when the step ends
+    heartbeat: Arc<Mutex<Option<Heartbeat>>>,
+}
+
+struct Heartbeat {
+    stop: tokio::sync::oneshot::Sender<()>,
+    task: tokio::task::JoinHandle<()>,
 }
 
 impl StartedTestStep {
     // note: keep the self-consuming method for crate api, but use this one internally,
     // since `StartedTestStep::end` only needs to take ownership for syntactic reasons
     async fn end_impl(&self, status: tv::TestStatus) -> Result<(), tv::OcptvError> {
+        let heartbeat = self.heartbeat.lock().unwrap().take();
+        if let Some(Heartbeat { stop, task }) = heartbeat {
+            // the task may have already exited on an output error, so ignore both results
+            let _ = stop.send(());
+            let _ = task.await;
+        }
+
+        if status == tv::TestStatus::Error {
+            self.step.step_errored.store(true, Ordering::Release);
+        }
         let end = TestStepArtifactImpl::TestStepEnd(spec::TestStepEnd { status });
 
         self.step.emitter.emit(&end).await?;
@@ -151,6 +253,154 @@ impl StartedTestStep {
         self.end_impl(status).await
     }
 
+    /// Ends the test step as skipped, eg. when its preconditions aren't met.
+    /// The reason is emitted as an INFO log just before the `testStepEnd` artifact.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # tokio_test::block_on(async {
+    /// # use ocptv::output::*;
+    /// let dut = DutInfo::new("my_dut");
+    /// let run = TestRun::new("diagnostic_name", "1.0").start(dut).await?;
+    ///
+    /// let step = run.add_step("step_name").start().await?;
+    /// step.skip("no fans detected").await?;
+    ///
+    /// # Ok::<(), OcptvError>(())
+    /// # });
+    /// ```
+    pub async fn skip(self, reason: &str) -> Result<(), tv::OcptvError> {
+        self.add_log(spec::LogSeverity::Info, reason).await?;
+        self.end_impl(tv::TestStatus::Skip).await
+    }
+
+    /// Starts a background task that emits a DEBUG log every `interval` until the step
+    /// ends, so that a consumer watching the output can tell a long running step from
+    /// a hung one. The task is stopped before the `testStepEnd` artifact is emitted,
+    /// so no heartbeat follows it. Calling this again replaces the previous heartbeat.
+    ///
+    /// Must be called from within a tokio runtime. Panics if `interval` is zero.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # tokio_test::block_on(async {
+    /// # use std::time::Duration;
+    /// # use ocptv::output::*;
+    /// let dut = DutInfo::new("my_dut");
+    /// let run = TestRun::new("diagnostic_name", "1.0").start(dut).await?;
+    ///
+    /// let step = run
+    ///     .add_step("step_name")
+    ///     .start()
+    ///     .await?
+    ///     .with_heartbeat(Duration::from_secs(30));
+    /// step.end(TestStatus::Complete).await?;
+    ///
+    /// # Ok::<(), OcptvError>(())
+    /// # });
+    /// ```
+    pub fn with_heartbeat(self, interval: Duration) -> Self {
+        let (stop, mut stopped) = tokio::sync::oneshot::channel::<()>();
+        let emitter = Arc::clone(&self.step.emitter);
+
+        let task = tokio::spawn(async move {
+            let mut ticks =
+                tokio::time::interval_at(tokio::time::Instant::now() + interval, interval);
+            loop {
+                tokio::select! {
+                    _ = &mut stopped => break,
+                    _ = ticks.tick() => {
+                        let log = log::Log::builder(HEARTBEAT_MESSAGE)
+                            .severity(spec::LogSeverity::Debug)
+                            .build();
+                        if emitter
+                            .emit(&TestStepArtifactImpl::Log(log.to_artifact()))
+                            .await
+                            .is_err()
+                        {
+                            break;
+                        }
+                    }
+                }
+            }
+        });
+
+        let previous = self
+            .heartbeat
+            .lock()
+            .unwrap()
+            .replace(Heartbeat { stop, task });
+        if let Some(previous) = previous {
+            let _ = previous.stop.send(());
+        }
+        self
+    }
+
+    /// Returns another handle to this step, eg. to add measurements from several
+    /// concurrent tasks. All the handles emit artifacts under the same step id and share
+    /// the measurement series counter and the state tracked by the step, like
+    /// [`StartedTestStep::all_measurements_passed`].
+    ///
+    /// Any of the handles may end the step, which should happen only once.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # tokio_test::block_on(async {
+    /// # use ocptv::output::*;
+    /// let dut = DutInfo::new("my_dut");
+    /// let run = TestRun::new("diagnostic_name", "1.0").start(dut).await?;
+    /// let step = run.add_step("step_name").start().await?;
+    ///
+    /// let handle = step.clone_handle();
+    /// let task = tokio::spawn(async move { handle.add_measurement("fan0_speed", 1600).await });
+    /// step.add_measurement("fan1_speed", 1650).await?;
+    /// task.await.unwrap()?;
+    ///
+    /// step.end(TestStatus::Complete).await?;
+    ///
+    /// # Ok::<(), OcptvError>(())
+    /// # });
+    /// ```
+    pub fn clone_handle(&self) -> StartedTestStep {
+        StartedTestStep {
+            step: TestStep {
+                name: self.step.name.clone(),
+                emitter: Arc::clone(&self.step.emitter),
+                series_seqno: Arc::clone(&self.step.series_seqno),
+                series_id_generator: self.step.series_id_generator.clone(),
+                step_errored: Arc::clone(&self.step.step_errored),
+                auto_timing: self.step.auto_timing,
+                inline_file_max_size: self.step.inline_file_max_size,
+            },
+            measurement_failed: Arc::clone(&self.measurement_failed),
+            measurement_seqno: Arc::clone(&self.measurement_seqno),
+            heartbeat: Arc::clone(&self.heartbeat),
+        }
+    }
+
+    /// Returns the step identifier, as emitted in the `testStepId` field of its artifacts.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # tokio_test::block_on(async {
+    /// # use ocptv::output::*;
+    /// let dut = DutInfo::new("my_dut");
+    /// let run = TestRun::new("diagnostic_name", "1.0").start(dut).await?;
+    ///
+    /// let step = run.add_step("step_name").start().await?;
+    /// assert_eq!(step.id(), "step0");
+    ///
+    /// # Ok::<(), OcptvError>(())
+    /// # });
+    /// ```
+    pub fn id(&self) -> &str {
+        &self.step.emitter.step_id
+    }
+
     /// Emits Log message.
     /// This method accepts a [`tv::LogSeverity`] to define the severity
     /// and a [`String`] for the message.
@@ -207,6 +457,44 @@ impl StartedTestStep {
         Ok(())
     }
 
+    /// Emits a Log message with structured fields, for log aggregators.
+    ///
+    /// The spec `log` object has no field for them, so the fields are appended to the
+    /// message in logfmt style, with json values, eg. `fan speed out of range fan="fan0"
+    /// rpm=3600`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # tokio_test::block_on(async {
+    /// # use ocptv::output::*;
+    /// let dut = DutInfo::new("my_dut");
+    /// let run = TestRun::new("diagnostic_name", "1.0").start(dut).await?;
+    ///
+    /// let step = run.add_step("step_name").start().await?;
+    /// step.add_log_fields(
+    ///     LogSeverity::Warning,
+    ///     "fan speed out of range",
+    ///     vec![("fan", "fan0".into()), ("rpm", 3600.into())],
+    /// ).await?;
+    /// step.end(TestStatus::Complete).await?;
+    ///
+    /// # Ok::<(), OcptvError>(())
+    /// # });
+    /// ```
+    pub async fn add_log_fields(
+        &self,
+        severity: spec::LogSeverity,
+        msg: &str,
+        fields: Vec<(&str, tv::Value)>,
+    ) -> Result<(), tv::OcptvError> {
+        let msg = fields
+            .into_iter()
+            .fold(msg.to_owned(), |msg, (k, v)| format!("{} {}={}", msg, k, v));
+
+        self.add_log(severity, &msg).await
+    }
+
     /// Emits Log message.
     /// This method accepts a [`tv::Log`] object.
     ///
@@ -235,12 +523,50 @@ impl StartedTestStep {
     pub async fn add_log_detail(&self, log: log::Log) -> Result<(), tv::OcptvError> {
         self.step
             .emitter
-            .emit(&TestStepArtifactImpl::Log(log.to_artifact()))
+            .emit_at(
+                &TestStepArtifactImpl::Log(log.to_artifact()),
+                log.timestamp(),
+            )
             .await?;
 
         Ok(())
     }
 
+    /// Emits a Log message with the given `timestamp` instead of the current time,
+    /// eg. when replaying captured logs. See [`log::LogBuilder::timestamp`].
+    ///
+    /// ref: <https://github.com/opencomputeproject/ocp-diag-core/tree/main/json_spec#log>
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # tokio_test::block_on(async {
+    /// # use ocptv::output::*;
+    /// let dut = DutInfo::new("my_dut");
+    /// let run = TestRun::new("diagnostic_name", "1.0").start(dut).await?;
+    /// let step = run.add_step("step_name").start().await?;
+    ///
+    /// let captured_at = chrono::DateTime::from_timestamp(1_700_000_000, 0)
+    ///     .unwrap()
+    ///     .with_timezone(&chrono_tz::UTC);
+    /// step.add_log_at(LogSeverity::Info, "fan0 spun up", captured_at).await?;
+    ///
+    /// # Ok::<(), OcptvError>(())
+    /// # });
+    /// ```
+    pub async fn add_log_at(
+        &self,
+        severity: spec::LogSeverity,
+        msg: &str,
+        timestamp: chrono::DateTime<chrono_tz::Tz>,
+    ) -> Result<(), tv::OcptvError> {
+        let log = log::Log::builder(msg)
+            .severity(severity)
+            .timestamp(timestamp)
+            .build();
+        self.add_log_detail(log).await
+    }
+
     /// Emits an Error symptom.
     /// This method accepts a [`String`] to define the symptom.
     ///
@@ -376,6 +702,91 @@ impl StartedTestStep {
         Ok(())
     }
 
+    /// Emits several Error messages as a group, in order. No other artifact is
+    /// emitted between them, even by concurrent tasks.
+    ///
+    /// ref: <https://github.com/opencomputeproject/ocp-diag-core/tree/main/json_spec#error>
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # tokio_test::block_on(async {
+    /// # use ocptv::output::*;
+    /// let dut = DutInfo::new("my_dut");
+    /// let run = TestRun::new("diagnostic_name", "1.0").start(dut).await?;
+    ///
+    /// let step = run.add_step("step_name").start().await?;
+    /// step.add_errors(&[
+    ///     Error::builder("pcie_link_down").build(),
+    ///     Error::builder("nvme_missing").build(),
+    /// ]).await?;
+    /// step.end(TestStatus::Complete).await?;
+    ///
+    /// # Ok::<(), OcptvError>(())
+    /// # });
+    /// ```
+    pub async fn add_errors(&self, errors: &[error::Error]) -> Result<(), tv::OcptvError> {
+        let artifacts = errors
+            .iter()
+            .map(|e| TestStepArtifactImpl::Error(e.to_artifact()))
+            .collect::<Vec<_>>();
+        self.step.emitter.emit_all(&artifacts).await?;
+
+        Ok(())
+    }
+
+    /// Emits several Error messages as a group, like [`StartedTestStep::add_errors`],
+    /// right after an [`ErrorCorrelation`] extension tagging them with `correlation_id`,
+    /// so consumers can treat them as a single incident.
+    ///
+    /// The spec `error` object has no field for the correlation id, hence the extension.
+    /// With [`tv::ConfigBuilder::strict_extensions`], [`ErrorCorrelation`] needs to be in
+    /// the registry.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # tokio_test::block_on(async {
+    /// # use ocptv::output::*;
+    /// let dut = DutInfo::new("my_dut");
+    /// let run = TestRun::new("diagnostic_name", "1.0").start(dut).await?;
+    ///
+    /// let step = run.add_step("step_name").start().await?;
+    /// step.add_correlated_errors(
+    ///     &[
+    ///         Error::builder("pcie_link_down").build(),
+    ///         Error::builder("nvme_missing").build(),
+    ///     ],
+    ///     "incident0",
+    /// ).await?;
+    /// step.end(TestStatus::Complete).await?;
+    ///
+    /// # Ok::<(), OcptvError>(())
+    /// # });
+    /// ```
+    pub async fn add_correlated_errors(
+        &self,
+        errors: &[error::Error],
+        correlation_id: &str,
+    ) -> Result<(), tv::OcptvError> {
+        let correlation = ErrorCorrelation {
+            correlation_id: correlation_id.to_owned(),
+            error_count: errors.len(),
+        };
+
+        let mut artifacts = vec![TestStepArtifactImpl::Extension(typed_extension(
+            &correlation,
+        )?)];
+        artifacts.extend(
+            errors
+                .iter()
+                .map(|e| TestStepArtifactImpl::Error(e.to_artifact())),
+        );
+        self.step.emitter.emit_all(&artifacts).await?;
+
+        Ok(())
+    }
+
     /// Emits a Measurement message.
     ///
     /// ref: <https://github.com/opencomputeproject/ocp-diag-core/tree/main/json_spec#measurement>
@@ -395,25 +806,17 @@ impl StartedTestStep {
     /// # Ok::<(), OcptvError>(())
     /// # });
     /// ```
-    pub async fn add_measurement<V: Into<tv::Value>>(
+    pub async fn add_measurement<V: Into<measure::MeasurementValue>>(
         &self,
         name: &str,
         value: V,
     ) -> Result<(), tv::OcptvError> {
-        let measurement = measure::Measurement::new(name, value);
-
-        self.step
-            .emitter
-            .emit(&TestStepArtifactImpl::Measurement(
-                measurement.to_artifact(),
-            ))
-            .await?;
-
-        Ok(())
+        self.add_measurement_detail(measure::Measurement::new(name, value))
+            .await
     }
 
     /// Emits a Measurement message.
-    /// This method accepts a [`tv::Error`] object.
+    /// This method accepts a [`tv::Measurement`] object.
     ///
     /// ref: <https://github.com/opencomputeproject/ocp-diag-core/tree/main/json_spec#measurement>
     ///
@@ -444,14 +847,179 @@ impl StartedTestStep {
         &self,
         detail: measure::Measurement,
     ) -> Result<(), tv::OcptvError> {
-        self.step
+        self.add_measurement_with_ordinal(detail).await?;
+
+        Ok(())
+    }
+
+    /// Emits a Measurement message, like [`StartedTestStep::add_measurement_detail`], and
+    /// returns the ordinal of the measurement in this step.
+    ///
+    /// The ordinal is 0 for the first measurement written, then increases by one for
+    /// each measurement written through any handle of the step, see
+    /// [`StartedTestStep::clone_handle`]. Unlike the sequence number, it's local to the
+    /// step and only counts measurements, eg. for correlating them with other data.
+    /// Measurements that fail to be written don't get an ordinal, and the ordinals of the
+    /// ones written concurrently follow the order in which they appear in the output.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # tokio_test::block_on(async {
+    /// # use ocptv::output::*;
+    /// let dut = DutInfo::new("my_dut");
+    /// let run = TestRun::new("diagnostic_name", "1.0").start(dut).await?;
+    /// let step = run.add_step("step_name").start().await?;
+    ///
+    /// step.add_measurement("fan0", 1600).await?;
+    /// let ordinal = step
+    ///     .add_measurement_with_ordinal(Measurement::new("fan1", 1700))
+    ///     .await?;
+    /// assert_eq!(ordinal, 1);
+    ///
+    /// step.end(TestStatus::Complete).await?;
+    ///
+    /// # Ok::<(), OcptvError>(())
+    /// # });
+    /// ```
+    pub async fn add_measurement_with_ordinal(
+        &self,
+        detail: measure::Measurement,
+    ) -> Result<u64, tv::OcptvError> {
+        if !detail.passes_validators() {
+            self.measurement_failed.store(true, Ordering::Release);
+        }
+
+        // the ordinal is taken under the emit lock, so it follows the output order
+        let ordinal = self
+            .step
             .emitter
-            .emit(&spec::TestStepArtifactImpl::Measurement(
-                detail.to_artifact(),
-            ))
+            .emit_then(
+                &spec::TestStepArtifactImpl::Measurement(
+                    detail.to_artifact_with(self.step.emitter.non_finite_policy()),
+                ),
+                || self.measurement_seqno.fetch_add(1, Ordering::AcqRel),
+            )
             .await?;
 
-        Ok(())
+        Ok(ordinal)
+    }
+
+    /// Emits a Measurement with the given validators, followed by a Diagnosis with the
+    /// outcome of evaluating them against the value. The diagnosis verdict is derived from
+    /// `verdict_prefix`, as `{verdict_prefix}_pass` or `{verdict_prefix}_fail`, so the two
+    /// artifacts can be traced to each other.
+    ///
+    /// Returns whether all the validators passed. See [`measure::Validator::evaluate`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # tokio_test::block_on(async {
+    /// # use ocptv::output::*;
+    /// let dut = DutInfo::new("my_dut");
+    /// let run = TestRun::new("diagnostic_name", "1.0").start(dut).await?;
+    /// let step = run.add_step("step_name").start().await?;
+    ///
+    /// let passed = step
+    ///     .add_measurement_and_diagnosis(
+    ///         "fan_speed",
+    ///         1600,
+    ///         &[Validator::builder(ValidatorType::GreaterThan, 1000).build()],
+    ///         "fan_speed",
+    ///     )
+    ///     .await?;
+    /// assert!(passed);
+    ///
+    /// # Ok::<(), OcptvError>(())
+    /// # });
+    /// ```
+    pub async fn add_measurement_and_diagnosis<V: Into<measure::MeasurementValue>>(
+        &self,
+        name: &str,
+        value: V,
+        validators: &[measure::Validator],
+        verdict_prefix: &str,
+    ) -> Result<bool, tv::OcptvError> {
+        let detail = measure::Measurement::builder(name, value)
+            .add_validators(validators)
+            .build();
+        let passed = detail.passes_validators();
+
+        self.add_measurement_detail(detail).await?;
+
+        let (verdict, diagnosis_type) = match passed {
+            true => (
+                format!("{}_pass", verdict_prefix),
+                spec::DiagnosisType::Pass,
+            ),
+            false => (
+                format!("{}_fail", verdict_prefix),
+                spec::DiagnosisType::Fail,
+            ),
+        };
+        self.add_diagnosis(&verdict, diagnosis_type).await?;
+
+        Ok(passed)
+    }
+
+    /// Returns whether all the measurements added to this step so far passed their
+    /// validators, as evaluated by [`measure::Validator::evaluate`]. Measurements without
+    /// validators always pass, and so does a step without measurements.
+    ///
+    /// Measurement series elements are not considered.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # tokio_test::block_on(async {
+    /// # use ocptv::output::*;
+    /// let dut = DutInfo::new("my_dut");
+    /// let run = TestRun::new("diagnostic_name", "1.0").start(dut).await?;
+    /// let step = run.add_step("step_name").start().await?;
+    ///
+    /// let measurement = Measurement::builder("fan_speed", 1600)
+    ///     .add_validator(Validator::builder(ValidatorType::GreaterThan, 1000).build())
+    ///     .build();
+    /// step.add_measurement_detail(measurement).await?;
+    ///
+    /// let status = match step.all_measurements_passed() {
+    ///     true => TestStatus::Complete,
+    ///     false => TestStatus::Error,
+    /// };
+    /// step.end(status).await?;
+    ///
+    /// # Ok::<(), OcptvError>(())
+    /// # });
+    /// ```
+    pub fn all_measurements_passed(&self) -> bool {
+        !self.measurement_failed.load(Ordering::Acquire)
+    }
+
+    /// Returns a handle that adds measurements to this step, tagging each of them as
+    /// part of the `group` logical group. See [`measure::MeasurementBuilder::group`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # tokio_test::block_on(async {
+    /// # use ocptv::output::*;
+    /// let dut = DutInfo::new("my_dut");
+    /// let run = TestRun::new("diagnostic_name", "1.0").start(dut).await?;
+    /// let step = run.add_step("step_name").start().await?;
+    ///
+    /// let rails = step.measurement_group("voltage_rails");
+    /// rails.add_measurement("rail_12v", 12.1).await?;
+    /// rails.add_measurement("rail_5v", 5.02).await?;
+    ///
+    /// # Ok::<(), OcptvError>(())
+    /// # });
+    /// ```
+    pub fn measurement_group(&self, group: &str) -> MeasurementGroup<'_> {
+        MeasurementGroup {
+            step: self,
+            group: group.to_owned(),
+        }
     }
 
     /// Create a Measurement Series (a time-series list of measurements).
@@ -500,20 +1068,60 @@ impl StartedTestStep {
         detail: measure::MeasurementSeriesDetail,
     ) -> tv::MeasurementSeries {
         // spec says this identifier is unique in the scope of the test run, so create it from
-        // the step identifier and a counter
+        // the step identifier and a counter shared by all the steps in the run; the counter
+        // alone also keeps custom generated ids unique
         // ref: https://github.com/opencomputeproject/ocp-diag-core/blob/main/json_spec/README.md#measurementseriesstart
         let series_id = match &detail.id {
-            Ident::Auto => format!(
-                "{}_series{}",
-                self.step.emitter.step_id,
-                self.measurement_seqno.fetch_add(1, Ordering::AcqRel)
-            ),
+            Ident::Auto => {
+                let seqno = self.step.series_seqno.fetch_add(1, Ordering::AcqRel);
+                match &self.step.series_id_generator {
+                    Some(generator) => generator(seqno),
+                    None => format!("{}_series{}", self.step.emitter.step_id, seqno),
+                }
+            }
             Ident::Exact(value) => value.to_owned(),
         };
 
         tv::MeasurementSeries::new(&series_id, detail, Arc::clone(&self.step.emitter))
     }
 
+    /// Adds a run scope measurement series to this step, see [`tv::RunMeasurementSeries`].
+    /// Starting the returned series emits a `measurementSeriesStart` in this step, with
+    /// the id shared by all the steps, and its elements continue the series indices.
+    ///
+    /// ref: <https://github.com/opencomputeproject/ocp-diag-core/tree/main/json_spec#measurementseriesstart>
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # tokio_test::block_on(async {
+    /// # use ocptv::output::*;
+    /// let dut = DutInfo::new("my_dut");
+    /// let run = TestRun::new("diagnostic_name", "1.0").start(dut).await?;
+    /// let temperature = run.add_measurement_series("temperature");
+    ///
+    /// let step = run.add_step("warmup").start().await?;
+    /// let series = step.add_run_measurement_series(&temperature).start().await?;
+    /// series.add_measurement(40).await?; // index 0
+    /// series.end().await?;
+    /// step.end(TestStatus::Complete).await?;
+    ///
+    /// let step = run.add_step("stress").start().await?;
+    /// let series = step.add_run_measurement_series(&temperature).start().await?;
+    /// series.add_measurement(75).await?; // index 1
+    /// series.end().await?;
+    /// step.end(TestStatus::Complete).await?;
+    ///
+    /// # Ok::<(), OcptvError>(())
+    /// # });
+    /// ```
+    pub fn add_run_measurement_series(
+        &self,
+        series: &tv::RunMeasurementSeries,
+    ) -> tv::MeasurementSeries {
+        series.in_step(Arc::clone(&self.step.emitter))
+    }
+
     /// Emits a Diagnosis message.
     ///
     /// ref: <https://github.com/opencomputeproject/ocp-diag-core/tree/main/json_spec#diagnosis>
@@ -590,6 +1198,35 @@ impl StartedTestStep {
         Ok(())
     }
 
+    /// Emits a Diagnosis message for a check that was skipped, with the reason in the
+    /// message. See [`diagnosis::Diagnosis::skip`] for the convention used.
+    ///
+    /// ref: <https://github.com/opencomputeproject/ocp-diag-core/tree/main/json_spec#diagnosis>
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # tokio_test::block_on(async {
+    /// # use ocptv::output::*;
+    /// let dut = DutInfo::new("my dut");
+    /// let run = TestRun::new("diagnostic_name", "1.0").start(dut).await?;
+    ///
+    /// let step = run.add_step("step_name").start().await?;
+    /// step.add_skip_diagnosis("ecc-check", "no ECC capable DIMMs").await?;
+    /// step.end(TestStatus::Skip).await?;
+    ///
+    /// # Ok::<(), OcptvError>(())
+    /// # });
+    /// ```
+    pub async fn add_skip_diagnosis(
+        &self,
+        verdict: &str,
+        reason: &str,
+    ) -> Result<(), tv::OcptvError> {
+        self.add_diagnosis_detail(diagnosis::Diagnosis::skip(verdict, reason))
+            .await
+    }
+
     /// Emits a File message.
     ///
     /// ref: <https://github.com/opencomputeproject/ocp-diag-core/tree/main/json_spec#file>
@@ -621,6 +1258,52 @@ impl StartedTestStep {
         Ok(())
     }
 
+    /// Emits a File message with the content inlined in the uri, as a base64 encoded
+    /// `data:` uri. See [`file::File::inline`].
+    ///
+    /// Returns an error, without emitting anything, if the content is larger than
+    /// [`tv::ConfigBuilder::inline_file_max_size`].
+    ///
+    /// ref: <https://github.com/opencomputeproject/ocp-diag-core/tree/main/json_spec#file>
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # tokio_test::block_on(async {
+    /// # use ocptv::output::*;
+    /// let dut = DutInfo::new("my_dut");
+    /// let run = TestRun::new("diagnostic_name", "1.0").start(dut).await?;
+    ///
+    /// let step = run.add_step("step_name").start().await?;
+    /// let regs = [0xde, 0xad, 0xbe, 0xef];
+    /// step.add_inline_file("regs", &regs, mime::APPLICATION_OCTET_STREAM).await?;
+    /// step.end(TestStatus::Complete).await?;
+    ///
+    /// # Ok::<(), OcptvError>(())
+    /// # });
+    /// ```
+    pub async fn add_inline_file(
+        &self,
+        name: &str,
+        content: &[u8],
+        content_type: mime::Mime,
+    ) -> Result<(), tv::OcptvError> {
+        if content.len() > self.step.inline_file_max_size {
+            return Err(tv::OcptvError::Other(
+                format!(
+                    "inline file `{}` has {} bytes, over the limit of {} bytes",
+                    name,
+                    content.len(),
+                    self.step.inline_file_max_size
+                )
+                .into(),
+            ));
+        }
+
+        self.add_file_detail(file::File::inline(name, content, content_type)?)
+            .await
+    }
+
     /// Emits a File message.
     /// This method accepts a [`tv::Error`] object.
     ///
@@ -687,12 +1370,141 @@ impl StartedTestStep {
     ) -> Result<(), tv::OcptvError> {
         let ext = TestStepArtifactImpl::Extension(spec::Extension {
             name: name.to_owned(),
-            content: serde_json::to_value(&any).map_err(|e| OcptvError::Format(Box::new(e)))?,
+            content: serde_json::to_value(&any)?,
         });
 
         self.step.emitter.emit(&ext).await?;
         Ok(())
     }
+
+    /// Emits an extension message for a type implementing [`Extension`]. The extension
+    /// name is [`Extension::NAME`], and the [`Extension::TYPE`] tag is injected into the
+    /// content as its `@type` field, replacing any existing one.
+    ///
+    /// Returns an error if `ext` doesn't serialize to a json object.
+    ///
+    /// ref: <https://github.com/opencomputeproject/ocp-diag-core/tree/main/json_spec#extension>
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # tokio_test::block_on(async {
+    /// # use ocptv::output::*;
+    /// let dut = DutInfo::new("my_dut");
+    /// let run = TestRun::new("diagnostic_name", "1.0").start(dut).await?;
+    /// let step = run.add_step("step_name").start().await?;
+    ///
+    /// #[derive(serde::Serialize)]
+    /// struct FanCurve { points: Vec<(u32, u32)> }
+    ///
+    /// impl Extension for FanCurve {
+    ///     const TYPE: &'static str = "FanCurve/v2";
+    ///     const NAME: &'static str = "fan_curve";
+    /// }
+    ///
+    /// step.add_extension_typed(FanCurve { points: vec![(30, 800), (70, 2000)] }).await?;
+    ///
+    /// # Ok::<(), OcptvError>(())
+    /// # });
+    /// ```
+    pub async fn add_extension_typed<E: Extension>(&self, ext: E) -> Result<(), tv::OcptvError> {
+        let ext = TestStepArtifactImpl::Extension(typed_extension(&ext)?);
+
+        self.step.emitter.emit(&ext).await?;
+        Ok(())
+    }
+}
+
+fn typed_extension<E: Extension>(ext: &E) -> Result<spec::Extension, tv::OcptvError> {
+    let tv::Value::Object(fields) = serde_json::to_value(ext)? else {
+        return Err(tv::OcptvError::Other(
+            format!("extension {} content is not a json object", E::TYPE).into(),
+        ));
+    };
+
+    let mut content = serde_json::Map::new();
+    content.insert(EXTENSION_TYPE_FIELD.to_owned(), E::TYPE.into());
+    content.extend(
+        fields
+            .into_iter()
+            .filter(|(k, _)| k != EXTENSION_TYPE_FIELD),
+    );
+
+    Ok(spec::Extension {
+        name: E::NAME.to_owned(),
+        content: tv::Value::Object(content),
+    })
+}
+
+/// An extension content type with a (usually versioned) type tag, emitted through
+/// [`StartedTestStep::add_extension_typed`].
+pub trait Extension: serde::Serialize {
+    /// Type tag emitted in the `@type` field of the extension content.
+    const TYPE: &'static str;
+
+    /// Name of the emitted extension, defaults to the type tag.
+    const NAME: &'static str = Self::TYPE;
+}
+
+/// Extension emitted by [`StartedTestStep::add_correlated_errors`] right before the
+/// errors it correlates, which are the next `errorCount` artifacts of the step.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ErrorCorrelation {
+    #[serde(rename = "correlationId")]
+    pub correlation_id: String,
+    #[serde(rename = "errorCount")]
+    pub error_count: usize,
+}
+
+impl Extension for ErrorCorrelation {
+    const TYPE: &'static str = "ErrorCorrelation/v1";
+    const NAME: &'static str = "error_correlation";
+}
+
+/// Set of the known extension type tags, eg. from a central registry shared by several
+/// teams. With [`tv::ConfigBuilder::strict_extensions`], emitting an extension whose
+/// content has an `@type` not in the registry, or no `@type` at all, fails.
+///
+/// # Examples
+///
+/// ```rust
+/// # use ocptv::output::*;
+/// #[derive(serde::Serialize)]
+/// struct FanCurve { points: Vec<(u32, u32)> }
+///
+/// impl Extension for FanCurve {
+///     const TYPE: &'static str = "FanCurve/v2";
+/// }
+///
+/// let registry = ExtensionRegistry::new()
+///     .register_type::<FanCurve>()
+///     .register("ThermalMap/v1");
+/// assert!(registry.contains("FanCurve/v2"));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ExtensionRegistry {
+    types: HashSet<String>,
+}
+
+impl ExtensionRegistry {
+    pub fn new() -> Self {
+        ExtensionRegistry::default()
+    }
+
+    /// Adds the `type_tag` to the known types.
+    pub fn register(mut self, type_tag: &str) -> Self {
+        self.types.insert(type_tag.to_owned());
+        self
+    }
+
+    /// Adds the [`Extension::TYPE`] of `E` to the known types.
+    pub fn register_type<E: Extension>(self) -> Self {
+        self.register(E::TYPE)
+    }
+
+    pub fn contains(&self, type_tag: &str) -> bool {
+        self.types.contains(type_tag)
+    }
 }
 
 /// TODO: docs
@@ -703,21 +1515,55 @@ pub struct ScopedTestStep {
 impl ScopedTestStep {
     delegate! {
         to self.step {
+            pub fn id(&self) -> &str;
+
             pub async fn add_log(&self, severity: spec::LogSeverity, msg: &str) -> Result<(), tv::OcptvError>;
+            pub async fn add_log_fields(
+                &self,
+                severity: spec::LogSeverity,
+                msg: &str,
+                fields: Vec<(&str, tv::Value)>,
+            ) -> Result<(), tv::OcptvError>;
             pub async fn add_log_detail(&self, log: log::Log) -> Result<(), tv::OcptvError>;
+            pub async fn add_log_at(
+                &self,
+                severity: spec::LogSeverity,
+                msg: &str,
+                timestamp: chrono::DateTime<chrono_tz::Tz>,
+            ) -> Result<(), tv::OcptvError>;
 
             pub async fn add_error(&self, symptom: &str) -> Result<(), tv::OcptvError>;
             pub async fn add_error_msg(&self, symptom: &str, msg: &str) -> Result<(), tv::OcptvError>;
             pub async fn add_error_detail(&self, error: error::Error) -> Result<(), tv::OcptvError>;
+            pub async fn add_errors(&self, errors: &[error::Error]) -> Result<(), tv::OcptvError>;
+            pub async fn add_correlated_errors(
+                &self,
+                errors: &[error::Error],
+                correlation_id: &str,
+            ) -> Result<(), tv::OcptvError>;
 
-            pub async fn add_measurement<V: Into<tv::Value>>(&self, name: &str, value: V) -> Result<(), tv::OcptvError>;
+            pub async fn add_measurement<V: Into<measure::MeasurementValue>>(&self, name: &str, value: V) -> Result<(), tv::OcptvError>;
             pub async fn add_measurement_detail(&self, detail: measure::Measurement) -> Result<(), tv::OcptvError>;
+            pub async fn add_measurement_with_ordinal(&self, detail: measure::Measurement) -> Result<u64, tv::OcptvError>;
+            pub fn measurement_group(&self, group: &str) -> MeasurementGroup<'_>;
+            pub fn all_measurements_passed(&self) -> bool;
+            pub async fn add_measurement_and_diagnosis<V: Into<measure::MeasurementValue>>(
+                &self,
+                name: &str,
+                value: V,
+                validators: &[measure::Validator],
+                verdict_prefix: &str,
+            ) -> Result<bool, tv::OcptvError>;
 
             pub fn add_measurement_series(&self, name: &str) -> tv::MeasurementSeries;
             pub fn add_measurement_series_detail(
                 &self,
                 detail: measure::MeasurementSeriesDetail,
             ) -> tv::MeasurementSeries;
+            pub fn add_run_measurement_series(
+                &self,
+                series: &tv::RunMeasurementSeries,
+            ) -> tv::MeasurementSeries;
 
             pub async fn add_diagnosis(
                 &self,
@@ -725,15 +1571,48 @@ impl ScopedTestStep {
                 diagnosis_type: spec::DiagnosisType,
             ) -> Result<(), tv::OcptvError>;
             pub async fn add_diagnosis_detail(&self, diagnosis: diagnosis::Diagnosis) -> Result<(), tv::OcptvError>;
+            pub async fn add_skip_diagnosis(&self, verdict: &str, reason: &str) -> Result<(), tv::OcptvError>;
 
             pub async fn add_file(&self, name: &str, uri: tv::Uri) -> Result<(), tv::OcptvError>;
             pub async fn add_file_detail(&self, file: file::File) -> Result<(), tv::OcptvError>;
+            pub async fn add_inline_file(&self, name: &str, content: &[u8], content_type: mime::Mime) -> Result<(), tv::OcptvError>;
 
             pub async fn add_extension<S: serde::Serialize>(&self, name: &str, any: S) -> Result<(), tv::OcptvError>;
+            pub async fn add_extension_typed<E: Extension>(&self, ext: E) -> Result<(), tv::OcptvError>;
         }
     }
 }
 
+/// Adds measurements to a step, tagged with a logical group name.
+/// Created by [`StartedTestStep::measurement_group`].
+pub struct MeasurementGroup<'a> {
+    step: &'a StartedTestStep,
+    group: String,
+}
+
+impl MeasurementGroup<'_> {
+    /// Emits a Measurement message in this group.
+    /// See [`StartedTestStep::add_measurement`].
+    pub async fn add_measurement<V: Into<measure::MeasurementValue>>(
+        &self,
+        name: &str,
+        value: V,
+    ) -> Result<(), tv::OcptvError> {
+        self.add_measurement_detail(measure::Measurement::new(name, value))
+            .await
+    }
+
+    /// Emits a Measurement message in this group, replacing any group set on `detail`.
+    /// See [`StartedTestStep::add_measurement_detail`].
+    pub async fn add_measurement_detail(
+        &self,
+        mut detail: measure::Measurement,
+    ) -> Result<(), tv::OcptvError> {
+        detail.set_group(&self.group);
+        self.step.add_measurement_detail(detail).await
+    }
+}
+
 pub struct StepEmitter {
     step_id: String,
     // root emitter
@@ -742,12 +1621,51 @@ pub struct StepEmitter {
 
 impl StepEmitter {
     pub async fn emit(&self, object: &spec::TestStepArtifactImpl) -> Result<(), io::Error> {
+        self.emit_at(object, None).await
+    }
+
+    /// Emits the artifact with the given `timestamp`, see [`emitter::JsonEmitter::emit_at`].
+    pub async fn emit_at(
+        &self,
+        object: &spec::TestStepArtifactImpl,
+        timestamp: Option<chrono::DateTime<chrono_tz::Tz>>,
+    ) -> Result<(), io::Error> {
         let root = spec::RootImpl::TestStepArtifact(spec::TestStepArtifact {
             id: self.step_id.clone(),
             // TODO: can these copies be avoided?
             artifact: object.clone(),
         });
-        self.emitter.emit(&root).await?;
+        self.emitter.emit_at(&root, timestamp).await?;
+
+        Ok(())
+    }
+
+    /// Emits the artifact, then calls `after` under the emit lock, see
+    /// [`emitter::JsonEmitter::emit_then`].
+    pub async fn emit_then<R>(
+        &self,
+        object: &spec::TestStepArtifactImpl,
+        after: impl FnOnce() -> R,
+    ) -> Result<R, io::Error> {
+        let root = spec::RootImpl::TestStepArtifact(spec::TestStepArtifact {
+            id: self.step_id.clone(),
+            artifact: object.clone(),
+        });
+        self.emitter.emit_then(&root, after).await
+    }
+
+    /// Emits all the artifacts in order, without any other artifact in between them.
+    pub async fn emit_all(&self, objects: &[spec::TestStepArtifactImpl]) -> Result<(), io::Error> {
+        let roots = objects
+            .iter()
+            .map(|object| {
+                spec::RootImpl::TestStepArtifact(spec::TestStepArtifact {
+                    id: self.step_id.clone(),
+                    artifact: object.clone(),
+                })
+            })
+            .collect::<Vec<_>>();
+        self.emitter.emit_all(&roots).await?;
 
         Ok(())
     }
@@ -755,4 +1673,8 @@ impl StepEmitter {
     pub fn timestamp_provider(&self) -> &(dyn config::TimestampProvider + Send + Sync + 'static) {
         self.emitter.timestamp_provider()
     }
+
+    pub fn non_finite_policy(&self) -> config::NonFinitePolicy {
+        self.emitter.non_finite_policy()
+    }
 }
@@ -0,0 +1,155 @@
+// (c) Meta Platforms, Inc. and affiliates.
+//
+// Use of this source code is governed by an MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+//! Helpers for testing diagnostics built on this crate.
+//!
+//! Requires the `testutil` feature.
+
+use std::future::Future;
+use std::sync::Arc;
+
+use tokio::sync::Mutex;
+
+use crate::output as tv;
+use crate::spec;
+use tv::{config, dut, run};
+
+/// Name of the test run started by [`run_to_buffer`].
+pub const RUN_NAME: &str = "run_name";
+/// Version of the test run started by [`run_to_buffer`].
+pub const RUN_VERSION: &str = "1.0";
+/// Id of the DUT the test run started by [`run_to_buffer`] is using.
+pub const DUT_ID: &str = "dut_id";
+/// Timestamp of all the artifacts produced by [`run_to_buffer`].
+pub const TIMESTAMP_FORMATTED: &str = "1970-01-01T00:00:00.000Z";
+
+struct FixedTimestampProvider;
+
+impl config::TimestampProvider for FixedTimestampProvider {
+    fn now(&self) -> chrono::DateTime<chrono_tz::Tz> {
+        chrono::DateTime::from_timestamp_nanos(0).with_timezone(&chrono_tz::UTC)
+    }
+}
+
+/// Runs `func` inside a test run scope and returns all the emitted artifacts, parsed.
+///
+/// The run is named [`RUN_NAME`], uses a DUT with id [`DUT_ID`] and all the artifacts
+/// have the timestamp [`TIMESTAMP_FORMATTED`]. If `func` succeeds, the run ends with
+/// a `COMPLETE` status and a `PASS` result.
+///
+/// # Examples
+///
+/// ```rust
+/// # tokio_test::block_on(async {
+/// # use ocptv::output::*;
+/// let artifacts = testutil::run_to_buffer(|run| async move {
+///     run.add_log(LogSeverity::Info, "message").await
+/// })
+/// .await?;
+///
+/// assert_eq!(artifacts.len(), 4);
+/// assert_eq!(artifacts[2]["testRunArtifact"]["log"]["message"], "message");
+///
+/// # Ok::<(), OcptvError>(())
+/// # });
+/// ```
+pub async fn run_to_buffer<F, R>(func: F) -> Result<Vec<tv::Value>, tv::OcptvError>
+where
+    R: Future<Output = Result<(), tv::OcptvError>> + Send + 'static,
+    F: FnOnce(run::ScopedTestRun) -> R,
+{
+    let buffer = Arc::new(Mutex::new(vec![]));
+    let config = config::Config::builder()
+        .with_buffer_output(Arc::clone(&buffer))
+        .with_timestamp_provider(Box::new(FixedTimestampProvider))
+        .build();
+
+    run::TestRun::builder(RUN_NAME, RUN_VERSION)
+        .config(config)
+        .build()
+        .scope(dut::DutInfo::new(DUT_ID), |r| {
+            let fut = func(r);
+            async move {
+                fut.await?;
+                Ok(run::TestRunOutcome {
+                    status: spec::TestStatus::Complete,
+                    result: spec::TestResult::Pass,
+                })
+            }
+        })
+        .await?;
+
+    let artifacts = buffer
+        .lock()
+        .await
+        .iter()
+        .map(|s| serde_json::from_str(s))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(artifacts)
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use assert_json_diff::assert_json_eq;
+    use serde_json::json;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_run_to_buffer_empty_run() -> Result<()> {
+        let artifacts = run_to_buffer(|_| async { Ok(()) }).await?;
+
+        assert_json_eq!(
+            tv::Value::Array(artifacts),
+            json!([
+                {
+                    "schemaVersion": {
+                        "major": spec::SPEC_VERSION.0,
+                        "minor": spec::SPEC_VERSION.1,
+                    },
+                    "sequenceNumber": 0,
+                    "timestamp": TIMESTAMP_FORMATTED,
+                },
+                {
+                    "testRunArtifact": {
+                        "testRunStart": {
+                            "dutInfo": {
+                                "dutInfoId": DUT_ID,
+                            },
+                            "name": RUN_NAME,
+                            "parameters": {},
+                            "version": RUN_VERSION,
+                            "commandLine": "",
+                        }
+                    },
+                    "sequenceNumber": 1,
+                    "timestamp": TIMESTAMP_FORMATTED,
+                },
+                {
+                    "testRunArtifact": {
+                        "testRunEnd": {
+                            "result": "PASS",
+                            "status": "COMPLETE",
+                        }
+                    },
+                    "sequenceNumber": 2,
+                    "timestamp": TIMESTAMP_FORMATTED,
+                },
+            ])
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_run_to_buffer_propagates_error() -> Result<()> {
+        let result = run_to_buffer(|_| async { Err(tv::OcptvError::Other("failed".into())) }).await;
+        assert!(result.is_err());
+
+        Ok(())
+    }
+}
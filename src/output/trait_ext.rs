@@ -4,7 +4,9 @@
 // license that can be found in the LICENSE file or at
 // https://opensource.org/licenses/MIT.
 
-use std::collections::BTreeMap;
+use serde_json::Map;
+
+use crate::output as tv;
 
 pub trait VecExt<T, U> {
     fn map_option<F>(&self, func: F) -> Option<Vec<U>>
@@ -21,12 +23,14 @@ impl<T, U> VecExt<T, U> for Vec<T> {
     }
 }
 
+// note: metadata and parameters are kept in `serde_json::Map`, which with the `preserve_order`
+// feature retains insertion order; this keeps the output stable and diffable against golden files
 pub trait MapExt<K, V> {
-    fn option(&self) -> Option<BTreeMap<K, V>>;
+    fn option(&self) -> Option<Map<K, V>>;
 }
 
-impl<K: Clone, V: Clone> MapExt<K, V> for BTreeMap<K, V> {
-    fn option(&self) -> Option<BTreeMap<K, V>> {
+impl MapExt<String, tv::Value> for Map<String, tv::Value> {
+    fn option(&self) -> Option<Map<String, tv::Value>> {
         (!self.is_empty()).then_some(self.clone())
     }
 }
@@ -4,20 +4,55 @@
 // license that can be found in the LICENSE file or at
 // https://opensource.org/licenses/MIT.
 
+use std::collections::VecDeque;
 use std::convert::Infallible;
 use std::io::{self, Write};
 use std::path::Path;
 use std::sync::Arc;
+use std::time::Duration;
 
 use async_trait::async_trait;
 use tokio::fs;
-use tokio::io::AsyncWriteExt;
-use tokio::sync::Mutex;
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+use tokio::sync::{broadcast, mpsc, Mutex};
+use tokio_stream::wrappers::BroadcastStream;
+use tokio_stream::{Stream, StreamExt};
+use unwrap_infallible::UnwrapInfallible;
 
-/// TODO: docs
+use crate::output as tv;
+use crate::spec;
+
+/// Output sink for serialized artifacts, used with [`crate::output::ConfigBuilder::with_custom_output`].
+///
+/// Each call receives a single serialized artifact, without a line terminator. The emitter
+/// awaits every write before returning to the caller, so an implementation may suspend
+/// to apply backpressure on the diagnostic, eg. see [`BoundedChannelWriter`].
 #[async_trait]
 pub trait Writer {
     async fn write(&self, s: &str) -> Result<(), io::Error>;
+
+    /// Flushes any artifacts buffered by the writer to the underlying sink.
+    /// Called after every artifact when [`crate::output::ConfigBuilder::flush_each`] is
+    /// enabled; does nothing by default.
+    async fn flush(&self) -> Result<(), io::Error> {
+        Ok(())
+    }
+
+    /// Format the artifacts should be serialized with for this writer.
+    fn format(&self) -> OutputFormat {
+        OutputFormat::Compact
+    }
+}
+
+/// Json formatting of the serialized artifacts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub enum OutputFormat {
+    /// Single line per artifact (JSONL).
+    #[default]
+    Compact,
+    /// Indented with 2 spaces, so an artifact spans multiple lines.
+    Pretty,
 }
 
 pub enum WriterType {
@@ -25,13 +60,85 @@ pub enum WriterType {
     Stdout(StdoutWriter),
     File(FileWriter),
     Buffer(BufferWriter),
+    BytesBuffer(BytesBufferWriter),
+    Recording(RecordingWriter),
+    // the output of a `tv::SharedConfig`, see `tv::SharedConfig::config`
+    Shared(Arc<tv::Config>),
 
     Custom(Box<dyn Writer + Send + Sync + 'static>),
 }
 
+impl WriterType {
+    pub(crate) fn format(&self) -> OutputFormat {
+        match self {
+            WriterType::File(file) => file.format,
+            WriterType::Stdout(_)
+            | WriterType::Buffer(_)
+            | WriterType::BytesBuffer(_)
+            | WriterType::Recording(_) => OutputFormat::Compact,
+            WriterType::Shared(config) => config.writer.format(),
+
+            WriterType::Custom(custom) => custom.format(),
+        }
+    }
+
+    /// Writes the artifact, followed by `sep` for the outputs that take a terminator.
+    /// Returns the number of bytes written.
+    pub(crate) async fn write_terminated(&self, s: &str, sep: &str) -> Result<usize, io::Error> {
+        match self {
+            WriterType::File(file) => file.write_terminated(s, sep).await?,
+            WriterType::Stdout(stdout) => stdout.write_terminated(s, sep).await.unwrap_infallible(),
+            WriterType::BytesBuffer(buffer) => {
+                buffer.write_terminated(s, sep).await.unwrap_infallible()
+            }
+            WriterType::Shared(config) => {
+                return Box::pin(config.writer.write_terminated(s, sep)).await;
+            }
+
+            WriterType::Buffer(buffer) => {
+                buffer.write(s).await?;
+                return Ok(s.len());
+            }
+            WriterType::Custom(custom) => {
+                custom.write(s).await?;
+                return Ok(s.len());
+            }
+            // artifacts are recorded before serialization, see `JsonEmitter::emit_artifact`
+            WriterType::Recording(_) => return Ok(0),
+        }
+        Ok(s.len() + sep.len())
+    }
+
+    pub(crate) async fn flush(&self) -> Result<(), io::Error> {
+        match self {
+            WriterType::File(file) => file.flush().await,
+            WriterType::Stdout(stdout) => stdout.flush().await,
+            WriterType::Custom(custom) => custom.flush().await,
+            WriterType::Shared(config) => Box::pin(config.writer.flush()).await,
+            // in-memory, nothing buffered
+            WriterType::Buffer(_) | WriterType::BytesBuffer(_) | WriterType::Recording(_) => Ok(()),
+        }
+    }
+}
+
 /// TODO: docs
 pub struct FileWriter {
     file: Arc<Mutex<fs::File>>,
+    index: Option<Mutex<FileIndex>>,
+    format: OutputFormat,
+}
+
+// sidecar index of a `FileWriter`, see `FileWriter::new_indexed`
+struct FileIndex {
+    file: fs::File,
+    // size of the output file so far, ie. the offset of the next artifact
+    offset: u64,
+}
+
+#[derive(serde::Deserialize)]
+struct IndexedArtifact {
+    #[serde(rename = "sequenceNumber")]
+    seqno: Option<u64>,
 }
 
 impl FileWriter {
@@ -39,39 +146,493 @@ impl FileWriter {
         let file = fs::File::create(path).await?;
         Ok(FileWriter {
             file: Arc::new(Mutex::new(file)),
+            index: None,
+            format: OutputFormat::Compact,
+        })
+    }
+
+    /// Creates the output file at `path`, along with an index file at `path.idx`, so that
+    /// readers of large outputs can seek to a given artifact.
+    ///
+    /// The index has a line per artifact, with its sequence number and the byte offset
+    /// of the artifact in the output file, separated by a space, eg. `12 4096`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # tokio_test::block_on(async {
+    /// # use ocptv::output::*;
+    /// let path = std::env::temp_dir().join("ocptv_indexed_example.jsonl");
+    /// // also creates ocptv_indexed_example.jsonl.idx
+    /// let writer = FileWriter::new_indexed(&path).await?;
+    ///
+    /// # Ok::<(), OcptvError>(())
+    /// # });
+    /// ```
+    pub async fn new_indexed<P: AsRef<Path>>(path: P) -> Result<Self, io::Error> {
+        let mut index_path = path.as_ref().as_os_str().to_owned();
+        index_path.push(".idx");
+
+        let index = fs::File::create(index_path).await?;
+        Ok(FileWriter {
+            index: Some(Mutex::new(FileIndex {
+                file: index,
+                offset: 0,
+            })),
+            ..FileWriter::new(path).await?
         })
     }
 
+    /// Writes the artifacts indented with 2 spaces instead of one per line.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # tokio_test::block_on(async {
+    /// # use ocptv::output::*;
+    /// let writer = FileWriter::new("output.json").await?.with_indent();
+    /// let config = Config::builder()
+    ///     .with_custom_output(Box::new(writer))
+    ///     .build();
+    ///
+    /// # Ok::<(), OcptvError>(())
+    /// # });
+    /// ```
+    pub fn with_indent(mut self) -> Self {
+        self.format = OutputFormat::Pretty;
+        self
+    }
+
     pub async fn write(&self, s: &str) -> Result<(), io::Error> {
+        self.write_terminated(s, "\n").await
+    }
+
+    pub(crate) async fn write_terminated(
+        &self,
+        s: &str,
+        terminator: &str,
+    ) -> Result<(), io::Error> {
         let mut handle = self.file.lock().await;
 
         let mut buf = Vec::<u8>::new();
-        writeln!(buf, "{}", s)?;
+        write!(buf, "{}{}", s, terminator)?;
 
         handle.write_all(&buf).await?;
         handle.flush().await?;
 
+        // note: still under the file lock, so the offsets follow the write order
+        if let Some(index) = &self.index {
+            let mut index = index.lock().await;
+            // artifacts without a sequence number, eg. removed by a transform, aren't indexed
+            if let Ok(IndexedArtifact { seqno: Some(seqno) }) = serde_json::from_str(s) {
+                let entry = format!("{} {}\n", seqno, index.offset);
+                index.file.write_all(entry.as_bytes()).await?;
+                index.file.flush().await?;
+            }
+            index.offset += buf.len() as u64;
+        }
+
         Ok(())
     }
+
+    pub async fn flush(&self) -> Result<(), io::Error> {
+        self.file.lock().await.flush().await
+    }
 }
 
+#[async_trait]
+impl Writer for FileWriter {
+    async fn write(&self, s: &str) -> Result<(), io::Error> {
+        self.write_terminated(s, "\n").await
+    }
+
+    async fn flush(&self) -> Result<(), io::Error> {
+        FileWriter::flush(self).await
+    }
+
+    fn format(&self) -> OutputFormat {
+        self.format
+    }
+}
+
+/// Number of artifacts a [`BufferWriter`] subscriber may fall behind before missing some.
+const SUBSCRIBER_CAPACITY: usize = 1024;
+
 /// TODO: docs
 #[derive(Debug)]
 pub struct BufferWriter {
     buffer: Arc<Mutex<Vec<String>>>,
+    bound: Option<(usize, BufferOverflow)>,
+    subscribers: broadcast::Sender<String>,
+}
+
+/// What a bounded [`BufferWriter`] does with a new artifact once the buffer is full.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum BufferOverflow {
+    /// Fail the write, so the emitting call returns an [`crate::output::OcptvError::IoError`].
+    Error,
+    /// Discard the oldest artifact in the buffer to make room for the new one.
+    DropOldest,
 }
 
 impl BufferWriter {
     pub fn new(buffer: Arc<Mutex<Vec<String>>>) -> Self {
+        Self {
+            buffer,
+            bound: None,
+            subscribers: broadcast::channel(SUBSCRIBER_CAPACITY).0,
+        }
+    }
+
+    /// Creates a writer that keeps at most `max_lines` artifacts in `buffer`, applying
+    /// `overflow` when a new artifact doesn't fit. Guards long runs against unbounded
+    /// memory growth when the buffer isn't drained.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use std::sync::Arc;
+    /// # use tokio::sync::Mutex;
+    /// # use ocptv::output::*;
+    /// let buffer = Arc::new(Mutex::new(vec![]));
+    /// let writer = BufferWriter::bounded(buffer, 1000, BufferOverflow::DropOldest);
+    /// ```
+    pub fn bounded(
+        buffer: Arc<Mutex<Vec<String>>>,
+        max_lines: usize,
+        overflow: BufferOverflow,
+    ) -> Self {
+        Self {
+            buffer,
+            bound: Some((max_lines, overflow)),
+            subscribers: broadcast::channel(SUBSCRIBER_CAPACITY).0,
+        }
+    }
+
+    /// Returns a stream yielding each artifact written from now on, as it's written,
+    /// eg. for live dashboards. The stream ends when the writer is dropped, ie. when
+    /// the test run using it goes away.
+    ///
+    /// Subscribers don't slow down the writer: a subscriber falling more than 1024
+    /// artifacts behind skips the oldest ones it hasn't received yet, and continues
+    /// from there. The buffer itself still gets every artifact.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # tokio_test::block_on(async {
+    /// # use std::sync::Arc;
+    /// # use tokio::sync::Mutex;
+    /// # use tokio_stream::StreamExt;
+    /// # use ocptv::output::*;
+    /// let writer = BufferWriter::new(Arc::new(Mutex::new(vec![])));
+    /// let mut artifacts = writer.subscribe();
+    /// let dashboard = tokio::spawn(async move {
+    ///     while let Some(artifact) = artifacts.next().await {
+    ///         // update the dashboard
+    ///     }
+    /// });
+    ///
+    /// let config = Config::builder().with_buffer_writer(writer).build();
+    /// let dut = DutInfo::new("my_dut");
+    /// let run = TestRun::builder("diagnostic_name", "1.0")
+    ///     .config(config)
+    ///     .build()
+    ///     .start(dut)
+    ///     .await?;
+    /// run.end(TestStatus::Complete, TestResult::Pass).await?;
+    /// dashboard.await.unwrap();
+    ///
+    /// # Ok::<(), OcptvError>(())
+    /// # });
+    /// ```
+    pub fn subscribe(&self) -> impl Stream<Item = String> + Send + Unpin + 'static {
+        BroadcastStream::new(self.subscribers.subscribe()).filter_map(Result::ok)
+    }
+
+    pub async fn write(&self, s: &str) -> Result<(), io::Error> {
+        let mut buffer = self.buffer.lock().await;
+
+        if let Some((max_lines, overflow)) = self.bound {
+            if buffer.len() >= max_lines {
+                match overflow {
+                    BufferOverflow::Error => {
+                        return Err(io::Error::other(format!(
+                            "buffer writer is full ({} lines)",
+                            max_lines
+                        )));
+                    }
+                    BufferOverflow::DropOldest if max_lines == 0 => return Ok(()),
+                    BufferOverflow::DropOldest => {
+                        let excess = buffer.len() + 1 - max_lines;
+                        buffer.drain(..excess);
+                    }
+                }
+            }
+        }
+
+        buffer.push(s.to_string());
+        if self.subscribers.receiver_count() > 0 {
+            // no receivers left is not an error for the buffer itself
+            let _ = self.subscribers.send(s.to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Writer collecting the output as contiguous bytes, eg. for parsers expecting `&[u8]`.
+///
+/// Unlike [`BufferWriter`], each artifact is followed by the line separator, so the
+/// buffer holds the same JSONL stream that would be written to a file.
+///
+/// # Examples
+///
+/// ```rust
+/// # use std::sync::Arc;
+/// # use tokio::sync::Mutex;
+/// # use ocptv::output::*;
+/// let buffer = Arc::new(Mutex::new(Vec::<u8>::new()));
+/// let config = Config::builder()
+///     .with_bytes_buffer_output(Arc::clone(&buffer))
+///     .build();
+/// ```
+#[derive(Debug)]
+pub struct BytesBufferWriter {
+    buffer: Arc<Mutex<Vec<u8>>>,
+}
+
+impl BytesBufferWriter {
+    pub fn new(buffer: Arc<Mutex<Vec<u8>>>) -> Self {
         Self { buffer }
     }
 
     pub async fn write(&self, s: &str) -> Result<(), Infallible> {
-        self.buffer.lock().await.push(s.to_string());
+        self.write_terminated(s, "\n").await
+    }
+
+    pub(crate) async fn write_terminated(
+        &self,
+        s: &str,
+        terminator: &str,
+    ) -> Result<(), Infallible> {
+        let mut buffer = self.buffer.lock().await;
+        buffer.extend_from_slice(s.as_bytes());
+        buffer.extend_from_slice(terminator.as_bytes());
         Ok(())
     }
 }
 
+/// Records the artifacts in memory without serializing them, for asserting on the emitted
+/// artifacts in unit tests without parsing json. The writer is a cheap handle to the shared
+/// record, so a clone can be kept to read it back.
+/// Created by [`tv::ConfigBuilder::record_mode`].
+///
+/// The artifacts are recorded as [`RecordedArtifact`] values, before serialization, so the
+/// options applied to the json output, eg. [`tv::ConfigBuilder::with_transform`], don't
+/// affect them.
+///
+/// # Examples
+///
+/// ```rust
+/// # tokio_test::block_on(async {
+/// # use ocptv::output::*;
+/// let recorder = RecordingWriter::new();
+/// let config = Config::builder().record_mode(recorder.clone()).build();
+///
+/// let dut = DutInfo::new("my_dut");
+/// let run = TestRun::builder("diagnostic_name", "1.0").config(config).build();
+/// let run = run.start(dut).await?;
+/// run.add_log(LogSeverity::Info, "message").await?;
+/// run.end(TestStatus::Complete, TestResult::Pass).await?;
+///
+/// let recorded = recorder.recorded().await;
+/// assert!(matches!(
+///     &recorded[2],
+///     RecordedArtifact::Log { step_id: None, log } if log.to_artifact().message == "message"
+/// ));
+///
+/// # Ok::<(), OcptvError>(())
+/// # });
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct RecordingWriter {
+    artifacts: Arc<Mutex<Vec<RecordedArtifact>>>,
+}
+
+impl RecordingWriter {
+    pub fn new() -> Self {
+        RecordingWriter::default()
+    }
+
+    /// Returns a copy of the artifacts recorded so far, in emission order.
+    pub async fn recorded(&self) -> Vec<RecordedArtifact> {
+        self.artifacts.lock().await.clone()
+    }
+
+    pub(crate) async fn record(&self, root: &spec::RootImpl) {
+        self.artifacts
+            .lock()
+            .await
+            .push(RecordedArtifact::from_spec(root.clone()));
+    }
+}
+
+/// Artifact recorded by a [`RecordingWriter`], holding the same objects that were passed
+/// to the emitting calls, eg. the [`tv::Log`] of a log. The artifacts of a step carry the
+/// id of the step, and the run level logs and errors have no step id.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub enum RecordedArtifact {
+    SchemaVersion,
+    TestRunStart {
+        name: String,
+        version: String,
+    },
+    TestRunEnd {
+        status: tv::TestStatus,
+        result: tv::TestResult,
+    },
+    TestStepStart {
+        step_id: String,
+        name: String,
+    },
+    TestStepEnd {
+        step_id: String,
+        status: tv::TestStatus,
+    },
+    Log {
+        step_id: Option<String>,
+        log: tv::Log,
+    },
+    Error {
+        step_id: Option<String>,
+        error: tv::Error,
+    },
+    Measurement {
+        step_id: String,
+        measurement: tv::Measurement,
+    },
+    MeasurementSeriesStart {
+        step_id: String,
+        series_id: String,
+        name: String,
+    },
+    MeasurementSeriesElement {
+        step_id: String,
+        series_id: String,
+        index: u64,
+        element: tv::MeasurementElementDetail,
+    },
+    MeasurementSeriesEnd {
+        step_id: String,
+        series_id: String,
+        total_count: u64,
+    },
+    Diagnosis {
+        step_id: String,
+        diagnosis: tv::Diagnosis,
+    },
+    File {
+        step_id: String,
+        file: tv::File,
+    },
+    Extension {
+        step_id: String,
+        name: String,
+        content: tv::Value,
+    },
+    /// Pre-serialized artifact, see [`tv::StartedTestRun::emit_raw`].
+    Raw(tv::Value),
+}
+
+impl RecordedArtifact {
+    fn from_spec(root: spec::RootImpl) -> Self {
+        use spec::TestRunArtifactImpl as RunImpl;
+        use spec::TestStepArtifactImpl as StepImpl;
+
+        let (step_id, artifact) = match root {
+            spec::RootImpl::SchemaVersion(_) => return RecordedArtifact::SchemaVersion,
+            spec::RootImpl::Raw(raw) => return RecordedArtifact::Raw(raw.into()),
+            spec::RootImpl::TestRunArtifact(run) => {
+                return match run.artifact {
+                    RunImpl::TestRunStart(start) => RecordedArtifact::TestRunStart {
+                        name: start.name,
+                        version: start.version,
+                    },
+                    RunImpl::TestRunEnd(end) => RecordedArtifact::TestRunEnd {
+                        status: end.status,
+                        result: end.result,
+                    },
+                    RunImpl::Log(log) => RecordedArtifact::Log {
+                        step_id: None,
+                        log: tv::Log::from_spec(log),
+                    },
+                    RunImpl::Error(error) => RecordedArtifact::Error {
+                        step_id: None,
+                        error: tv::Error::from_spec(error),
+                    },
+                }
+            }
+            spec::RootImpl::TestStepArtifact(step) => (step.id, step.artifact),
+        };
+
+        match artifact {
+            StepImpl::TestStepStart(start) => RecordedArtifact::TestStepStart {
+                step_id,
+                name: start.name,
+            },
+            StepImpl::TestStepEnd(end) => RecordedArtifact::TestStepEnd {
+                step_id,
+                status: end.status,
+            },
+            StepImpl::Measurement(measurement) => RecordedArtifact::Measurement {
+                step_id,
+                measurement: tv::Measurement::from_spec(measurement),
+            },
+            StepImpl::MeasurementSeriesStart(start) => RecordedArtifact::MeasurementSeriesStart {
+                step_id,
+                series_id: start.series_id,
+                name: start.name,
+            },
+            StepImpl::MeasurementSeriesElement(element) => {
+                RecordedArtifact::MeasurementSeriesElement {
+                    step_id,
+                    series_id: element.series_id.clone(),
+                    index: element.index,
+                    element: tv::MeasurementElementDetail::from_spec(element),
+                }
+            }
+            StepImpl::MeasurementSeriesEnd(end) => RecordedArtifact::MeasurementSeriesEnd {
+                step_id,
+                series_id: end.series_id,
+                total_count: end.total_count,
+            },
+            StepImpl::Diagnosis(diagnosis) => RecordedArtifact::Diagnosis {
+                step_id,
+                diagnosis: tv::Diagnosis::from_spec(diagnosis),
+            },
+            StepImpl::Log(log) => RecordedArtifact::Log {
+                step_id: Some(step_id),
+                log: tv::Log::from_spec(log),
+            },
+            StepImpl::Error(error) => RecordedArtifact::Error {
+                step_id: Some(step_id),
+                error: tv::Error::from_spec(error),
+            },
+            StepImpl::File(file) => RecordedArtifact::File {
+                step_id,
+                file: tv::File::from_spec(file),
+            },
+            StepImpl::Extension(extension) => RecordedArtifact::Extension {
+                step_id,
+                name: extension.name,
+                content: extension.content,
+            },
+        }
+    }
+}
+
 /// TODO: docs
 #[derive(Debug, Clone)]
 pub struct StdoutWriter {}
@@ -83,13 +644,609 @@ impl StdoutWriter {
     }
 
     pub async fn write(&self, s: &str) -> Result<(), Infallible> {
-        println!("{}", s);
+        self.write_terminated(s, "\n").await
+    }
+
+    pub(crate) async fn write_terminated(
+        &self,
+        s: &str,
+        terminator: &str,
+    ) -> Result<(), Infallible> {
+        print!("{}{}", s, terminator);
+        // stdout is line buffered, so a terminator without a newline wouldn't be flushed
+        let _ = io::stdout().flush();
+        Ok(())
+    }
+
+    pub async fn flush(&self) -> Result<(), io::Error> {
+        io::stdout().flush()
+    }
+}
+
+/// Writer for any [`tokio::io::AsyncWrite`] sink, eg. a custom transport.
+///
+/// Each artifact is written followed by a newline, and the sink is flushed after every
+/// artifact, same as [`FileWriter`].
+///
+/// # Examples
+///
+/// ```rust
+/// # use ocptv::output::*;
+/// let writer = AsyncWriteWriter::new(tokio::io::sink());
+/// let config = Config::builder().with_custom_output(Box::new(writer)).build();
+/// ```
+#[derive(Debug)]
+pub struct AsyncWriteWriter<W> {
+    sink: Mutex<W>,
+}
+
+impl<W: AsyncWrite + Unpin + Send> AsyncWriteWriter<W> {
+    pub fn new(sink: W) -> Self {
+        AsyncWriteWriter {
+            sink: Mutex::new(sink),
+        }
+    }
+
+    /// Consumes the writer, returning the underlying sink.
+    pub fn into_inner(self) -> W {
+        self.sink.into_inner()
+    }
+}
+
+#[async_trait]
+impl<W: AsyncWrite + Unpin + Send> Writer for AsyncWriteWriter<W> {
+    async fn write(&self, s: &str) -> Result<(), io::Error> {
+        let mut handle = self.sink.lock().await;
+
+        let mut buf = Vec::<u8>::new();
+        writeln!(buf, "{}", s)?;
+
+        handle.write_all(&buf).await?;
+        handle.flush().await?;
+
+        Ok(())
+    }
+
+    async fn flush(&self) -> Result<(), io::Error> {
+        self.sink.lock().await.flush().await
+    }
+}
+
+/// Writer that sends each serialized artifact over a bounded channel.
+///
+/// Once the channel holds `capacity` artifacts, emitting waits until the consumer
+/// drains it, so a slow sink slows down the diagnostic instead of buffering without bound.
+///
+/// The consumer must make progress concurrently with the emitting code. If it is only
+/// polled after the test run ends, or lives on the same single-threaded runtime as a task
+/// that blocks on emitting, the run deadlocks as soon as the channel fills up.
+///
+/// # Examples
+///
+/// ```rust
+/// # tokio_test::block_on(async {
+/// # use ocptv::output::*;
+/// let (writer, mut rx) = BoundedChannelWriter::new(16);
+/// let consumer = tokio::spawn(async move {
+///     while let Some(artifact) = rx.recv().await {
+///         // ship the artifact to a slow sink
+///     }
+/// });
+///
+/// let config = Config::builder().with_custom_output(Box::new(writer)).build();
+/// let dut = DutInfo::new("my_dut");
+/// let run = TestRun::builder("diagnostic_name", "1.0")
+///     .config(config)
+///     .build()
+///     .start(dut)
+///     .await?;
+/// run.end(TestStatus::Complete, TestResult::Pass).await?;
+///
+/// # Ok::<(), OcptvError>(())
+/// # });
+/// ```
+#[derive(Debug)]
+pub struct BoundedChannelWriter {
+    sender: mpsc::Sender<String>,
+}
+
+impl BoundedChannelWriter {
+    /// Creates the writer along with the receiving end of its channel.
+    ///
+    /// Panics if `capacity` is 0.
+    pub fn new(capacity: usize) -> (Self, mpsc::Receiver<String>) {
+        let (sender, receiver) = mpsc::channel(capacity);
+        (BoundedChannelWriter { sender }, receiver)
+    }
+}
+
+#[async_trait]
+impl Writer for BoundedChannelWriter {
+    async fn write(&self, s: &str) -> Result<(), io::Error> {
+        self.sender
+            .send(s.to_owned())
+            .await
+            .map_err(|_| io::Error::new(io::ErrorKind::BrokenPipe, "channel receiver was dropped"))
+    }
+}
+
+/// Writer retrying the writes of another writer on IO errors, for sinks with transient
+/// failures, eg. network-backed ones.
+///
+/// A failed write is retried up to `retries` times. The first retry waits for `delay`, and
+/// the wait doubles after each further failure. Once the retries are exhausted, the last
+/// error is returned.
+///
+/// # Examples
+///
+/// ```rust
+/// # use std::time::Duration;
+/// # use ocptv::output::*;
+/// let inner = AsyncWriteWriter::new(tokio::io::sink());
+/// let writer = RetryWriter::new(Box::new(inner), 3, Duration::from_millis(100));
+/// let config = Config::builder().with_custom_output(Box::new(writer)).build();
+/// ```
+pub struct RetryWriter {
+    inner: Box<dyn Writer + Send + Sync + 'static>,
+    retries: usize,
+    delay: Duration,
+}
+
+impl RetryWriter {
+    pub fn new(
+        inner: Box<dyn Writer + Send + Sync + 'static>,
+        retries: usize,
+        delay: Duration,
+    ) -> Self {
+        RetryWriter {
+            inner,
+            retries,
+            delay,
+        }
+    }
+}
+
+#[async_trait]
+impl Writer for RetryWriter {
+    async fn write(&self, s: &str) -> Result<(), io::Error> {
+        let mut delay = self.delay;
+        let mut attempt = 0;
+
+        loop {
+            match self.inner.write(s).await {
+                Err(_) if attempt < self.retries => {
+                    tokio::time::sleep(delay).await;
+                    delay = delay.saturating_mul(2);
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    async fn flush(&self) -> Result<(), io::Error> {
+        self.inner.flush().await
+    }
+
+    fn format(&self) -> OutputFormat {
+        self.inner.format()
+    }
+}
+
+/// Writer keeping only the last `capacity` serialized artifacts in memory, eg. to dump
+/// the recent output from a panic hook. Usually combined with another writer through a
+/// [`TeeWriter`], so the full output is still written elsewhere.
+///
+/// The writer is a cheap handle to the shared ring, so a clone can be kept to read it.
+/// Reading doesn't need an async context, see [`RingBufferWriter::recent`].
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// # tokio_test::block_on(async {
+/// # use ocptv::output::*;
+/// let ring = RingBufferWriter::new(100);
+/// let file = FileWriter::new(std::env::temp_dir().join("ocptv_ring_example.jsonl")).await?;
+/// let writer = TeeWriter::new(vec![Box::new(file), Box::new(ring.clone())]);
+/// let config = Config::builder().with_custom_output(Box::new(writer)).build();
+///
+/// std::panic::set_hook(Box::new(move |_| {
+///     for line in ring.recent() {
+///         eprintln!("{}", line);
+///     }
+/// }));
+/// # let _ = std::panic::take_hook();
+///
+/// # Ok::<(), OcptvError>(())
+/// # });
+/// ```
+#[derive(Debug, Clone)]
+pub struct RingBufferWriter {
+    // note: a sync mutex, so the ring can be read from a panic hook
+    lines: Arc<std::sync::Mutex<VecDeque<String>>>,
+    capacity: usize,
+}
+
+impl RingBufferWriter {
+    pub fn new(capacity: usize) -> Self {
+        RingBufferWriter {
+            lines: Arc::new(std::sync::Mutex::new(VecDeque::with_capacity(capacity))),
+            capacity,
+        }
+    }
+
+    /// Returns the most recent artifacts, oldest first.
+    pub fn recent(&self) -> Vec<String> {
+        // a poisoned lock still holds valid lines, and this is likely called while panicking
+        let lines = self.lines.lock().unwrap_or_else(|e| e.into_inner());
+        lines.iter().cloned().collect()
+    }
+}
+
+#[async_trait]
+impl Writer for RingBufferWriter {
+    async fn write(&self, s: &str) -> Result<(), io::Error> {
+        if self.capacity == 0 {
+            return Ok(());
+        }
+
+        let mut lines = self.lines.lock().unwrap_or_else(|e| e.into_inner());
+        if lines.len() == self.capacity {
+            lines.pop_front();
+        }
+        lines.push_back(s.to_owned());
         Ok(())
     }
 }
 
+/// Writer duplicating the output to several other writers, in order.
+///
+/// Every artifact is written to, and flushes reach, all the writers, even if some fail;
+/// the first error is then returned. The artifacts are serialized in the format of the first writer.
+///
+/// # Examples
+///
+/// ```rust
+/// # use ocptv::output::*;
+/// let ring = RingBufferWriter::new(100);
+/// let writer = TeeWriter::new(vec![
+///     Box::new(AsyncWriteWriter::new(tokio::io::sink())),
+///     Box::new(ring.clone()),
+/// ]);
+/// let config = Config::builder().with_custom_output(Box::new(writer)).build();
+/// ```
+pub struct TeeWriter {
+    writers: Vec<Box<dyn Writer + Send + Sync + 'static>>,
+}
+
+impl TeeWriter {
+    pub fn new(writers: Vec<Box<dyn Writer + Send + Sync + 'static>>) -> Self {
+        TeeWriter { writers }
+    }
+}
+
+#[async_trait]
+impl Writer for TeeWriter {
+    async fn write(&self, s: &str) -> Result<(), io::Error> {
+        let mut result = Ok(());
+        for writer in &self.writers {
+            let r = writer.write(s).await;
+            if result.is_ok() {
+                result = r;
+            }
+        }
+        result
+    }
+
+    async fn flush(&self) -> Result<(), io::Error> {
+        let mut result = Ok(());
+        for writer in &self.writers {
+            let r = writer.flush().await;
+            if result.is_ok() {
+                result = r;
+            }
+        }
+        result
+    }
+
+    fn format(&self) -> OutputFormat {
+        self.writers
+            .first()
+            .map_or(OutputFormat::Compact, |w| w.format())
+    }
+}
+
+/// Field injected by a [`PrefixWriter`] into each artifact.
+const STREAM_ID_FIELD: &str = "streamId";
+
+/// Writer tagging each artifact with a stream id before passing it to another writer, so
+/// consumers can demux several test runs multiplexed over one pipe.
+///
+/// The id is injected as a `streamId` field, first in the artifact object, so that each
+/// artifact stays valid json, eg. `{"streamId":"fan0","sequenceNumber":0,...}`.
+/// Artifacts that aren't json objects, eg. raw ones, are passed through unchanged.
+///
+/// # Examples
+///
+/// ```rust
+/// # tokio_test::block_on(async {
+/// # use ocptv::output::*;
+/// let shared = AsyncWriteWriter::new(tokio::io::sink());
+/// let writer = PrefixWriter::new(Box::new(shared), "fan0");
+/// let config = Config::builder().with_custom_output(Box::new(writer)).build();
+///
+/// # Ok::<(), OcptvError>(())
+/// # });
+/// ```
+pub struct PrefixWriter {
+    inner: Box<dyn Writer + Send + Sync + 'static>,
+    stream_id: String,
+}
+
+impl PrefixWriter {
+    pub fn new(inner: Box<dyn Writer + Send + Sync + 'static>, stream_id: &str) -> Self {
+        PrefixWriter {
+            inner,
+            stream_id: stream_id.to_owned(),
+        }
+    }
+}
+
+#[async_trait]
+impl Writer for PrefixWriter {
+    async fn write(&self, s: &str) -> Result<(), io::Error> {
+        let Ok(tv::Value::Object(fields)) = serde_json::from_str(s) else {
+            return self.inner.write(s).await;
+        };
+
+        let mut artifact = serde_json::Map::new();
+        artifact.insert(STREAM_ID_FIELD.to_owned(), self.stream_id.clone().into());
+        artifact.extend(fields.into_iter().filter(|(k, _)| k != STREAM_ID_FIELD));
+
+        let artifact = tv::Value::Object(artifact);
+        let s = match self.inner.format() {
+            OutputFormat::Pretty => format!("{:#}", artifact),
+            _ => artifact.to_string(),
+        };
+        self.inner.write(&s).await
+    }
+
+    async fn flush(&self) -> Result<(), io::Error> {
+        self.inner.flush().await
+    }
+
+    fn format(&self) -> OutputFormat {
+        self.inner.format()
+    }
+}
+
+/// Writer collecting the artifacts into a single json array document, for consumers
+/// that don't read JSONL. The array is opened by the first artifact, and the following
+/// ones are comma separated; flushing closes it and writes the whole document to the
+/// inner writer at once, eg. `[{"schemaVersion":...},...]`.
+///
+/// The test run flushes its output when it ends, so the document is written by
+/// [`crate::output::StartedTestRun::end`]. Artifacts written after a flush start a new document.
+/// Don't combine with [`crate::output::ConfigBuilder::flush_each`], which would write each
+/// artifact as its own array.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// # tokio_test::block_on(async {
+/// # use ocptv::output::*;
+/// let file = FileWriter::new(std::env::temp_dir().join("ocptv_array_example.json")).await?;
+/// let writer = JsonArrayWriter::new(Box::new(file));
+/// let config = Config::builder().with_custom_output(Box::new(writer)).build();
+///
+/// let dut = DutInfo::new("my_dut");
+/// let run = TestRun::builder("diagnostic_name", "1.0")
+///     .config(config)
+///     .build()
+///     .start(dut)
+///     .await?;
+/// run.end(TestStatus::Complete, TestResult::Pass).await?;
+///
+/// # Ok::<(), OcptvError>(())
+/// # });
+/// ```
+#[derive(Clone)]
+pub struct JsonArrayWriter {
+    inner: Arc<dyn Writer + Send + Sync + 'static>,
+    // the open document, if any artifact was written since the last flush
+    document: Arc<Mutex<Option<String>>>,
+}
+
+impl JsonArrayWriter {
+    pub fn new(inner: Box<dyn Writer + Send + Sync + 'static>) -> Self {
+        JsonArrayWriter {
+            inner: Arc::from(inner),
+            document: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Closes the array and writes the document to the inner writer, then flushes it.
+    pub async fn flush(&self) -> Result<(), io::Error> {
+        // keep the lock while writing, so the documents stay in order
+        let mut document = self.document.lock().await;
+        if let Some(mut array) = document.take() {
+            array.push(']');
+            self.inner.write(&array).await?;
+        }
+        self.inner.flush().await
+    }
+}
+
+#[async_trait]
+impl Writer for JsonArrayWriter {
+    async fn write(&self, s: &str) -> Result<(), io::Error> {
+        let mut document = self.document.lock().await;
+        match document.as_mut() {
+            Some(array) => {
+                array.push(',');
+                array.push_str(s);
+            }
+            None => *document = Some(format!("[{}", s)),
+        }
+        Ok(())
+    }
+
+    async fn flush(&self) -> Result<(), io::Error> {
+        JsonArrayWriter::flush(self).await
+    }
+
+    fn format(&self) -> OutputFormat {
+        self.inner.format()
+    }
+}
+
+/// Severity of the syslog messages sent by a [`SyslogWriter`].
+///
+/// Requires the `syslog` feature.
+#[cfg(feature = "syslog")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum SyslogSeverity {
+    Emergency,
+    Alert,
+    Critical,
+    Error,
+    Warning,
+    Notice,
+    Info,
+    Debug,
+}
+
+#[cfg(feature = "syslog")]
+impl SyslogSeverity {
+    /// Severity for an artifact by default: logs keep their own severity, errors are
+    /// sent as [`SyslogSeverity::Error`] and everything else as [`SyslogSeverity::Info`].
+    pub fn for_artifact(artifact: &tv::Value) -> Self {
+        let body = artifact
+            .get("testRunArtifact")
+            .or_else(|| artifact.get("testStepArtifact"));
+        let Some(body) = body else {
+            return SyslogSeverity::Info;
+        };
+
+        if body.get("error").is_some() {
+            return SyslogSeverity::Error;
+        }
+        match body.pointer("/log/severity").and_then(|s| s.as_str()) {
+            Some("DEBUG") => SyslogSeverity::Debug,
+            Some("WARNING") => SyslogSeverity::Warning,
+            Some("ERROR") => SyslogSeverity::Error,
+            Some("FATAL") => SyslogSeverity::Critical,
+            _ => SyslogSeverity::Info,
+        }
+    }
+}
+
+/// Destination of the messages of a [`SyslogWriter`], implemented for the local syslog
+/// connection made by [`SyslogWriter::local`]. Other implementations can eg. capture the
+/// messages in tests.
+///
+/// Requires the `syslog` feature.
+#[cfg(feature = "syslog")]
+pub trait SyslogSink {
+    fn send(&mut self, severity: SyslogSeverity, message: &str) -> Result<(), io::Error>;
+}
+
+#[cfg(feature = "syslog")]
+impl SyslogSink for syslog::Logger<syslog::LoggerBackend, syslog::Formatter3164> {
+    fn send(&mut self, severity: SyslogSeverity, message: &str) -> Result<(), io::Error> {
+        let result = match severity {
+            SyslogSeverity::Emergency => self.emerg(message),
+            SyslogSeverity::Alert => self.alert(message),
+            SyslogSeverity::Critical => self.crit(message),
+            SyslogSeverity::Error => self.err(message),
+            SyslogSeverity::Warning => self.warning(message),
+            SyslogSeverity::Notice => self.notice(message),
+            SyslogSeverity::Info => self.info(message),
+            SyslogSeverity::Debug => self.debug(message),
+        };
+        result.map_err(|e| io::Error::other(e.to_string()))
+    }
+}
+
+/// Function choosing the syslog severity of an artifact, see [`SyslogWriter::with_severity`].
+#[cfg(feature = "syslog")]
+pub type SyslogSeverityMap = dyn Fn(&tv::Value) -> SyslogSeverity + Send + Sync + 'static;
+
+#[cfg(feature = "syslog")]
+pub use syslog::Facility as SyslogFacility;
+
+/// Writer sending each serialized artifact as a syslog message, eg. on embedded targets
+/// where all the logs go to the local syslog.
+///
+/// The severity of each message is chosen from the artifact, by default with
+/// [`SyslogSeverity::for_artifact`].
+///
+/// Requires the `syslog` feature.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// # use ocptv::output::*;
+/// let writer = SyslogWriter::local(SyslogFacility::LOG_USER, "diagnostic_name")?
+///     .with_severity(Box::new(|_| SyslogSeverity::Notice));
+/// let config = Config::builder().with_syslog_output(writer).build();
+///
+/// # Ok::<(), OcptvError>(())
+/// ```
+#[cfg(feature = "syslog")]
+pub struct SyslogWriter {
+    // note: a sync mutex, sending to syslog is blocking anyway
+    sink: std::sync::Mutex<Box<dyn SyslogSink + Send + 'static>>,
+    severity: Box<SyslogSeverityMap>,
+}
+
+#[cfg(feature = "syslog")]
+impl SyslogWriter {
+    pub fn new(sink: Box<dyn SyslogSink + Send + 'static>) -> Self {
+        SyslogWriter {
+            sink: std::sync::Mutex::new(sink),
+            severity: Box::new(SyslogSeverity::for_artifact),
+        }
+    }
+
+    /// Connects to the local syslog over its unix socket, sending the messages with the
+    /// given `facility` and `process` name.
+    pub fn local(facility: SyslogFacility, process: &str) -> Result<Self, io::Error> {
+        let formatter = syslog::Formatter3164 {
+            facility,
+            hostname: None,
+            process: process.to_owned(),
+            pid: std::process::id(),
+        };
+        let logger = syslog::unix(formatter).map_err(|e| io::Error::other(e.to_string()))?;
+
+        Ok(SyslogWriter::new(Box::new(logger)))
+    }
+
+    /// Replaces the function choosing the severity of each artifact.
+    pub fn with_severity(mut self, severity: Box<SyslogSeverityMap>) -> Self {
+        self.severity = severity;
+        self
+    }
+}
+
+#[cfg(feature = "syslog")]
+#[async_trait]
+impl Writer for SyslogWriter {
+    async fn write(&self, s: &str) -> Result<(), io::Error> {
+        let artifact = serde_json::from_str(s)?;
+        let severity = (self.severity)(&artifact);
+
+        let mut sink = self.sink.lock().unwrap_or_else(|e| e.into_inner());
+        sink.send(severity, s)
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use std::time::Duration;
+
     use super::*;
     use crate::output::*;
     use anyhow::Result;
@@ -124,4 +1281,383 @@ mod tests {
 
         Ok(())
     }
+
+    struct FlakyWriter {
+        failures: Mutex<usize>,
+        written: Arc<Mutex<Vec<String>>>,
+    }
+
+    #[async_trait]
+    impl Writer for FlakyWriter {
+        async fn write(&self, s: &str) -> Result<(), io::Error> {
+            let mut failures = self.failures.lock().await;
+            if *failures > 0 {
+                *failures -= 1;
+                return Err(io::Error::new(io::ErrorKind::TimedOut, "transient"));
+            }
+
+            self.written.lock().await.push(s.to_owned());
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_retry_writer_recovers_from_transient_errors() -> Result<()> {
+        let written = Arc::new(Mutex::new(vec![]));
+        let flaky = FlakyWriter {
+            failures: Mutex::new(2),
+            written: Arc::clone(&written),
+        };
+        let writer = RetryWriter::new(Box::new(flaky), 3, Duration::from_millis(1));
+
+        writer.write("artifact").await?;
+        assert_eq!(*written.lock().await, vec!["artifact"]);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_retry_writer_propagates_after_retries() -> Result<()> {
+        let written = Arc::new(Mutex::new(vec![]));
+        let flaky = FlakyWriter {
+            failures: Mutex::new(3),
+            written: Arc::clone(&written),
+        };
+        let writer = RetryWriter::new(Box::new(flaky), 2, Duration::from_millis(1));
+
+        let actual = writer.write("artifact").await;
+        assert_eq!(actual.map_err(|e| e.kind()), Err(io::ErrorKind::TimedOut));
+        assert!(written.lock().await.is_empty());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_bounded_buffer_writer_errors_on_overflow() -> Result<()> {
+        let buffer = Arc::new(Mutex::new(vec![]));
+        let writer = BufferWriter::bounded(Arc::clone(&buffer), 2, BufferOverflow::Error);
+
+        writer.write("first").await?;
+        writer.write("second").await?;
+        let actual = writer.write("third").await;
+        assert!(actual.is_err());
+        assert_eq!(*buffer.lock().await, vec!["first", "second"]);
+
+        // draining the buffer makes room again
+        buffer.lock().await.clear();
+        writer.write("third").await?;
+        assert_eq!(*buffer.lock().await, vec!["third"]);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_bounded_buffer_writer_drops_oldest() -> Result<()> {
+        let buffer = Arc::new(Mutex::new(vec![]));
+        let writer = BufferWriter::bounded(Arc::clone(&buffer), 2, BufferOverflow::DropOldest);
+
+        for s in ["first", "second", "third", "fourth"] {
+            writer.write(s).await?;
+        }
+        assert_eq!(*buffer.lock().await, vec!["third", "fourth"]);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_bounded_buffer_output_fails_the_run() -> Result<()> {
+        let buffer = Arc::new(Mutex::new(vec![]));
+        let config = Config::builder()
+            .with_bounded_buffer_output(Arc::clone(&buffer), 2, BufferOverflow::Error)
+            .build();
+
+        let dut = DutInfo::builder("dut_id").build();
+        let run = TestRun::builder("run_name", "1.0")
+            .config(config)
+            .build()
+            .start(dut)
+            .await?;
+
+        // schemaVersion and testRunStart filled the buffer
+        let actual = run.add_log(LogSeverity::Info, "message").await;
+        assert!(matches!(actual, Err(OcptvError::IoError(_))));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_buffer_writer_subscriber_receives_artifacts() -> Result<()> {
+        let buffer = Arc::new(Mutex::new(vec![]));
+        let writer = BufferWriter::new(Arc::clone(&buffer));
+        let artifacts = writer.subscribe();
+        let subscriber = tokio::spawn(artifacts.collect::<Vec<_>>());
+
+        let dut = DutInfo::builder("dut_id").build();
+        let run = TestRun::builder("run_name", "1.0")
+            .config(Config::builder().with_buffer_writer(writer).build())
+            .build()
+            .start(dut)
+            .await?;
+        run.end(TestStatus::Complete, TestResult::Pass).await?;
+
+        // the run (and its writer) is gone, so the stream ended
+        let received = subscriber.await?;
+        assert_eq!(received, *buffer.lock().await);
+
+        let artifacts = received
+            .iter()
+            .map(|s| serde_json::from_str::<serde_json::Value>(s))
+            .collect::<Result<Vec<_>, _>>()?;
+        assert!(artifacts[0].get("schemaVersion").is_some());
+        assert!(artifacts[1]
+            .pointer("/testRunArtifact/testRunStart")
+            .is_some());
+        assert!(artifacts[2]
+            .pointer("/testRunArtifact/testRunEnd")
+            .is_some());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_async_write_writer_outputs_jsonl() -> Result<()> {
+        let writer = AsyncWriteWriter::new(io::Cursor::new(Vec::<u8>::new()));
+
+        writer.write(r#"{"a":1}"#).await?;
+        writer.write(r#"{"b":2}"#).await?;
+
+        let bytes = writer.into_inner().into_inner();
+        let lines = String::from_utf8(bytes)?
+            .lines()
+            .map(serde_json::from_str::<serde_json::Value>)
+            .collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(
+            lines,
+            vec![serde_json::json!({"a": 1}), serde_json::json!({"b": 2})]
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_bounded_channel_writer_applies_backpressure() -> Result<()> {
+        let (writer, mut rx) = BoundedChannelWriter::new(1);
+
+        writer.write("first").await?;
+        // channel is full, so the next write must wait until the consumer drains it
+        let blocked = tokio::time::timeout(Duration::from_millis(50), writer.write("second")).await;
+        assert!(blocked.is_err());
+
+        let consumer = tokio::spawn(async move {
+            let mut received = vec![];
+            while let Some(s) = rx.recv().await {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+                received.push(s);
+            }
+            received
+        });
+
+        writer.write("second").await?;
+        writer.write("third").await?;
+        drop(writer);
+
+        assert_eq!(consumer.await?, vec!["first", "second", "third"]);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_bounded_channel_writer_dropped_receiver() -> Result<()> {
+        let (writer, rx) = BoundedChannelWriter::new(1);
+        drop(rx);
+
+        let actual = writer.write("artifact").await;
+        assert_eq!(actual.map_err(|e| e.kind()), Err(io::ErrorKind::BrokenPipe));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_ring_buffer_writer_keeps_recent() -> Result<()> {
+        let ring = RingBufferWriter::new(3);
+        for s in ["first", "second", "third", "fourth", "fifth"] {
+            ring.write(s).await?;
+        }
+        assert_eq!(ring.recent(), vec!["third", "fourth", "fifth"]);
+
+        let empty = RingBufferWriter::new(0);
+        empty.write("first").await?;
+        assert!(empty.recent().is_empty());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_tee_writer_writes_to_all() -> Result<()> {
+        let ring = RingBufferWriter::new(2);
+        let written = Arc::new(Mutex::new(vec![]));
+        let flaky = FlakyWriter {
+            failures: Mutex::new(1),
+            written: Arc::clone(&written),
+        };
+        let writer = TeeWriter::new(vec![Box::new(flaky), Box::new(ring.clone())]);
+
+        // the first writer fails, but the ring still gets the artifact
+        let actual = writer.write("first").await;
+        assert_eq!(actual.map_err(|e| e.kind()), Err(io::ErrorKind::TimedOut));
+        writer.write("second").await?;
+        writer.write("third").await?;
+
+        assert_eq!(*written.lock().await, vec!["second", "third"]);
+        assert_eq!(ring.recent(), vec!["second", "third"]);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_json_array_writer_outputs_single_document() -> Result<()> {
+        let ring = RingBufferWriter::new(10);
+        let writer = JsonArrayWriter::new(Box::new(ring.clone()));
+
+        let dut = DutInfo::builder("dut_id").build();
+        let run = TestRun::builder("run_name", "1.0")
+            .config(
+                Config::builder()
+                    .with_custom_output(Box::new(writer.clone()))
+                    .build(),
+            )
+            .build()
+            .start(dut)
+            .await?;
+        run.add_log(LogSeverity::Info, "message").await?;
+        // nothing reaches the inner writer until the array is closed
+        assert!(ring.recent().is_empty());
+
+        // the run end closes the array
+        run.end(TestStatus::Complete, TestResult::Pass).await?;
+        let documents = ring.recent();
+        assert_eq!(documents.len(), 1);
+        let artifacts: Vec<tv::Value> = serde_json::from_str(&documents[0])?;
+        assert_eq!(artifacts.len(), 4);
+        for (i, artifact) in artifacts.iter().enumerate() {
+            assert_eq!(artifact["sequenceNumber"], i);
+        }
+        assert!(artifacts[0].get("schemaVersion").is_some());
+        assert!(artifacts[3]
+            .pointer("/testRunArtifact/testRunEnd")
+            .is_some());
+
+        // flushing again doesn't write another document
+        writer.flush().await?;
+        assert_eq!(ring.recent().len(), 1);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_prefix_writer_injects_stream_id() -> Result<()> {
+        let ring = RingBufferWriter::new(10);
+
+        let dut = DutInfo::builder("dut_id").build();
+        let run = TestRun::builder("run_name", "1.0")
+            .config(
+                Config::builder()
+                    .with_custom_output(Box::new(PrefixWriter::new(Box::new(ring.clone()), "fan0")))
+                    .build(),
+            )
+            .build()
+            .start(dut)
+            .await?;
+        run.add_log(LogSeverity::Info, "message").await?;
+        run.end(TestStatus::Complete, TestResult::Pass).await?;
+
+        let lines = ring.recent();
+        assert_eq!(lines.len(), 4);
+        for (i, line) in lines.iter().enumerate() {
+            assert!(line.starts_with(r#"{"streamId":"fan0","#));
+            let artifact: tv::Value = serde_json::from_str(line)?;
+            assert_eq!(artifact["sequenceNumber"], i);
+        }
+
+        // not an object, so there's nowhere to put the id
+        let writer = PrefixWriter::new(Box::new(ring.clone()), "fan0");
+        writer.write("[1,2]").await?;
+        assert_eq!(ring.recent().last().unwrap(), "[1,2]");
+
+        Ok(())
+    }
+
+    #[cfg(feature = "syslog")]
+    struct MockSyslog {
+        sent: Arc<std::sync::Mutex<Vec<(SyslogSeverity, String)>>>,
+    }
+
+    #[cfg(feature = "syslog")]
+    impl SyslogSink for MockSyslog {
+        fn send(&mut self, severity: SyslogSeverity, message: &str) -> Result<(), io::Error> {
+            self.sent
+                .lock()
+                .unwrap()
+                .push((severity, message.to_owned()));
+            Ok(())
+        }
+    }
+
+    #[cfg(feature = "syslog")]
+    #[tokio::test]
+    async fn test_syslog_writer_severity() -> Result<()> {
+        let sent = Arc::new(std::sync::Mutex::new(vec![]));
+        let writer = SyslogWriter::new(Box::new(MockSyslog {
+            sent: Arc::clone(&sent),
+        }));
+
+        let dut = DutInfo::builder("dut_id").build();
+        let run = TestRun::builder("run_name", "1.0")
+            .config(Config::builder().with_syslog_output(writer).build())
+            .build()
+            .start(dut)
+            .await?;
+        run.add_log(LogSeverity::Warning, "fan is slow").await?;
+        let step = run.add_step("step_name").start().await?;
+        step.add_error("fan-stuck").await?;
+        step.end(TestStatus::Complete).await?;
+        run.end(TestStatus::Complete, TestResult::Fail).await?;
+
+        let sent = sent.lock().unwrap();
+        let severities = sent.iter().map(|(s, _)| *s).collect::<Vec<_>>();
+        assert_eq!(
+            severities,
+            [
+                SyslogSeverity::Info,
+                SyslogSeverity::Info,
+                SyslogSeverity::Warning,
+                SyslogSeverity::Info,
+                SyslogSeverity::Error,
+                SyslogSeverity::Info,
+                SyslogSeverity::Info,
+            ]
+        );
+        // the message is the serialized artifact
+        let log: tv::Value = serde_json::from_str(&sent[2].1)?;
+        assert_eq!(log["testRunArtifact"]["log"]["message"], "fan is slow");
+
+        Ok(())
+    }
+
+    #[cfg(feature = "syslog")]
+    #[tokio::test]
+    async fn test_syslog_writer_custom_severity() -> Result<()> {
+        let sent = Arc::new(std::sync::Mutex::new(vec![]));
+        let writer = SyslogWriter::new(Box::new(MockSyslog {
+            sent: Arc::clone(&sent),
+        }))
+        .with_severity(Box::new(|_| SyslogSeverity::Notice));
+
+        writer
+            .write(r#"{"testRunArtifact":{"error":{"symptom":"s"}}}"#)
+            .await?;
+        assert_eq!(sent.lock().unwrap()[0].0, SyslogSeverity::Notice);
+
+        Ok(())
+    }
 }
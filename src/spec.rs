@@ -4,11 +4,10 @@
 // license that can be found in the LICENSE file or at
 // <https://opensource.org/licenses/MIT.>
 
-use std::collections::BTreeMap;
-
 use chrono::DateTime;
 use serde::Deserialize;
 use serde::Serialize;
+use serde_json::Map;
 use serde_with::serde_as;
 
 use crate::output as tv;
@@ -85,7 +84,15 @@ pub enum ValidatorType {
     NotInSet,
 }
 
-/// TODO: docs
+/// Type of a DUT subcomponent.
+/// Covers all the values allowed by the spec; other kinds of parts (eg. cables) should
+/// use the closest match or `Unspecified`, along with a descriptive subcomponent name.
+///
+/// ref: <https://github.com/opencomputeproject/ocp-diag-core/tree/main/json_spec#subcomponenttype>
+///
+/// schema url: <https://github.com/opencomputeproject/ocp-diag-core/blob/main/json_spec/output/subcomponent.json>
+///
+/// schema ref: <https://github.com/opencomputeproject/ocp-diag-core/subcomponent/$defs/type>
 #[derive(Debug, Serialize, Clone, PartialEq)]
 #[non_exhaustive]
 pub enum SubcomponentType {
@@ -167,7 +174,8 @@ pub enum TestResult {
 /// schema url: <https://github.com/opencomputeproject/ocp-diag-core/blob/main/json_spec/output/log.json>
 ///
 /// schema ref: <https://github.com/opencomputeproject/ocp-diag-core/log/$defs/severity>
-#[derive(Debug, Serialize, Clone, PartialEq)]
+// note: variants are declared by increasing severity, for the ordering
+#[derive(Debug, Serialize, Clone, PartialEq, Eq, PartialOrd, Ord)]
 #[non_exhaustive]
 pub enum LogSeverity {
     #[serde(rename = "DEBUG")]
@@ -189,7 +197,7 @@ pub enum LogSeverity {
 /// schema url: <https://github.com/opencomputeproject/ocp-diag-core/blob/main/json_spec/output/dut_info.json>
 ///
 /// schema ref: <https://github.com/opencomputeproject/ocp-diag-core/dutInfo/$defs/softwareInfo/properties/softwareType>
-#[derive(Debug, Serialize, Clone, PartialEq)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 #[serde(rename = "softwareType")]
 #[non_exhaustive]
 pub enum SoftwareType {
@@ -208,7 +216,6 @@ pub struct Root {
     #[serde(flatten)]
     pub artifact: RootImpl,
 
-    // TODO : manage different timezones
     #[serde(rename = "timestamp")]
     #[serde(with = "rfc3339_format")]
     pub timestamp: DateTime<chrono_tz::Tz>,
@@ -228,6 +235,10 @@ pub enum RootImpl {
 
     #[serde(rename = "testStepArtifact")]
     TestStepArtifact(TestStepArtifact),
+
+    // pre-serialized artifact body, eg. produced by another ocptv library
+    #[serde(untagged)]
+    Raw(serde_json::Map<String, tv::Value>),
 }
 
 /// Low-level model for the `schemaVersion` spec object.
@@ -271,6 +282,7 @@ pub struct TestRunArtifact {
     pub artifact: TestRunArtifactImpl,
 }
 
+#[allow(clippy::large_enum_variant)]
 #[derive(Debug, Serialize, PartialEq, Clone)]
 #[non_exhaustive]
 pub enum TestRunArtifactImpl {
@@ -308,14 +320,14 @@ pub struct TestRunStart {
     pub command_line: String,
 
     #[serde(rename = "parameters")]
-    pub parameters: BTreeMap<String, tv::Value>,
+    pub parameters: Map<String, tv::Value>,
 
     #[serde(rename = "dutInfo")]
     pub dut_info: DutInfo,
 
     #[serde(skip_serializing_if = "Option::is_none")]
     #[serde(rename = "metadata")]
-    pub metadata: Option<BTreeMap<String, tv::Value>>,
+    pub metadata: Option<Map<String, tv::Value>>,
 }
 
 /// Low-level model for the `dutInfo` spec object.
@@ -326,7 +338,7 @@ pub struct TestRunStart {
 /// schema url: <https://github.com/opencomputeproject/ocp-diag-core/blob/main/json_spec/output/dut_info.json>
 ///
 /// schema ref: <https://github.com/opencomputeproject/ocp-diag-core/dutInfo>
-#[derive(Debug, Serialize, Default, Clone, PartialEq)]
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
 #[serde(rename = "dutInfo")]
 pub struct DutInfo {
     #[serde(rename = "dutInfoId")]
@@ -350,7 +362,7 @@ pub struct DutInfo {
 
     #[serde(skip_serializing_if = "Option::is_none")]
     #[serde(rename = "metadata")]
-    pub metadata: Option<BTreeMap<String, tv::Value>>,
+    pub metadata: Option<Map<String, tv::Value>>,
 }
 
 /// Low-level model for the `platformInfo` spec object.
@@ -361,7 +373,7 @@ pub struct DutInfo {
 /// schema url: <https://github.com/opencomputeproject/ocp-diag-core/blob/main/json_spec/output/dut_info.json>
 ///
 /// schema ref: <https://github.com/opencomputeproject/ocp-diag-core/dutInfo/$defs/platformInfo>
-#[derive(Debug, Serialize, Default, Clone, PartialEq)]
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
 #[serde(rename = "platformInfo")]
 pub struct PlatformInfo {
     #[serde(rename = "info")]
@@ -376,7 +388,7 @@ pub struct PlatformInfo {
 /// schema url: <https://github.com/opencomputeproject/ocp-diag-core/blob/main/json_spec/output/dut_info.json>
 ///
 /// schema ref: <https://github.com/opencomputeproject/ocp-diag-core/dutInfo/$defs/softwareInfo>
-#[derive(Debug, Serialize, Clone, PartialEq)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 #[serde(rename = "softwareInfo")]
 pub struct SoftwareInfo {
     #[serde(rename = "softwareInfoId")]
@@ -416,7 +428,7 @@ impl serialize_ids::IdGetter for SoftwareInfo {
 /// schema url: <https://github.com/opencomputeproject/ocp-diag-core/blob/main/json_spec/output/dut_info.json>
 ///
 /// schema ref: <https://github.com/opencomputeproject/ocp-diag-core/dutInfo/$defs/hardwareInfo>
-#[derive(Debug, Serialize, Default, Clone, PartialEq)]
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
 #[serde(rename = "hardwareInfo")]
 pub struct HardwareInfo {
     #[serde(rename = "hardwareInfoId")]
@@ -682,7 +694,7 @@ pub struct Measurement {
 
     #[serde(skip_serializing_if = "Option::is_none")]
     #[serde(rename = "metadata")]
-    pub metadata: Option<BTreeMap<String, tv::Value>>,
+    pub metadata: Option<Map<String, tv::Value>>,
 }
 
 /// Low-level model for the `validator` spec object.
@@ -708,7 +720,7 @@ pub struct Validator {
 
     #[serde(skip_serializing_if = "Option::is_none")]
     #[serde(rename = "metadata")]
-    pub metadata: Option<BTreeMap<String, tv::Value>>,
+    pub metadata: Option<Map<String, tv::Value>>,
 }
 
 /// Low-level model for the `subcomponent` spec object.
@@ -779,7 +791,7 @@ pub struct MeasurementSeriesStart {
 
     #[serde(skip_serializing_if = "Option::is_none")]
     #[serde(rename = "metadata")]
-    pub metadata: Option<BTreeMap<String, tv::Value>>,
+    pub metadata: Option<Map<String, tv::Value>>,
 }
 
 /// Low-level model for the `measurementSeriesEnd` spec object.
@@ -825,7 +837,7 @@ pub struct MeasurementSeriesElement {
 
     #[serde(skip_serializing_if = "Option::is_none")]
     #[serde(rename = "metadata")]
-    pub metadata: Option<BTreeMap<String, tv::Value>>,
+    pub metadata: Option<Map<String, tv::Value>>,
 }
 
 /// Low-level model for the `diagnosis` spec object.
@@ -894,7 +906,7 @@ pub struct File {
 
     #[serde(skip_serializing_if = "Option::is_none")]
     #[serde(rename = "metadata")]
-    pub metadata: Option<BTreeMap<String, tv::Value>>,
+    pub metadata: Option<Map<String, tv::Value>>,
 }
 
 /// Low-level model for the `extension` spec object.
@@ -926,6 +938,24 @@ mod tests {
 
     use super::*;
 
+    #[test]
+    fn test_subcomponent_type_serialize() -> Result<()> {
+        let cases = [
+            (SubcomponentType::Unspecified, "UNSPECIFIED"),
+            (SubcomponentType::Asic, "ASIC"),
+            (SubcomponentType::AsicSubsystem, "ASIC-SUBSYSTEM"),
+            (SubcomponentType::Bus, "BUS"),
+            (SubcomponentType::Function, "FUNCTION"),
+            (SubcomponentType::Connector, "CONNECTOR"),
+        ];
+
+        for (value, expected) in cases {
+            assert_eq!(serde_json::to_value(value)?, json!(expected));
+        }
+
+        Ok(())
+    }
+
     #[test]
     fn test_rfc3339_format_serialize() -> Result<()> {
         let test_date = "2022-01-01T00:00:00.000Z";
@@ -4,7 +4,6 @@
 // license that can be found in the LICENSE file or at
 // https://opensource.org/licenses/MIT.
 
-#[cfg(coverage)]
 use anyhow::Result;
 
 // reasoning: the coverage(off) attribute is experimental in llvm-cov, so because we cannot
@@ -86,3 +85,774 @@ async fn test_config_builder_with_file() -> Result<()> {
 
     Ok(())
 }
+
+#[cfg(coverage)]
+#[tokio::test]
+async fn test_config_builder_with_indented_file() -> Result<()> {
+    use std::fs;
+
+    use assert_fs::prelude::*;
+
+    use ocptv::output::{Config, FileWriter, TestResult, TestRun, TestStatus};
+
+    use super::fixture::*;
+
+    let fs = assert_fs::TempDir::new()?;
+    let output_file = fs.child("output.json");
+
+    let run = TestRun::builder("run_name", "1.0")
+        .config(
+            Config::builder()
+                .with_timestamp_provider(Box::new(FixedTsProvider {}))
+                .with_custom_output(Box::new(
+                    FileWriter::new(output_file.path()).await?.with_indent(),
+                ))
+                .build(),
+        )
+        .build()
+        .start(fixture_dut())
+        .await?;
+    run.end(TestStatus::Complete, TestResult::Pass).await?;
+
+    let compact = collect_output(
+        |config| config,
+        |run_builder, dut| async move {
+            let run = run_builder.build().start(dut).await?;
+            run.end(TestStatus::Complete, TestResult::Pass).await?;
+            Ok(())
+        },
+    )
+    .await?;
+
+    let indented = fs::read_to_string(output_file.path())?;
+    assert!(indented.contains("\n  \"testRunArtifact\": {"));
+
+    let indented_values = serde_json::Deserializer::from_str(&indented)
+        .into_iter::<serde_json::Value>()
+        .collect::<Result<Vec<_>, _>>()?;
+    assert_eq!(compact, indented_values);
+
+    Ok(())
+}
+
+#[cfg(coverage)]
+#[tokio::test]
+async fn test_config_builder_with_crlf_line_separator() -> Result<()> {
+    use std::fs;
+
+    use assert_fs::prelude::*;
+
+    use ocptv::output::{Config, DutInfo, TestResult, TestRun, TestStatus};
+
+    use super::fixture::*;
+
+    let fs = assert_fs::TempDir::new()?;
+    let output_file = fs.child("output.jsonl");
+
+    let dut = DutInfo::builder("dut_id").build();
+    let run = TestRun::builder("run_name", "1.0")
+        .config(
+            Config::builder()
+                .with_timestamp_provider(Box::new(FixedTsProvider {}))
+                .with_file_output(output_file.path())
+                .await?
+                .line_separator("\r\n")?
+                .build(),
+        )
+        .build()
+        .start(dut)
+        .await?;
+    run.end(TestStatus::Complete, TestResult::Pass).await?;
+
+    let content = fs::read_to_string(output_file.path())?;
+    let records = content.split_terminator("\r\n").collect::<Vec<_>>();
+    assert!(content.ends_with("\r\n"));
+    assert_eq!(records.len(), 3);
+    for record in records {
+        assert!(!record.contains('\n'));
+        serde_json::from_str::<serde_json::Value>(record)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(coverage)]
+#[tokio::test]
+async fn test_config_builder_with_indexed_file() -> Result<()> {
+    use std::fs;
+
+    use assert_fs::prelude::*;
+
+    use ocptv::output::{Config, DutInfo, LogSeverity, TestResult, TestRun, TestStatus};
+
+    let fs = assert_fs::TempDir::new()?;
+    let output_file = fs.child("output.jsonl");
+
+    let dut = DutInfo::builder("dut_id").build();
+    let run = TestRun::builder("run_name", "1.0")
+        .config(
+            Config::builder()
+                .with_indexed_file_output(output_file.path())
+                .await?
+                .build(),
+        )
+        .build()
+        .start(dut)
+        .await?;
+    for i in 0..5 {
+        run.add_log(LogSeverity::Info, &format!("message {}", i))
+            .await?;
+    }
+    run.end(TestStatus::Complete, TestResult::Pass).await?;
+
+    let output = fs::read_to_string(output_file.path())?;
+    let index = fs::read_to_string(fs.child("output.jsonl.idx").path())?;
+
+    let entries = index.lines().collect::<Vec<_>>();
+    assert_eq!(entries.len(), 8);
+    for (i, entry) in entries.iter().enumerate() {
+        let (seqno, offset) = entry.split_once(' ').unwrap();
+        assert_eq!(seqno.parse::<usize>()?, i);
+
+        let line = output[offset.parse::<usize>()?..].lines().next().unwrap();
+        let artifact: serde_json::Value = serde_json::from_str(line)?;
+        assert_eq!(artifact["sequenceNumber"], i);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_config_builder_rejects_empty_line_separator() -> Result<()> {
+    use ocptv::output::{Config, OcptvError};
+
+    let actual = Config::builder().line_separator("");
+    assert!(matches!(actual, Err(OcptvError::Other(_))));
+
+    Config::builder().line_separator("\r\n")?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_config_builder_with_bytes_buffer() -> Result<()> {
+    use std::sync::Arc;
+
+    use serde_json::json;
+    use tokio::sync::Mutex;
+
+    use ocptv::output::{Config, DutInfo, TestResult, TestRun, TestStatus};
+
+    use super::fixture::*;
+
+    let expected = [
+        json_schema_version(),
+        json!({
+            "testRunArtifact": {
+                "testRunStart": {
+                    "dutInfo": {
+                        "dutInfoId": "dut_id"
+                    },
+                    "name": "run_name",
+                    "parameters": {},
+                    "version": "1.0",
+                    "commandLine": ""
+                }
+            },
+            "sequenceNumber": 1,
+            "timestamp": DATETIME_FORMATTED
+        }),
+        json!({
+            "testRunArtifact": {
+                "error": {
+                    "symptom": "symptom"
+                }
+            },
+            "sequenceNumber": 2,
+            "timestamp": DATETIME_FORMATTED
+        }),
+        json_run_pass(3),
+    ];
+
+    let buffer = Arc::new(Mutex::new(Vec::<u8>::new()));
+    let dut = DutInfo::builder("dut_id").build();
+    let run = TestRun::builder("run_name", "1.0")
+        .config(
+            Config::builder()
+                .with_timestamp_provider(Box::new(FixedTsProvider {}))
+                .with_bytes_buffer_output(Arc::clone(&buffer))
+                .build(),
+        )
+        .build()
+        .start(dut)
+        .await?;
+    run.add_error("symptom").await?;
+    run.end(TestStatus::Complete, TestResult::Pass).await?;
+
+    let bytes = buffer.lock().await.clone();
+    assert!(bytes.ends_with(b"\n"));
+
+    let actual = bytes
+        .split(|b| *b == b'\n')
+        .filter(|line| !line.is_empty())
+        .map(serde_json::from_slice::<serde_json::Value>)
+        .collect::<Result<Vec<_>, _>>()?;
+    assert_eq!(actual, expected);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_config_builder_without_timestamps() -> Result<()> {
+    use ocptv::output::{TestResult, TestStatus};
+
+    use super::fixture::*;
+
+    let artifacts = collect_output(
+        |config| config.emit_timestamps(false),
+        |run_builder, dut| async move {
+            let run = run_builder.build().start(dut).await?;
+            let step = run.add_step("step_name").start().await?;
+            let series = step.add_measurement_series("name").start().await?;
+            series.add_measurement(60).await?;
+            series.end().await?;
+            step.end(TestStatus::Complete).await?;
+            run.end(TestStatus::Complete, TestResult::Pass).await?;
+            Ok(())
+        },
+    )
+    .await?;
+
+    // schemaVersion, run start/end, step start/end, series start/element/end
+    assert_eq!(artifacts.len(), 8);
+    for artifact in artifacts {
+        let entry = artifact.to_string();
+        assert!(
+            !entry.contains("\"timestamp\""),
+            "found timestamp in {}",
+            entry
+        );
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_config_builder_with_lazy_start() -> Result<()> {
+    use serde_json::json;
+
+    use ocptv::output::{LogSeverity, TestResult, TestStatus};
+
+    use super::fixture::*;
+
+    async fn run(lazy: bool, log: bool) -> Result<Vec<serde_json::Value>> {
+        collect_output(
+            |config| config.lazy_start(lazy),
+            |run_builder, dut| async move {
+                let run = run_builder.build().start(dut).await?;
+                if log {
+                    run.add_log(LogSeverity::Info, "message").await?;
+                }
+                run.end(TestStatus::Complete, TestResult::Pass).await?;
+                Ok(())
+            },
+        )
+        .await
+    }
+
+    let log = json!({
+        "testRunArtifact": {
+            "log": {
+                "message": "message",
+                "severity": "INFO"
+            }
+        },
+        "sequenceNumber": 2,
+        "timestamp": DATETIME_FORMATTED
+    });
+
+    // lazy run without artifacts emits nothing, not even the end
+    assert!(run(true, false).await?.is_empty());
+
+    let expected = vec![
+        json_schema_version(),
+        json_run_default_start(),
+        log,
+        json_run_pass(3),
+    ];
+    assert_eq!(run(true, true).await?, expected);
+    assert_eq!(run(false, true).await?, expected);
+
+    let expected = vec![
+        json_schema_version(),
+        json_run_default_start(),
+        json_run_pass(2),
+    ];
+    assert_eq!(run(false, false).await?, expected);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_config_builder_with_sanitized_control_chars() -> Result<()> {
+    use ocptv::output::{LogSeverity, TestResult, TestStatus};
+
+    use super::fixture::*;
+
+    let artifacts = collect_output(
+        |config| config.sanitize_control_chars(true),
+        |run_builder, dut| async move {
+            let run = run_builder.build().start(dut).await?;
+            run.add_log(LogSeverity::Info, "fan\u{0007} failed\tto spin\n")
+                .await?;
+            run.end(TestStatus::Complete, TestResult::Pass).await?;
+            Ok(())
+        },
+    )
+    .await?;
+
+    assert_eq!(
+        artifacts[2]["testRunArtifact"]["log"]["message"],
+        "fan failed\tto spin\n"
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_config_builder_with_timestamp_precision() -> Result<()> {
+    use ocptv::output::{TestResult, TestStatus, TimestampPrecision};
+
+    use super::fixture::*;
+
+    async fn timestamps(precision: TimestampPrecision) -> Result<Vec<String>> {
+        let artifacts = collect_output(
+            |config| config.timestamp_precision(precision),
+            |run_builder, dut| async move {
+                let run = run_builder.build().start(dut).await?;
+                let step = run.add_step("step_name").start().await?;
+                let series = step.add_measurement_series("name").start().await?;
+                series.add_measurement(60).await?;
+                series.end().await?;
+                step.end(TestStatus::Complete).await?;
+                run.end(TestStatus::Complete, TestResult::Pass).await?;
+                Ok(())
+            },
+        )
+        .await?;
+
+        let mut timestamps = vec![];
+        for value in artifacts {
+            timestamps.push(value["timestamp"].as_str().unwrap().to_owned());
+            if let Some(ts) = value.pointer("/testStepArtifact/measurementSeriesElement/timestamp")
+            {
+                timestamps.push(ts.as_str().unwrap().to_owned());
+            }
+        }
+        Ok(timestamps)
+    }
+
+    for (precision, expected) in [
+        (TimestampPrecision::Millis, "1970-01-01T00:00:00.000Z"),
+        (TimestampPrecision::Micros, "1970-01-01T00:00:00.000000Z"),
+        (TimestampPrecision::Nanos, "1970-01-01T00:00:00.000000000Z"),
+    ] {
+        let actual = timestamps(precision).await?;
+        // 8 artifacts, plus the series element timestamp
+        assert_eq!(actual.len(), 9);
+        for ts in actual {
+            assert_eq!(ts.len(), expected.len());
+            assert_eq!(ts, expected);
+        }
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_config_builder_with_record_mode() -> Result<()> {
+    use ocptv::output::{
+        Config, DutInfo, LogSeverity, RecordedArtifact, RecordingWriter, TestResult, TestRun,
+        TestStatus,
+    };
+
+    let recorder = RecordingWriter::new();
+    let dut = DutInfo::builder("dut_id").build();
+    let run = TestRun::builder("run_name", "1.0")
+        .config(Config::builder().record_mode(recorder.clone()).build())
+        .build()
+        .start(dut)
+        .await?;
+
+    let step = run.add_step("step_name").start().await?;
+    step.add_log(LogSeverity::Info, "message").await?;
+    step.end(TestStatus::Complete).await?;
+    run.end(TestStatus::Complete, TestResult::Pass).await?;
+
+    let recorded = recorder.recorded().await;
+    assert_eq!(recorded.len(), 6);
+    assert!(matches!(recorded[0], RecordedArtifact::SchemaVersion));
+    assert!(matches!(
+        &recorded[1],
+        RecordedArtifact::TestRunStart { name, version } if name == "run_name" && version == "1.0"
+    ));
+    assert!(matches!(
+        &recorded[2],
+        RecordedArtifact::TestStepStart { step_id, name } if step_id == "step0" && name == "step_name"
+    ));
+    assert!(matches!(
+        &recorded[3],
+        RecordedArtifact::Log { step_id: Some(step_id), log }
+            if step_id == "step0" && log.to_artifact().message == "message"
+    ));
+    assert!(matches!(
+        &recorded[4],
+        RecordedArtifact::TestStepEnd { step_id, status: TestStatus::Complete } if step_id == "step0"
+    ));
+    assert!(matches!(
+        recorded[5],
+        RecordedArtifact::TestRunEnd {
+            status: TestStatus::Complete,
+            result: TestResult::Pass,
+        }
+    ));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_config_builder_with_non_utc_timezone() -> Result<()> {
+    use ocptv::output::{MeasurementElementDetail, TestResult, TestStatus};
+
+    use super::fixture::*;
+
+    let artifacts = collect_output(
+        // fixed offset, no daylight saving time
+        |config| config.timezone(chrono_tz::Asia::Kolkata),
+        |run_builder, dut| async move {
+            let run = run_builder.build().start(dut).await?;
+            let step = run.add_step("step_name").start().await?;
+            let series = step.add_measurement_series("name").start().await?;
+            series.add_measurement(60).await?;
+            series
+                .add_measurement_detail(
+                    MeasurementElementDetail::builder(70)
+                        .timestamp(DATETIME.with_timezone(&chrono_tz::UTC))
+                        .build(),
+                )
+                .await?;
+            series.end().await?;
+            step.end(TestStatus::Complete).await?;
+            run.end(TestStatus::Complete, TestResult::Pass).await?;
+            Ok(())
+        },
+    )
+    .await?;
+
+    assert_eq!(artifacts.len(), 9);
+    for artifact in &artifacts {
+        assert!(artifact["timestamp"].as_str().unwrap().ends_with("+05:30"));
+    }
+
+    let element_ts = |i: usize| {
+        artifacts[i]
+            .pointer("/testStepArtifact/measurementSeriesElement/timestamp")
+            .and_then(|ts| ts.as_str())
+            .unwrap()
+            .to_owned()
+    };
+    assert!(element_ts(4).ends_with("+05:30"));
+    // explicit UTC timestamp gets converted to the configured timezone
+    assert_eq!(element_ts(5), "1970-01-01T05:30:00.000+05:30");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_config_builder_with_non_finite_policy() -> Result<()> {
+    use serde_json::json;
+
+    use ocptv::output::{
+        Measurement, MeasurementElementDetail, NonFinitePolicy, TestResult, TestStatus, Value,
+    };
+
+    use super::fixture::*;
+
+    // measurement values, then series element values
+    async fn values(policy: NonFinitePolicy) -> Result<(Vec<Value>, Vec<Value>)> {
+        let artifacts = collect_output(
+            |config| config.non_finite_policy(policy),
+            |run_builder, dut| async move {
+                let run = run_builder.build().start(dut).await?;
+                let step = run.add_step("step_name").start().await?;
+
+                step.add_measurement("name", f64::NAN).await?;
+                step.add_measurement_detail(Measurement::builder("name", f32::INFINITY).build())
+                    .await?;
+                step.add_measurement_detail(Measurement::float("name", f64::NEG_INFINITY))
+                    .await?;
+                step.add_measurement("name", 1.5).await?;
+
+                let series = step.add_measurement_series("series").start().await?;
+                series.add_measurement(f64::NAN).await?;
+                series
+                    .add_measurement_detail(
+                        MeasurementElementDetail::builder(f64::INFINITY).build(),
+                    )
+                    .await?;
+                series
+                    .add_measurement_at_index(2, f64::NEG_INFINITY)
+                    .await?;
+                series.add_measurement(1.5).await?;
+                series.end().await?;
+
+                step.end(TestStatus::Complete).await?;
+                run.end(TestStatus::Complete, TestResult::Pass).await?;
+                Ok(())
+            },
+        )
+        .await?;
+
+        let values = |pointer: &str| {
+            artifacts
+                .iter()
+                .filter_map(|a| a.pointer(pointer))
+                .cloned()
+                .collect::<Vec<_>>()
+        };
+        Ok((
+            values("/testStepArtifact/measurement/value"),
+            values("/testStepArtifact/measurementSeriesElement/value"),
+        ))
+    }
+
+    let nulls = vec![json!(null), json!(null), json!(null), json!(1.5)];
+    assert_eq!(values(NonFinitePolicy::Null).await?, (nulls.clone(), nulls));
+
+    let sentinels = vec![
+        json!("NaN"),
+        json!("Infinity"),
+        json!("-Infinity"),
+        json!(1.5),
+    ];
+    assert_eq!(
+        values(NonFinitePolicy::Sentinel).await?,
+        (sentinels.clone(), sentinels)
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_config_builder_with_flush_each() -> Result<()> {
+    use std::io;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    use async_trait::async_trait;
+
+    use ocptv::output::{Config, DutInfo, TestResult, TestRun, TestStatus, Writer};
+
+    #[derive(Default)]
+    struct FlushCounter {
+        writes: AtomicUsize,
+        flushes: AtomicUsize,
+    }
+
+    struct CountingWriter(Arc<FlushCounter>);
+
+    #[async_trait]
+    impl Writer for CountingWriter {
+        async fn write(&self, _: &str) -> Result<(), io::Error> {
+            self.0.writes.fetch_add(1, Ordering::SeqCst);
+            Ok(())
+        }
+
+        async fn flush(&self) -> Result<(), io::Error> {
+            self.0.flushes.fetch_add(1, Ordering::SeqCst);
+            Ok(())
+        }
+    }
+
+    async fn counts(flush_each: bool) -> Result<(usize, usize)> {
+        let counter = Arc::new(FlushCounter::default());
+        let dut = DutInfo::builder("dut_id").build();
+        let run = TestRun::builder("run_name", "1.0")
+            .config(
+                Config::builder()
+                    .with_custom_output(Box::new(CountingWriter(Arc::clone(&counter))))
+                    .flush_each(flush_each)
+                    .build(),
+            )
+            .build()
+            .start(dut)
+            .await?;
+
+        let step = run.add_step("step_name").start().await?;
+        step.add_measurement("name", 50).await?;
+        step.end(TestStatus::Complete).await?;
+        run.end(TestStatus::Complete, TestResult::Pass).await?;
+
+        Ok((
+            counter.writes.load(Ordering::SeqCst),
+            counter.flushes.load(Ordering::SeqCst),
+        ))
+    }
+
+    assert_eq!(counts(true).await?, (6, 6));
+    // the run end flushes the output once
+    assert_eq!(counts(false).await?, (6, 1));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_config_builder_with_min_severity() -> Result<()> {
+    use ocptv::output::{LogSeverity, TestResult, TestStatus};
+
+    use super::fixture::*;
+
+    let artifacts = collect_output(
+        |config| config.min_severity(LogSeverity::Info),
+        |run_builder, dut| async move {
+            let run = run_builder.build().start(dut).await?;
+            run.add_log(LogSeverity::Debug, "run debug").await?;
+            run.add_log(LogSeverity::Info, "run info").await?;
+            let step = run.add_step("step_name").start().await?;
+            step.add_log(LogSeverity::Debug, "step debug").await?;
+            step.add_log(LogSeverity::Warning, "step warning").await?;
+            step.add_error("symptom").await?;
+            step.end(TestStatus::Complete).await?;
+            run.end(TestStatus::Complete, TestResult::Pass).await?;
+            Ok(())
+        },
+    )
+    .await?;
+
+    let messages = artifacts
+        .iter()
+        .filter_map(|a| {
+            a.pointer("/testRunArtifact/log/message")
+                .or_else(|| a.pointer("/testStepArtifact/log/message"))
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(messages, ["run info", "step warning"]);
+    assert!(artifacts
+        .iter()
+        .any(|a| a.pointer("/testStepArtifact/error").is_some()));
+
+    // no gaps left by the dropped logs
+    for (i, artifact) in artifacts.iter().enumerate() {
+        assert_eq!(artifact["sequenceNumber"], i);
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_config_builder_with_symptom_pattern() -> Result<()> {
+    use ocptv::output::{DiagnosisType, OcptvError, TestResult, TestStatus};
+
+    use super::fixture::*;
+
+    let pattern = regex::Regex::new(r"^[a-z][a-z0-9]*(_[a-z0-9]+)*$")?;
+    let artifacts = collect_output(
+        |config| config.symptom_pattern(pattern),
+        |run_builder, dut| async move {
+            let run = run_builder.build().start(dut).await?;
+            let step = run.add_step("step_name").start().await?;
+            step.add_error("fan_stuck").await?;
+            step.add_diagnosis("fan_ok", DiagnosisType::Pass).await?;
+
+            let actual = step.add_error("Fan Stuck").await;
+            match actual {
+                Err(OcptvError::IoError(e)) => {
+                    assert_eq!(e.kind(), std::io::ErrorKind::InvalidInput)
+                }
+                _ => panic!("expected an invalid input error"),
+            }
+            assert!(step
+                .add_diagnosis("fanOk", DiagnosisType::Pass)
+                .await
+                .is_err());
+            assert!(run.add_error("run-error").await.is_err());
+
+            step.end(TestStatus::Complete).await?;
+            run.end(TestStatus::Complete, TestResult::Pass).await?;
+            Ok(())
+        },
+    )
+    .await?;
+
+    // only the conforming error and diagnosis are written, without gaps
+    let symptoms = artifacts
+        .iter()
+        .filter_map(|a| {
+            a.pointer("/testStepArtifact/error/symptom")
+                .or_else(|| a.pointer("/testStepArtifact/diagnosis/verdict"))
+                .or_else(|| a.pointer("/testRunArtifact/error/symptom"))
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(symptoms, ["fan_stuck", "fan_ok"]);
+    for (i, artifact) in artifacts.iter().enumerate() {
+        assert_eq!(artifact["sequenceNumber"], i);
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_config_shared_between_runs() -> Result<()> {
+    use std::sync::Arc;
+
+    use tokio::sync::Mutex;
+
+    use ocptv::output::{Config, DutInfo, TestResult, TestRun, TestStatus};
+
+    let buffer: Arc<Mutex<Vec<String>>> = Arc::new(Mutex::new(vec![]));
+    let shared = Config::builder()
+        .with_buffer_output(Arc::clone(&buffer))
+        .build()
+        .shared();
+
+    for name in ["first", "second"] {
+        let run = TestRun::builder(name, "1.0")
+            .config(shared.config())
+            .build()
+            .start(DutInfo::new("dut_id"))
+            .await?;
+        run.end(TestStatus::Complete, TestResult::Pass).await?;
+    }
+
+    let artifacts = buffer
+        .lock()
+        .await
+        .iter()
+        .map(|s| serde_json::from_str::<serde_json::Value>(s))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    // schemaVersion, start and end for each run, each with its own sequence numbers
+    assert_eq!(artifacts.len(), 6);
+    let names = artifacts
+        .iter()
+        .filter_map(|a| a.pointer("/testRunArtifact/testRunStart/name"))
+        .collect::<Vec<_>>();
+    assert_eq!(names, ["first", "second"]);
+    let seqnos = artifacts
+        .iter()
+        .map(|a| a["sequenceNumber"].as_u64().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(seqnos, [0, 1, 2, 0, 1, 2]);
+
+    Ok(())
+}
+
+#[test]
+fn test_config_timezone() {
+    use ocptv::output::Config;
+
+    assert_eq!(Config::builder().build().timezone(), chrono_tz::UTC);
+
+    let config = Config::builder().timezone(chrono_tz::Asia::Kolkata).build();
+    assert_eq!(config.timezone(), chrono_tz::Asia::Kolkata);
+}
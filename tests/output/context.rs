@@ -0,0 +1,131 @@
+// (c) Meta Platforms, Inc. and affiliates.
+//
+// Use of this source code is governed by an MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+use anyhow::Result;
+use serde_json::json;
+
+use ocptv::output::{context, Measurement, OcptvError, TestStatus};
+
+use super::fixture::*;
+
+#[tokio::test]
+async fn test_ambient_logging_in_current_step() -> Result<()> {
+    let expected = [
+        json_schema_version(),
+        json_run_default_start(),
+        json_step_default_start(),
+        json!({
+            "testStepArtifact": {
+                "testStepId": "step1",
+                "testStepStart": {
+                    "name": "second step"
+                }
+            },
+            "sequenceNumber": 3,
+            "timestamp": DATETIME_FORMATTED
+        }),
+        json!({
+            "testStepArtifact": {
+                "testStepId": "step1",
+                "log": {
+                    "message": "inner",
+                    "severity": "INFO"
+                }
+            },
+            "sequenceNumber": 4,
+            "timestamp": DATETIME_FORMATTED
+        }),
+        json!({
+            "testStepArtifact": {
+                "testStepId": "step0",
+                "measurement": {
+                    "name": "fan_speed",
+                    "value": 1600
+                }
+            },
+            "sequenceNumber": 5,
+            "timestamp": DATETIME_FORMATTED
+        }),
+        json!({
+            "testStepArtifact": {
+                "testStepId": "step1",
+                "testStepEnd": {
+                    "status": "COMPLETE"
+                }
+            },
+            "sequenceNumber": 6,
+            "timestamp": DATETIME_FORMATTED
+        }),
+        json_step_complete(7),
+        json_run_pass(8),
+    ];
+
+    check_output_run(&expected, |r, _| async move {
+        let first = r.add_step("first step").start().await?;
+        let second = r.add_step("second step").start().await?;
+
+        context::with_current_step(&first, async {
+            context::with_current_step(&second, context::log_info("inner")).await?;
+            context::add_measurement("fan_speed", 1600).await
+        })
+        .await?;
+
+        second.end(TestStatus::Complete).await?;
+        first.end(TestStatus::Complete).await?;
+
+        Ok(())
+    })
+    .await
+}
+
+#[tokio::test]
+async fn test_ambient_measurement_shares_step_ordinals() -> Result<()> {
+    let measurement = |seqno: i32, name: &str| {
+        json!({
+            "testStepArtifact": {
+                "testStepId": "step0",
+                "measurement": {
+                    "name": name,
+                    "value": 1600
+                }
+            },
+            "sequenceNumber": seqno,
+            "timestamp": DATETIME_FORMATTED
+        })
+    };
+    let expected = [
+        json_schema_version(),
+        json_run_default_start(),
+        json_step_default_start(),
+        measurement(3, "fan0"),
+        measurement(4, "fan1"),
+        json_step_complete(5),
+        json_run_pass(6),
+    ];
+
+    check_output_run(&expected, |r, _| async move {
+        let step = r.add_step("first step").start().await?;
+        context::with_current_step(&step, context::add_measurement("fan0", 1600)).await?;
+
+        let detail = Measurement::new("fan1", 1600);
+        assert_eq!(step.add_measurement_with_ordinal(detail).await?, 1);
+
+        step.end(TestStatus::Complete).await?;
+        Ok(())
+    })
+    .await
+}
+
+#[tokio::test]
+async fn test_ambient_logging_outside_step_scope() -> Result<()> {
+    let actual = context::log_info("orphan").await;
+    assert!(matches!(actual, Err(OcptvError::Other(_))));
+
+    let actual = context::add_measurement("fan_speed", 1600).await;
+    assert!(matches!(actual, Err(OcptvError::Other(_))));
+
+    Ok(())
+}
@@ -7,7 +7,7 @@
 use anyhow::Result;
 use serde_json::json;
 
-use ocptv::output::{Diagnosis, DiagnosisType, Subcomponent};
+use ocptv::output::{Diagnosis, DiagnosisType, Subcomponent, Validator, ValidatorType};
 
 use super::fixture::*;
 
@@ -40,6 +40,67 @@ async fn test_step_with_diagnosis() -> Result<()> {
     .await
 }
 
+#[tokio::test]
+async fn test_step_with_skip_diagnosis() -> Result<()> {
+    let expected = [
+        json_schema_version(),
+        json_run_default_start(),
+        json_step_default_start(),
+        json!({
+            "testStepArtifact": {
+                "testStepId": "step0",
+                "diagnosis": {
+                    "verdict": "ecc-check",
+                    "type": "UNKNOWN",
+                    "message": "skipped: no ECC capable DIMMs"
+                }
+            },
+            "sequenceNumber": 3,
+            "timestamp": DATETIME_FORMATTED
+        }),
+        json_step_complete(4),
+        json_run_pass(5),
+    ];
+
+    check_output_step(&expected, |s, _| async move {
+        s.add_skip_diagnosis("ecc-check", "no ECC capable DIMMs")
+            .await?;
+
+        Ok(())
+    })
+    .await
+}
+
+#[tokio::test]
+async fn test_step_with_diagnosis_unknown() -> Result<()> {
+    let expected = [
+        json_schema_version(),
+        json_run_default_start(),
+        json_step_default_start(),
+        json!({
+            "testStepArtifact": {
+                "testStepId": "step0",
+                "diagnosis": {
+                    "verdict": "inconclusive",
+                    "type": "UNKNOWN"
+                }
+            },
+            "sequenceNumber": 3,
+            "timestamp": DATETIME_FORMATTED
+        }),
+        json_step_complete(4),
+        json_run_pass(5),
+    ];
+
+    check_output_step(&expected, |s, _| async move {
+        s.add_diagnosis("inconclusive", DiagnosisType::Unknown)
+            .await?;
+
+        Ok(())
+    })
+    .await
+}
+
 #[tokio::test]
 async fn test_step_with_diagnosis_builder() -> Result<()> {
     let expected = [
@@ -80,3 +141,83 @@ async fn test_step_with_diagnosis_builder() -> Result<()> {
     })
     .await
 }
+
+#[tokio::test]
+async fn test_step_with_measurement_and_diagnosis() -> Result<()> {
+    let expected = [
+        json_schema_version(),
+        json_run_default_start(),
+        json_step_default_start(),
+        json!({
+            "testStepArtifact": {
+                "testStepId": "step0",
+                "measurement": {
+                    "name": "fan_speed",
+                    "value": 1600,
+                    "validators": [{
+                        "type": "GREATER_THAN",
+                        "value": 1000
+                    }]
+                }
+            },
+            "sequenceNumber": 3,
+            "timestamp": DATETIME_FORMATTED
+        }),
+        json!({
+            "testStepArtifact": {
+                "testStepId": "step0",
+                "diagnosis": {
+                    "verdict": "fan_speed_pass",
+                    "type": "PASS"
+                }
+            },
+            "sequenceNumber": 4,
+            "timestamp": DATETIME_FORMATTED
+        }),
+        json!({
+            "testStepArtifact": {
+                "testStepId": "step0",
+                "measurement": {
+                    "name": "fan_speed",
+                    "value": 800,
+                    "validators": [{
+                        "type": "GREATER_THAN",
+                        "value": 1000
+                    }]
+                }
+            },
+            "sequenceNumber": 5,
+            "timestamp": DATETIME_FORMATTED
+        }),
+        json!({
+            "testStepArtifact": {
+                "testStepId": "step0",
+                "diagnosis": {
+                    "verdict": "fan_speed_fail",
+                    "type": "FAIL"
+                }
+            },
+            "sequenceNumber": 6,
+            "timestamp": DATETIME_FORMATTED
+        }),
+        json_step_complete(7),
+        json_run_pass(8),
+    ];
+
+    check_output_step(&expected, |s, _| async move {
+        let validators = [Validator::builder(ValidatorType::GreaterThan, 1000).build()];
+
+        let passed = s
+            .add_measurement_and_diagnosis("fan_speed", 1600, &validators, "fan_speed")
+            .await?;
+        assert!(passed);
+
+        let passed = s
+            .add_measurement_and_diagnosis("fan_speed", 800, &validators, "fan_speed")
+            .await?;
+        assert!(!passed);
+
+        Ok(())
+    })
+    .await
+}
@@ -99,6 +99,73 @@ async fn test_testrun_with_error_with_details() -> Result<()> {
     .await
 }
 
+#[tokio::test]
+async fn test_testrun_with_error_with_code() -> Result<()> {
+    let expected = [
+        json_schema_version(),
+        json_run_default_start(),
+        json!({
+            "testRunArtifact": {
+                "error": {
+                    "message": "[E1042] fan0 stuck at 0 rpm",
+                    "symptom": "fan-stuck"
+                }
+            },
+            "sequenceNumber": 2,
+            "timestamp": DATETIME_FORMATTED
+        }),
+        json_run_pass(3),
+    ];
+
+    check_output_run(&expected, |r, _| async move {
+        r.add_error_detail(
+            Error::builder("fan-stuck")
+                .code("E1042")
+                .message("fan0 stuck at 0 rpm")
+                .build(),
+        )
+        .await
+    })
+    .await
+}
+
+#[tokio::test]
+async fn test_testrun_with_error_resolving_dut_from_run() -> Result<()> {
+    let expected = [
+        json_schema_version(),
+        json_run_default_start(),
+        json!({
+            "testRunArtifact": {
+                "error": {
+                    "softwareInfoIds": [
+                        "sw0"
+                    ],
+                    "symptom": "symptom"
+                }
+            },
+            "sequenceNumber": 2,
+            "timestamp": DATETIME_FORMATTED
+        }),
+        json_run_pass(3),
+    ];
+
+    check_output_run(&expected, |r, _| async move {
+        let sw_info = r
+            .dut()
+            .software_info("sw0")
+            .expect("sw0 is registered on the run dut")
+            .clone();
+
+        r.add_error_detail(
+            Error::builder("symptom")
+                .add_software_info(&sw_info)
+                .build(),
+        )
+        .await
+    })
+    .await
+}
+
 #[tokio::test]
 async fn test_testrun_with_error_before_start() -> Result<()> {
     let expected = [
@@ -282,3 +349,59 @@ async fn test_testrun_step_error_with_details() -> Result<()> {
     })
     .await
 }
+
+#[tokio::test]
+async fn test_testrun_step_correlated_errors() -> Result<()> {
+    let error = |symptom: &str, seqno: i32| {
+        json!({
+            "testStepArtifact": {
+                "testStepId": "step0",
+                "error": {
+                    "symptom": symptom
+                }
+            },
+            "sequenceNumber": seqno,
+            "timestamp": DATETIME_FORMATTED
+        })
+    };
+    let expected = [
+        json_schema_version(),
+        json_run_default_start(),
+        json_step_default_start(),
+        json!({
+            "testStepArtifact": {
+                "testStepId": "step0",
+                "extension": {
+                    "name": "error_correlation",
+                    "content": {
+                        "@type": "ErrorCorrelation/v1",
+                        "correlationId": "incident0",
+                        "errorCount": 3
+                    }
+                }
+            },
+            "sequenceNumber": 3,
+            "timestamp": DATETIME_FORMATTED
+        }),
+        error("pcie_link_down", 4),
+        error("nvme_missing", 5),
+        error("raid_degraded", 6),
+        json_step_complete(7),
+        json_run_pass(8),
+    ];
+
+    check_output_step(&expected, |s, _| async move {
+        s.add_correlated_errors(
+            &[
+                Error::builder("pcie_link_down").build(),
+                Error::builder("nvme_missing").build(),
+                Error::builder("raid_degraded").build(),
+            ],
+            "incident0",
+        )
+        .await?;
+
+        Ok(())
+    })
+    .await
+}
@@ -7,7 +7,7 @@
 use anyhow::Result;
 use serde_json::json;
 
-use ocptv::output::{File, Uri};
+use ocptv::output::{File, TestStatus, Uri};
 
 use super::fixture::*;
 
@@ -82,3 +82,71 @@ async fn test_step_with_file_builder() -> Result<()> {
     })
     .await
 }
+
+#[tokio::test]
+async fn test_step_with_inline_file() -> Result<()> {
+    let expected = [
+        json_schema_version(),
+        json_run_default_start(),
+        json_step_default_start(),
+        json!({
+            "testStepArtifact": {
+                "testStepId": "step0",
+                "file": {
+                    "displayName": "regs",
+                    "uri": "data:application/octet-stream;base64,3q2+7w==",
+                    "isSnapshot": false,
+                    "contentType": "application/octet-stream"
+                }
+            },
+            "sequenceNumber": 3,
+            "timestamp": DATETIME_FORMATTED
+        }),
+        json_step_complete(4),
+        json_run_pass(5),
+    ];
+
+    check_output_step(&expected, |s, _| async move {
+        s.add_inline_file(
+            "regs",
+            &[0xde, 0xad, 0xbe, 0xef],
+            mime::APPLICATION_OCTET_STREAM,
+        )
+        .await?;
+
+        Ok(())
+    })
+    .await
+}
+
+#[tokio::test]
+async fn test_step_with_inline_file_over_size_cap() -> Result<()> {
+    use ocptv::output::OcptvError;
+
+    let artifacts = collect_output(
+        |config| config.inline_file_max_size(4),
+        |run_builder, dut| async move {
+            let run = run_builder.build().start(dut).await?;
+            let step = run.add_step("step_name").start().await?;
+
+            // at the cap is fine
+            step.add_inline_file("regs", &[0; 4], mime::APPLICATION_OCTET_STREAM)
+                .await?;
+            let actual = step
+                .add_inline_file("regs", &[0; 5], mime::APPLICATION_OCTET_STREAM)
+                .await;
+            assert!(matches!(actual, Err(OcptvError::Other(_))));
+            step.end(TestStatus::Complete).await?;
+            Ok(())
+        },
+    )
+    .await?;
+
+    let files = artifacts
+        .iter()
+        .filter(|a| a.pointer("/testStepArtifact/file").is_some())
+        .count();
+    assert_eq!(files, 1);
+
+    Ok(())
+}
@@ -13,8 +13,8 @@ use serde_json::json;
 use tokio::sync::Mutex;
 
 use ocptv::output::{
-    Config, DutInfo, HardwareInfo, Ident, OcptvError, ScopedTestRun, ScopedTestStep, SoftwareInfo,
-    SoftwareType, TestResult, TestRun, TestRunBuilder, TestRunOutcome, TestStatus,
+    Config, ConfigBuilder, DutInfo, HardwareInfo, Ident, OcptvError, ScopedTestRun, ScopedTestStep,
+    SoftwareInfo, SoftwareType, TestResult, TestRun, TestRunBuilder, TestRunOutcome, TestStatus,
     TimestampProvider, SPEC_VERSION,
 };
 
@@ -112,6 +112,54 @@ pub fn json_step_complete(seqno: i32) -> serde_json::Value {
     })
 }
 
+pub fn fixture_dut() -> DutInfo {
+    let mut dut = DutInfo::builder("dut_id").build();
+    dut.add_software_info(
+        SoftwareInfo::builder("ubuntu")
+            .id(Ident::Exact("sw0".to_owned())) // name is important as fixture
+            .version("22")
+            .software_type(SoftwareType::System)
+            .build(),
+    );
+    dut.add_hardware_info(
+        HardwareInfo::builder("fan")
+            .id(Ident::Exact("hw0".to_owned()))
+            .location("board0/fan")
+            .build(),
+    );
+    dut
+}
+
+/// Runs `test_fn` with the fixture run builder and dut, then returns the parsed output.
+/// `config_fn` can change the fixture config, which writes to a buffer with fixed timestamps.
+pub async fn collect_output<C, F, R>(config_fn: C, test_fn: F) -> Result<Vec<serde_json::Value>>
+where
+    C: FnOnce(ConfigBuilder) -> ConfigBuilder,
+    R: Future<Output = Result<()>>,
+    F: FnOnce(TestRunBuilder, DutInfo) -> R,
+{
+    let buffer: Arc<Mutex<Vec<String>>> = Arc::new(Mutex::new(vec![]));
+    let run_builder = TestRun::builder("run_name", "1.0").config(
+        config_fn(
+            Config::builder()
+                .with_buffer_output(Arc::clone(&buffer))
+                .with_timestamp_provider(Box::new(FixedTsProvider {})),
+        )
+        .build(),
+    );
+
+    // run the main test closure
+    test_fn(run_builder, fixture_dut()).await?;
+
+    let artifacts = buffer
+        .lock()
+        .await
+        .iter()
+        .map(|s| serde_json::from_str(s))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(artifacts)
+}
+
 pub async fn check_output<F, R>(expected: &[serde_json::Value], test_fn: F) -> Result<()>
 where
     R: Future<Output = Result<()>>,
@@ -151,6 +199,25 @@ where
     Ok(())
 }
 
+pub async fn check_output_config<C, F, R>(
+    expected: &[serde_json::Value],
+    config_fn: C,
+    test_fn: F,
+) -> Result<()>
+where
+    C: FnOnce(ConfigBuilder) -> ConfigBuilder,
+    R: Future<Output = Result<()>>,
+    F: FnOnce(TestRunBuilder, DutInfo) -> R,
+{
+    let actual = collect_output(config_fn, test_fn).await?;
+    assert_eq!(actual.len(), expected.len());
+    for (i, value) in actual.iter().enumerate() {
+        assert_json_eq!(value, expected[i]);
+    }
+
+    Ok(())
+}
+
 pub async fn check_output_run<F, R>(expected: &[serde_json::Value], test_fn: F) -> Result<()>
 where
     R: Future<Output = Result<(), OcptvError>> + Send + 'static,
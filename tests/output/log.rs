@@ -7,7 +7,7 @@
 use anyhow::Result;
 use serde_json::json;
 
-use ocptv::output::{Log, LogSeverity};
+use ocptv::output::{Log, LogSeverity, TestStatus};
 
 use super::fixture::*;
 
@@ -144,3 +144,97 @@ async fn test_testrun_step_log_with_details() -> Result<()> {
     })
     .await
 }
+
+#[tokio::test]
+async fn test_testrun_step_log_with_fields() -> Result<()> {
+    let expected = [
+        json_schema_version(),
+        json_run_default_start(),
+        json_step_default_start(),
+        json!({
+            "testStepArtifact": {
+                "testStepId": "step0",
+                "log": {
+                    "message": "fan speed out of range fan=\"fan0\" rpm=3600",
+                    "severity": "WARNING"
+                }
+            },
+            "sequenceNumber": 3,
+            "timestamp": DATETIME_FORMATTED
+        }),
+        json_step_complete(4),
+        json_run_pass(5),
+    ];
+
+    check_output_step(&expected, |s, _| async move {
+        s.add_log_fields(
+            LogSeverity::Warning,
+            "fan speed out of range",
+            vec![("fan", "fan0".into()), ("rpm", 3600.into())],
+        )
+        .await?;
+
+        Ok(())
+    })
+    .await
+}
+
+#[tokio::test]
+async fn test_testrun_and_step_log_at() -> Result<()> {
+    let expected = [
+        json_schema_version(),
+        json_run_default_start(),
+        json!({
+            "testRunArtifact": {
+                "log": {
+                    "message": "run replayed",
+                    "severity": "INFO"
+                }
+            },
+            "sequenceNumber": 2,
+            "timestamp": "2023-11-14T22:13:20.000Z"
+        }),
+        json!({
+            "testStepArtifact": {
+                "testStepId": "step0",
+                "testStepStart": {
+                    "name": "first step"
+                }
+            },
+            "sequenceNumber": 3,
+            "timestamp": DATETIME_FORMATTED
+        }),
+        json!({
+            "testStepArtifact": {
+                "testStepId": "step0",
+                "log": {
+                    "message": "step replayed",
+                    "severity": "WARNING"
+                }
+            },
+            "sequenceNumber": 4,
+            "timestamp": "2023-11-14T22:13:21.000Z"
+        }),
+        json_step_complete(5),
+        json_run_pass(6),
+    ];
+
+    check_output_run(&expected, |r, _| async move {
+        let captured_at = chrono::DateTime::from_timestamp(1_700_000_000, 0)
+            .unwrap()
+            .with_timezone(&chrono_tz::UTC);
+        r.add_log_at(LogSeverity::Info, "run replayed", captured_at)
+            .await?;
+
+        let step = r.add_step("first step").start().await?;
+        // explicit timestamps are converted to the configured timezone
+        let captured_at =
+            (captured_at + chrono::Duration::seconds(1)).with_timezone(&chrono_tz::Europe::Rome);
+        step.add_log_at(LogSeverity::Warning, "step replayed", captured_at)
+            .await?;
+        step.end(TestStatus::Complete).await?;
+
+        Ok(())
+    })
+    .await
+}
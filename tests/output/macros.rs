@@ -17,7 +17,7 @@ use ocptv::ocptv_error;
 use ocptv::output as tv;
 use ocptv::{
     ocptv_diagnosis_fail, ocptv_diagnosis_pass, ocptv_diagnosis_unknown, ocptv_log_debug,
-    ocptv_log_error, ocptv_log_fatal, ocptv_log_info, ocptv_log_warning,
+    ocptv_log_error, ocptv_log_fatal, ocptv_log_info, ocptv_log_warning, ocptv_measurement,
 };
 use tv::{Config, DutInfo, StartedTestRun, StartedTestStep, TestRun};
 
@@ -424,3 +424,39 @@ async fn test_ocptv_diagnosis_unknown_in_step() -> Result<()> {
     })
     .await
 }
+
+#[tokio::test]
+async fn test_ocptv_measurement_in_step() -> Result<()> {
+    let expected = json!({
+        "testStepArtifact": {
+            "measurement": {
+                "name": "fan_speed",
+                "value": 1600,
+            }
+        },
+        "sequenceNumber": 3
+    });
+
+    let line = Arc::new(Mutex::new(0));
+    let actual = check_output::<_, _, 4>(&expected, |run| {
+        let line = Arc::clone(&line);
+        async move {
+            let step = run.add_step("step_name").start().await?;
+            *line.lock().await = line!() + 1;
+            ocptv_measurement!(step, "fan_speed", 1600).await?;
+            Ok(())
+        }
+    })
+    .await?;
+
+    let source = &actual["testStepArtifact"]["measurement"]["metadata"]["sourceLocation"];
+    assert_eq!(
+        source,
+        &json!({
+            "file": file!(),
+            "line": *line.lock().await,
+        })
+    );
+
+    Ok(())
+}
@@ -5,6 +5,7 @@
 // https://opensource.org/licenses/MIT.
 
 mod config;
+mod context;
 mod diagnosis;
 mod error;
 mod file;
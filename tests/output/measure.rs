@@ -4,12 +4,15 @@
 // license that can be found in the LICENSE file or at
 // https://opensource.org/licenses/MIT.
 
+use std::sync::Arc;
+
 use anyhow::Result;
+use assert_json_diff::assert_json_eq;
 use serde_json::json;
 
 use ocptv::output::{
-    Ident, Measurement, MeasurementElementDetail, MeasurementSeriesDetail, Subcomponent, Validator,
-    ValidatorType,
+    Ident, LogSeverity, Measurement, MeasurementElementDetail, MeasurementSeriesDetail, OcptvError,
+    Quantity, Subcomponent, TestResult, TestStatus, Unit, Validator, ValidatorType,
 };
 
 use super::fixture::*;
@@ -43,6 +46,37 @@ async fn test_step_with_measurement() -> Result<()> {
     .await
 }
 
+#[tokio::test]
+async fn test_step_with_measurement_quantity() -> Result<()> {
+    let expected = [
+        json_schema_version(),
+        json_run_default_start(),
+        json_step_default_start(),
+        json!({
+            "testStepArtifact": {
+                "testStepId": "step0",
+                "measurement": {
+                    "name": "fan_speed",
+                    "value": 1200.0,
+                    "unit": "RPM"
+                }
+            },
+            "sequenceNumber": 3,
+            "timestamp": DATETIME_FORMATTED
+        }),
+        json_step_complete(4),
+        json_run_pass(5),
+    ];
+
+    check_output_step(&expected, |s, _| async move {
+        let measurement = Measurement::quantity("fan_speed", Quantity::new(1200.0, Unit::Rpm));
+        s.add_measurement_detail(measurement).await?;
+
+        Ok(())
+    })
+    .await
+}
+
 #[tokio::test]
 async fn test_step_with_measurement_builder() -> Result<()> {
     let expected = [
@@ -95,6 +129,175 @@ async fn test_step_with_measurement_builder() -> Result<()> {
     .await
 }
 
+#[tokio::test]
+async fn test_step_all_measurements_passed() -> Result<()> {
+    let expected = [
+        json_schema_version(),
+        json_run_default_start(),
+        json_step_default_start(),
+        json!({
+            "testStepArtifact": {
+                "testStepId": "step0",
+                "measurement": {
+                    "name": "fan_speed",
+                    "value": 1600,
+                    "validators": [{
+                        "type": "GREATER_THAN",
+                        "value": 1000
+                    }]
+                }
+            },
+            "sequenceNumber": 3,
+            "timestamp": DATETIME_FORMATTED
+        }),
+        json!({
+            "testStepArtifact": {
+                "testStepId": "step0",
+                "measurement": {
+                    "name": "temperature",
+                    "value": 95,
+                    "validators": [{
+                        "type": "LESS_THAN",
+                        "value": 80
+                    }]
+                }
+            },
+            "sequenceNumber": 4,
+            "timestamp": DATETIME_FORMATTED
+        }),
+        json_step_complete(5),
+        json_run_pass(6),
+    ];
+
+    check_output_step(&expected, |s, _| async move {
+        assert!(s.all_measurements_passed());
+
+        let measurement = Measurement::builder("fan_speed", 1600)
+            .add_validator(Validator::builder(ValidatorType::GreaterThan, 1000).build())
+            .build();
+        s.add_measurement_detail(measurement).await?;
+        assert!(s.all_measurements_passed());
+
+        let measurement = Measurement::builder("temperature", 95)
+            .add_validator(Validator::builder(ValidatorType::LessThan, 80).build())
+            .build();
+        s.add_measurement_detail(measurement).await?;
+        assert!(!s.all_measurements_passed());
+
+        Ok(())
+    })
+    .await
+}
+
+#[tokio::test]
+async fn test_step_measurement_ordinals() -> Result<()> {
+    let measurement = |seqno: i32, name: &str, value: i32| {
+        json!({
+            "testStepArtifact": {
+                "testStepId": "step0",
+                "measurement": {
+                    "name": name,
+                    "value": value
+                }
+            },
+            "sequenceNumber": seqno,
+            "timestamp": DATETIME_FORMATTED
+        })
+    };
+    let expected = [
+        json_schema_version(),
+        json_run_default_start(),
+        json_step_default_start(),
+        measurement(3, "fan0", 1000),
+        json!({
+            "testStepArtifact": {
+                "testStepId": "step0",
+                "log": {
+                    "message": "not a measurement",
+                    "severity": "INFO"
+                }
+            },
+            "sequenceNumber": 4,
+            "timestamp": DATETIME_FORMATTED
+        }),
+        measurement(5, "fan1", 1100),
+        measurement(6, "fan2", 1200),
+        json_step_complete(7),
+        json_run_pass(8),
+    ];
+
+    check_output_step(&expected, |s, _| async move {
+        let detail = Measurement::new("fan0", 1000);
+        assert_eq!(s.add_measurement_with_ordinal(detail).await?, 0);
+        s.add_log(LogSeverity::Info, "not a measurement").await?;
+        s.add_measurement("fan1", 1100).await?;
+        let detail = Measurement::new("fan2", 1200);
+        assert_eq!(s.add_measurement_with_ordinal(detail).await?, 2);
+
+        Ok(())
+    })
+    .await
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_step_measurement_ordinals_from_concurrent_handles() -> Result<()> {
+    const TASKS: u64 = 4;
+    const PER_TASK: u64 = 50;
+
+    let ordinals = Arc::new(std::sync::Mutex::new(vec![
+        None;
+        (TASKS * PER_TASK) as usize
+    ]));
+    let artifacts = collect_output(
+        |config| config,
+        |run_builder, dut| {
+            let ordinals = Arc::clone(&ordinals);
+            async move {
+                let run = run_builder.build().start(dut).await?;
+                let step = run.add_step("step_name").start().await?;
+
+                let tasks = (0..TASKS)
+                    .map(|task| {
+                        let handle = step.clone_handle();
+                        let ordinals = Arc::clone(&ordinals);
+                        tokio::spawn(async move {
+                            for i in 0..PER_TASK {
+                                // the value identifies the measurement in the output
+                                let value = task * PER_TASK + i;
+                                let ordinal = handle
+                                    .add_measurement_with_ordinal(Measurement::new("fan", value))
+                                    .await?;
+                                ordinals.lock().unwrap()[value as usize] = Some(ordinal);
+                            }
+                            Ok::<(), OcptvError>(())
+                        })
+                    })
+                    .collect::<Vec<_>>();
+                for task in tasks {
+                    task.await??;
+                }
+
+                step.end(TestStatus::Complete).await?;
+                run.end(TestStatus::Complete, TestResult::Pass).await?;
+                Ok(())
+            }
+        },
+    )
+    .await?;
+
+    let ordinals = ordinals.lock().unwrap();
+    let values = artifacts
+        .iter()
+        .filter_map(|a| a["testStepArtifact"]["measurement"]["value"].as_u64())
+        .collect::<Vec<_>>();
+    assert_eq!(values.len(), (TASKS * PER_TASK) as usize);
+    for (position, value) in values.iter().enumerate() {
+        assert_eq!(ordinals[*value as usize], Some(position as u64));
+    }
+
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_step_with_measurement_series() -> Result<()> {
     let expected = [
@@ -202,6 +405,107 @@ async fn test_step_with_multiple_measurement_series() -> Result<()> {
     .await
 }
 
+#[tokio::test]
+async fn test_measurement_series_ids_unique_across_steps() -> Result<()> {
+    let expected = [
+        json_schema_version(),
+        json_run_default_start(),
+        json_step_default_start(),
+        json!({
+            "testStepArtifact": {
+                "testStepId": "step0",
+                "measurementSeriesStart": {
+                    "measurementSeriesId": "step0_series0",
+                    "name": "name"
+                }
+            },
+            "sequenceNumber": 3,
+            "timestamp": DATETIME_FORMATTED
+        }),
+        json_step_complete(4),
+        json!({
+            "testStepArtifact": {
+                "testStepId": "step1",
+                "testStepStart": {
+                    "name": "second step"
+                }
+            },
+            "sequenceNumber": 5,
+            "timestamp": DATETIME_FORMATTED
+        }),
+        json!({
+            "testStepArtifact": {
+                "testStepId": "step1",
+                "measurementSeriesStart": {
+                    "measurementSeriesId": "step1_series1",
+                    "name": "name"
+                }
+            },
+            "sequenceNumber": 6,
+            "timestamp": DATETIME_FORMATTED
+        }),
+        json!({
+            "testStepArtifact": {
+                "testStepId": "step1",
+                "testStepEnd": {
+                    "status": "COMPLETE"
+                }
+            },
+            "sequenceNumber": 7,
+            "timestamp": DATETIME_FORMATTED
+        }),
+        json_run_pass(8),
+    ];
+
+    check_output_run(&expected, |r, _| async move {
+        let step = r.add_step("first step").start().await?;
+        step.add_measurement_series("name").start().await?;
+        step.end(TestStatus::Complete).await?;
+
+        let step = r.add_step("second step").start().await?;
+        step.add_measurement_series("name").start().await?;
+        step.end(TestStatus::Complete).await?;
+
+        Ok(())
+    })
+    .await
+}
+
+#[tokio::test]
+async fn test_step_with_measurement_series_id_generator() -> Result<()> {
+    let artifacts = collect_output(
+        |config| config.series_id_generator(Box::new(|n| format!("s-{}", n))),
+        |run_builder, dut| async move {
+            let run = run_builder.build().start(dut).await?;
+            let step = run.add_step("first step").start().await?;
+            step.add_measurement_series("name").start().await?;
+            step.add_measurement_series("name").start().await?;
+            step.add_measurement_series_detail(
+                MeasurementSeriesDetail::builder("name")
+                    .id(Ident::Exact("explicit".to_owned()))
+                    .build(),
+            )
+            .start()
+            .await?;
+            step.end(TestStatus::Complete).await?;
+            run.end(TestStatus::Complete, TestResult::Pass).await?;
+            Ok(())
+        },
+    )
+    .await?;
+
+    let expected = ["s-0", "s-1", "explicit"];
+    let series_starts = artifacts
+        .iter()
+        .filter_map(|value| {
+            value["testStepArtifact"]["measurementSeriesStart"]["measurementSeriesId"].as_str()
+        })
+        .collect::<Vec<_>>();
+    assert_json_eq!(json!(series_starts), json!(expected));
+
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_step_with_measurement_series_with_details() -> Result<()> {
     let expected = [
@@ -414,6 +718,75 @@ async fn test_step_with_measurement_series_element_index_no() -> Result<()> {
     .await
 }
 
+#[tokio::test]
+async fn test_step_with_measurement_series_element_at_index() -> Result<()> {
+    let element = |index: u64, value: i32, seqno: i32| {
+        json!({
+            "testStepArtifact": {
+                "testStepId": "step0",
+                "measurementSeriesElement": {
+                    "index": index,
+                    "measurementSeriesId": "step0_series0",
+                    "value": value,
+                    "timestamp": DATETIME_FORMATTED
+                }
+            },
+            "sequenceNumber": seqno,
+            "timestamp": DATETIME_FORMATTED
+        })
+    };
+
+    let expected = [
+        json_schema_version(),
+        json_run_default_start(),
+        json_step_default_start(),
+        json!({
+            "testStepArtifact": {
+                "testStepId": "step0",
+                "measurementSeriesStart": {
+                    "measurementSeriesId": "step0_series0",
+                    "name": "name"
+                }
+            },
+            "sequenceNumber": 3,
+            "timestamp": DATETIME_FORMATTED
+        }),
+        element(0, 60, 4),
+        element(5, 65, 5),
+        // auto index continues after the explicit one
+        element(6, 66, 6),
+        // a lower explicit index doesn't move the counter back
+        element(2, 62, 7),
+        element(7, 67, 8),
+        json!({
+            "testStepArtifact": {
+                "testStepId": "step0",
+                "measurementSeriesEnd": {
+                    "measurementSeriesId": "step0_series0",
+                    "totalCount": 8
+                }
+            },
+            "sequenceNumber": 9,
+            "timestamp": DATETIME_FORMATTED
+        }),
+        json_step_complete(10),
+        json_run_pass(11),
+    ];
+
+    check_output_step(&expected, |s, _| async move {
+        let series = s.add_measurement_series("name").start().await?;
+        series.add_measurement(60).await?;
+        series.add_measurement_at_index(5, 65).await?;
+        series.add_measurement(66).await?;
+        series.add_measurement_at_index(2, 62).await?;
+        series.add_measurement(67).await?;
+        series.end().await?;
+
+        Ok(())
+    })
+    .await
+}
+
 #[tokio::test]
 async fn test_step_with_measurement_series_element_with_details() -> Result<()> {
     let expected = [
@@ -678,3 +1051,726 @@ async fn test_step_with_measurement_series_scope() -> Result<()> {
     })
     .await
 }
+
+#[tokio::test]
+async fn test_step_with_measurement_series_from_stream() -> Result<()> {
+    let element = |index: u64, value: f64, seqno: i32| {
+        json!({
+            "testStepArtifact": {
+                "testStepId": "step0",
+                "measurementSeriesElement": {
+                    "index": index,
+                    "measurementSeriesId": "step0_series0",
+                    "value": value,
+                    "timestamp": DATETIME_FORMATTED
+                }
+            },
+            "sequenceNumber": seqno,
+            "timestamp": DATETIME_FORMATTED
+        })
+    };
+    let expected = [
+        json_schema_version(),
+        json_run_default_start(),
+        json_step_default_start(),
+        json!({
+            "testStepArtifact": {
+                "testStepId": "step0",
+                "measurementSeriesStart": {
+                    "measurementSeriesId": "step0_series0",
+                    "name": "fan_speed"
+                }
+            },
+            "sequenceNumber": 3,
+            "timestamp": DATETIME_FORMATTED
+        }),
+        element(0, 1000.0, 4),
+        element(1, 1200.0, 5),
+        element(2, 1100.0, 6),
+        json!({
+            "testStepArtifact": {
+                "testStepId": "step0",
+                "measurementSeriesEnd": {
+                    "measurementSeriesId": "step0_series0",
+                    "totalCount": 3
+                }
+            },
+            "sequenceNumber": 7,
+            "timestamp": DATETIME_FORMATTED
+        }),
+        json_step_complete(8),
+        json_run_pass(9),
+    ];
+
+    check_output_step(&expected, |s, _| async move {
+        let series = s.add_measurement_series("fan_speed").start().await?;
+        series
+            .consume_stream(futures::stream::iter([1000.0, 1200.0, 1100.0]))
+            .await?;
+        series.end().await?;
+
+        Ok(())
+    })
+    .await
+}
+
+#[tokio::test]
+async fn test_step_with_measurement_series_summary_stats() -> Result<()> {
+    let expected = [
+        json_schema_version(),
+        json_run_default_start(),
+        json_step_default_start(),
+        json!({
+            "testStepArtifact": {
+                "testStepId": "step0",
+                "measurementSeriesStart": {
+                    "measurementSeriesId": "step0_series0",
+                    "name": "fan_speed"
+                }
+            },
+            "sequenceNumber": 3,
+            "timestamp": DATETIME_FORMATTED
+        }),
+        json!({
+            "testStepArtifact": {
+                "testStepId": "step0",
+                "measurementSeriesElement": {
+                    "index": 0,
+                    "measurementSeriesId": "step0_series0",
+                    "value": 10,
+                    "timestamp": DATETIME_FORMATTED
+                }
+            },
+            "sequenceNumber": 4,
+            "timestamp": DATETIME_FORMATTED
+        }),
+        json!({
+            "testStepArtifact": {
+                "testStepId": "step0",
+                "measurementSeriesElement": {
+                    "index": 1,
+                    "measurementSeriesId": "step0_series0",
+                    "value": 20,
+                    "timestamp": DATETIME_FORMATTED
+                }
+            },
+            "sequenceNumber": 5,
+            "timestamp": DATETIME_FORMATTED
+        }),
+        json!({
+            "testStepArtifact": {
+                "testStepId": "step0",
+                "measurementSeriesElement": {
+                    "index": 2,
+                    "measurementSeriesId": "step0_series0",
+                    "value": 30,
+                    "timestamp": DATETIME_FORMATTED
+                }
+            },
+            "sequenceNumber": 6,
+            "timestamp": DATETIME_FORMATTED
+        }),
+        json!({
+            "testStepArtifact": {
+                "testStepId": "step0",
+                "measurementSeriesEnd": {
+                    "measurementSeriesId": "step0_series0",
+                    "totalCount": 3
+                }
+            },
+            "sequenceNumber": 7,
+            "timestamp": DATETIME_FORMATTED
+        }),
+        json!({
+            "testStepArtifact": {
+                "testStepId": "step0",
+                "measurement": {
+                    "name": "fan_speed_summary",
+                    "value": 20.0,
+                    "metadata": {
+                        "measurementSeriesId": "step0_series0",
+                        "count": 3,
+                        "min": 10,
+                        "max": 30,
+                        "mean": 20.0
+                    }
+                }
+            },
+            "sequenceNumber": 8,
+            "timestamp": DATETIME_FORMATTED
+        }),
+        json_step_complete(9),
+        json_run_pass(10),
+    ];
+
+    check_output_step(&expected, |s, _| async move {
+        let detail = MeasurementSeriesDetail::builder("fan_speed")
+            .with_summary_stats()
+            .build();
+        let series = s.add_measurement_series_detail(detail).start().await?;
+        series.add_measurement(10).await?;
+        series.add_measurement(20).await?;
+        series.add_measurement(30).await?;
+        series.end().await?;
+
+        Ok(())
+    })
+    .await
+}
+
+#[tokio::test]
+async fn test_step_with_measurement_series_require_elements_empty() -> Result<()> {
+    let expected = [
+        json_schema_version(),
+        json_run_default_start(),
+        json_step_default_start(),
+        json!({
+            "testStepArtifact": {
+                "testStepId": "step0",
+                "measurementSeriesStart": {
+                    "measurementSeriesId": "step0_series0",
+                    "name": "fan_speed"
+                }
+            },
+            "sequenceNumber": 3,
+            "timestamp": DATETIME_FORMATTED
+        }),
+        json!({
+            "testStepArtifact": {
+                "testStepId": "step0",
+                "measurementSeriesEnd": {
+                    "measurementSeriesId": "step0_series0",
+                    "totalCount": 0
+                }
+            },
+            "sequenceNumber": 4,
+            "timestamp": DATETIME_FORMATTED
+        }),
+        json!({
+            "testStepArtifact": {
+                "testStepId": "step0",
+                "log": {
+                    "severity": "WARNING",
+                    "message": "measurement series 'fan_speed' (step0_series0) ended without elements"
+                }
+            },
+            "sequenceNumber": 5,
+            "timestamp": DATETIME_FORMATTED
+        }),
+        json_step_complete(6),
+        json_run_pass(7),
+    ];
+
+    check_output_step(&expected, |s, _| async move {
+        let detail = MeasurementSeriesDetail::builder("fan_speed")
+            .require_elements()
+            .build();
+        let series = s.add_measurement_series_detail(detail).start().await?;
+        series.end().await?;
+
+        Ok(())
+    })
+    .await
+}
+
+#[tokio::test]
+async fn test_step_with_measurement_series_summary_stats_non_numeric() -> Result<()> {
+    let expected = [
+        json_schema_version(),
+        json_run_default_start(),
+        json_step_default_start(),
+        json!({
+            "testStepArtifact": {
+                "testStepId": "step0",
+                "measurementSeriesStart": {
+                    "measurementSeriesId": "step0_series0",
+                    "name": "fan_speed"
+                }
+            },
+            "sequenceNumber": 3,
+            "timestamp": DATETIME_FORMATTED
+        }),
+        json!({
+            "testStepArtifact": {
+                "testStepId": "step0",
+                "measurementSeriesElement": {
+                    "index": 0,
+                    "measurementSeriesId": "step0_series0",
+                    "value": 10,
+                    "timestamp": DATETIME_FORMATTED
+                }
+            },
+            "sequenceNumber": 4,
+            "timestamp": DATETIME_FORMATTED
+        }),
+        json!({
+            "testStepArtifact": {
+                "testStepId": "step0",
+                "measurementSeriesElement": {
+                    "index": 1,
+                    "measurementSeriesId": "step0_series0",
+                    "value": "n/a",
+                    "timestamp": DATETIME_FORMATTED
+                }
+            },
+            "sequenceNumber": 5,
+            "timestamp": DATETIME_FORMATTED
+        }),
+        json!({
+            "testStepArtifact": {
+                "testStepId": "step0",
+                "measurementSeriesEnd": {
+                    "measurementSeriesId": "step0_series0",
+                    "totalCount": 2
+                }
+            },
+            "sequenceNumber": 6,
+            "timestamp": DATETIME_FORMATTED
+        }),
+        json_step_complete(7),
+        json_run_pass(8),
+    ];
+
+    check_output_step(&expected, |s, _| async move {
+        let detail = MeasurementSeriesDetail::builder("fan_speed")
+            .with_summary_stats()
+            .build();
+        let series = s.add_measurement_series_detail(detail).start().await?;
+        series.add_measurement(10).await?;
+        series.add_measurement("n/a").await?;
+        series.end().await?;
+
+        Ok(())
+    })
+    .await
+}
+
+#[tokio::test]
+async fn test_step_with_measurement_group() -> Result<()> {
+    let expected = [
+        json_schema_version(),
+        json_run_default_start(),
+        json_step_default_start(),
+        json!({
+            "testStepArtifact": {
+                "testStepId": "step0",
+                "measurement": {
+                    "name": "rail_12v",
+                    "value": 12.1,
+                    "metadata": {
+                        "group": "voltage_rails"
+                    }
+                }
+            },
+            "sequenceNumber": 3,
+            "timestamp": DATETIME_FORMATTED
+        }),
+        json!({
+            "testStepArtifact": {
+                "testStepId": "step0",
+                "measurement": {
+                    "name": "rail_5v",
+                    "value": 5.02,
+                    "unit": "V",
+                    "metadata": {
+                        "group": "voltage_rails"
+                    }
+                }
+            },
+            "sequenceNumber": 4,
+            "timestamp": DATETIME_FORMATTED
+        }),
+        json!({
+            "testStepArtifact": {
+                "testStepId": "step0",
+                "measurement": {
+                    "name": "fan_speed",
+                    "value": 1600,
+                    "metadata": {
+                        "group": "fans"
+                    }
+                }
+            },
+            "sequenceNumber": 5,
+            "timestamp": DATETIME_FORMATTED
+        }),
+        json!({
+            "testStepArtifact": {
+                "testStepId": "step0",
+                "measurement": {
+                    "name": "temperature",
+                    "value": 40
+                }
+            },
+            "sequenceNumber": 6,
+            "timestamp": DATETIME_FORMATTED
+        }),
+        json_step_complete(7),
+        json_run_pass(8),
+    ];
+
+    check_output_step(&expected, |s, _| async move {
+        let rails = s.measurement_group("voltage_rails");
+        rails.add_measurement("rail_12v", 12.1).await?;
+        rails
+            .add_measurement_detail(Measurement::builder("rail_5v", 5.02).unit("V").build())
+            .await?;
+
+        s.add_measurement_detail(
+            Measurement::builder("fan_speed", 1600)
+                .group("fans")
+                .build(),
+        )
+        .await?;
+        s.add_measurement("temperature", 40).await?;
+
+        Ok(())
+    })
+    .await
+}
+
+#[tokio::test]
+async fn test_step_with_measurement_series_min_interval() -> Result<()> {
+    use std::sync::atomic::{AtomicI64, Ordering};
+    use std::time::Duration;
+
+    use ocptv::output::{CoalesceMode, TimestampProvider};
+
+    // clock that only moves when the test sets it, in millis since the epoch
+    struct ManualTsProvider {
+        millis: Arc<AtomicI64>,
+    }
+
+    impl TimestampProvider for ManualTsProvider {
+        fn now(&self) -> chrono::DateTime<chrono_tz::Tz> {
+            let millis = self.millis.load(Ordering::Acquire);
+            (DATETIME + chrono::Duration::milliseconds(millis)).with_timezone(&chrono_tz::UTC)
+        }
+    }
+
+    async fn elements(mode: CoalesceMode) -> Result<(Vec<serde_json::Value>, serde_json::Value)> {
+        let millis = Arc::new(AtomicI64::new(0));
+        let clock = ManualTsProvider {
+            millis: Arc::clone(&millis),
+        };
+        let artifacts = collect_output(
+            |config| config.with_timestamp_provider(Box::new(clock)),
+            |run_builder, dut| async move {
+                let run = run_builder.build().start(dut).await?;
+                let step = run.add_step("step_name").start().await?;
+                let detail = MeasurementSeriesDetail::builder("fan_speed")
+                    .min_interval(Duration::from_secs(1))
+                    .coalesce_mode(mode)
+                    .build();
+                let series = step.add_measurement_series_detail(detail).start().await?;
+                for (at, value) in [(0, 1), (300, 2), (900, 3), (1000, 4), (1500, 5), (2500, 6)] {
+                    millis.store(at, Ordering::Release);
+                    series.add_measurement(value).await?;
+                }
+                series.end().await?;
+                step.end(TestStatus::Complete).await?;
+                run.end(TestStatus::Complete, TestResult::Pass).await?;
+                Ok(())
+            },
+        )
+        .await?;
+
+        let mut elements = vec![];
+        let mut total_count = serde_json::Value::Null;
+        for value in artifacts {
+            if let Some(element) = value.pointer("/testStepArtifact/measurementSeriesElement") {
+                elements.push(json!([element["value"], element["timestamp"]]));
+            }
+            if let Some(count) = value.pointer("/testStepArtifact/measurementSeriesEnd/totalCount")
+            {
+                total_count = count.clone();
+            }
+        }
+        Ok((elements, total_count))
+    }
+
+    // intervals start at 0ms, 1000ms and 2500ms
+    let (latest, total_count) = elements(CoalesceMode::Latest).await?;
+    assert_eq!(
+        latest,
+        [
+            json!([3, "1970-01-01T00:00:00.900Z"]),
+            json!([5, "1970-01-01T00:00:01.500Z"]),
+            json!([6, "1970-01-01T00:00:02.500Z"]),
+        ]
+    );
+    assert_eq!(total_count, 3);
+
+    let (average, total_count) = elements(CoalesceMode::Average).await?;
+    assert_eq!(
+        average,
+        [
+            json!([2.0, "1970-01-01T00:00:00.900Z"]),
+            json!([4.5, "1970-01-01T00:00:01.500Z"]),
+            json!([6.0, "1970-01-01T00:00:02.500Z"]),
+        ]
+    );
+    assert_eq!(total_count, 3);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_step_with_measurement_series_summary_stats_coalesced() -> Result<()> {
+    use std::time::Duration;
+
+    let emitted = collect_output(
+        |config| config,
+        |run_builder, dut| async move {
+            let run = run_builder.build().start(dut).await?;
+            let step = run.add_step("step_name").start().await?;
+            let detail = MeasurementSeriesDetail::builder("fan_speed")
+                .min_interval(Duration::from_secs(1))
+                .with_summary_stats()
+                .build();
+            let series = step.add_measurement_series_detail(detail).start().await?;
+            for (at, value) in [(0, 1), (300, 2), (1000, 10)] {
+                let timestamp =
+                    (DATETIME + chrono::Duration::milliseconds(at)).with_timezone(&chrono_tz::UTC);
+                series
+                    .add_measurement_detail(
+                        MeasurementElementDetail::builder(value)
+                            .timestamp(timestamp)
+                            .build(),
+                    )
+                    .await?;
+            }
+            series.end().await?;
+            step.end(TestStatus::Complete).await?;
+            run.end(TestStatus::Complete, TestResult::Pass).await?;
+            Ok(())
+        },
+    )
+    .await?;
+
+    // the first element is coalesced away, so it's not part of the summary
+    let summary = emitted
+        .iter()
+        .find_map(|value| value.pointer("/testStepArtifact/measurement/metadata"))
+        .unwrap();
+    assert_eq!(summary["count"], 2);
+    assert_eq!(summary["min"], 2);
+    assert_eq!(summary["max"], 10);
+    assert_eq!(summary["mean"], 6.0);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_step_with_measurement_series_validated_elements() -> Result<()> {
+    let expected = [
+        json_schema_version(),
+        json_run_default_start(),
+        json_step_default_start(),
+        json!({
+            "testStepArtifact": {
+                "testStepId": "step0",
+                "measurementSeriesStart": {
+                    "measurementSeriesId": "step0_series0",
+                    "name": "temperature",
+                    "validators": [{
+                        "type": "LESS_THAN",
+                        "value": 80
+                    }]
+                }
+            },
+            "sequenceNumber": 3,
+            "timestamp": DATETIME_FORMATTED
+        }),
+        json!({
+            "testStepArtifact": {
+                "testStepId": "step0",
+                "measurementSeriesElement": {
+                    "index": 0,
+                    "measurementSeriesId": "step0_series0",
+                    "value": 60,
+                    "timestamp": DATETIME_FORMATTED,
+                    "metadata": {
+                        "passed": true
+                    }
+                }
+            },
+            "sequenceNumber": 4,
+            "timestamp": DATETIME_FORMATTED
+        }),
+        json!({
+            "testStepArtifact": {
+                "testStepId": "step0",
+                "measurementSeriesElement": {
+                    "index": 1,
+                    "measurementSeriesId": "step0_series0",
+                    "value": 95,
+                    "timestamp": DATETIME_FORMATTED,
+                    "metadata": {
+                        "key": "value",
+                        "passed": false
+                    }
+                }
+            },
+            "sequenceNumber": 5,
+            "timestamp": DATETIME_FORMATTED
+        }),
+        json!({
+            "testStepArtifact": {
+                "testStepId": "step0",
+                "measurementSeriesElement": {
+                    "index": 2,
+                    "measurementSeriesId": "step0_series0",
+                    "value": 70,
+                    "timestamp": DATETIME_FORMATTED,
+                    "metadata": {
+                        "passed": true
+                    }
+                }
+            },
+            "sequenceNumber": 6,
+            "timestamp": DATETIME_FORMATTED
+        }),
+        json!({
+            "testStepArtifact": {
+                "testStepId": "step0",
+                "measurementSeriesEnd": {
+                    "measurementSeriesId": "step0_series0",
+                    "totalCount": 3
+                }
+            },
+            "sequenceNumber": 7,
+            "timestamp": DATETIME_FORMATTED
+        }),
+        json_step_complete(8),
+        json_run_pass(9),
+    ];
+
+    check_output_step(&expected, |s, _| async move {
+        let detail = MeasurementSeriesDetail::builder("temperature")
+            .add_validator(Validator::builder(ValidatorType::LessThan, 80).build())
+            .build();
+        let series = s.add_measurement_series_detail(detail).start().await?;
+
+        series.add_measurement(60).await?;
+        assert!(series.all_elements_passed());
+        series
+            .add_measurement_detail(
+                MeasurementElementDetail::builder(95)
+                    .add_metadata("key", "value")
+                    .build(),
+            )
+            .await?;
+        assert!(!series.all_elements_passed());
+        series.add_measurement(70).await?;
+        assert!(!series.all_elements_passed());
+        series.end().await?;
+
+        Ok(())
+    })
+    .await
+}
+
+#[tokio::test]
+async fn test_run_measurement_series_across_steps() -> Result<()> {
+    let series_artifact = |step: &str, seqno: i32, artifact: serde_json::Value| {
+        let mut value = json!({
+            "testStepArtifact": {
+                "testStepId": step
+            },
+            "sequenceNumber": seqno,
+            "timestamp": DATETIME_FORMATTED
+        });
+        value["testStepArtifact"]
+            .as_object_mut()
+            .unwrap()
+            .extend(artifact.as_object().unwrap().clone());
+        value
+    };
+    let element = |step: &str, seqno: i32, index: u64, value: i32| {
+        series_artifact(
+            step,
+            seqno,
+            json!({
+                "measurementSeriesElement": {
+                    "index": index,
+                    "measurementSeriesId": "run_series0",
+                    "value": value,
+                    "timestamp": DATETIME_FORMATTED
+                }
+            }),
+        )
+    };
+    let start = |step: &str, seqno: i32| {
+        series_artifact(
+            step,
+            seqno,
+            json!({
+                "measurementSeriesStart": {
+                    "measurementSeriesId": "run_series0",
+                    "name": "temperature"
+                }
+            }),
+        )
+    };
+    let end = |step: &str, seqno: i32, total_count: u64| {
+        series_artifact(
+            step,
+            seqno,
+            json!({
+                "measurementSeriesEnd": {
+                    "measurementSeriesId": "run_series0",
+                    "totalCount": total_count
+                }
+            }),
+        )
+    };
+
+    let expected = [
+        json_schema_version(),
+        json_run_default_start(),
+        json_step_default_start(),
+        start("step0", 3),
+        element("step0", 4, 0, 40),
+        element("step0", 5, 1, 45),
+        end("step0", 6, 2),
+        json_step_complete(7),
+        series_artifact(
+            "step1",
+            8,
+            json!({"testStepStart": {"name": "second step"}}),
+        ),
+        start("step1", 9),
+        element("step1", 10, 2, 75),
+        end("step1", 11, 3),
+        series_artifact("step1", 12, json!({"testStepEnd": {"status": "COMPLETE"}})),
+        json_run_pass(13),
+    ];
+
+    check_output_run(&expected, |r, _| async move {
+        let temperature = r.add_measurement_series("temperature");
+        assert_eq!(temperature.id(), "run_series0");
+
+        let step = r.add_step("first step").start().await?;
+        let series = step
+            .add_run_measurement_series(&temperature)
+            .start()
+            .await?;
+        series.add_measurement(40).await?;
+        series.add_measurement(45).await?;
+        series.end().await?;
+        step.end(TestStatus::Complete).await?;
+
+        let step = r.add_step("second step").start().await?;
+        let series = step
+            .add_run_measurement_series(&temperature)
+            .start()
+            .await?;
+        series.add_measurement(75).await?;
+        series.end().await?;
+        step.end(TestStatus::Complete).await?;
+
+        Ok(())
+    })
+    .await
+}
@@ -11,7 +11,13 @@ use assert_json_diff::assert_json_include;
 use serde_json::json;
 use tokio::sync::Mutex;
 
-use ocptv::output::{DutInfo, TestResult, TestRun, TestStatus};
+use ocptv::output::{
+    Config, DiagnosisType, DutInfo, ExtensionRegistry, Ident, LogSeverity, OcptvError,
+    PlatformInfo, Provenance, RunStats, SoftwareInfo, SoftwareType, TestResult, TestRun,
+    TestStatus,
+};
+#[cfg(feature = "run-guard")]
+use ocptv::output::{ABANDONED_RUN_SYMPTOM, FAILED_RUN_SYMPTOM};
 
 use super::fixture::*;
 
@@ -64,6 +70,25 @@ async fn test_testrun_with_scope() -> Result<()> {
     .await
 }
 
+#[tokio::test]
+async fn test_testrun_with_late_platform_info() -> Result<()> {
+    let mut run_start = json_run_default_start();
+    run_start["testRunArtifact"]["testRunStart"]["dutInfo"]["platformInfos"] =
+        json!([{ "info": "bios_version=1.2.3" }]);
+
+    let expected = [json_schema_version(), run_start, json_run_pass(2)];
+
+    check_output(&expected, |run_builder, mut dut| async move {
+        dut.add_platform_info(PlatformInfo::new("bios_version=1.2.3"));
+
+        let run = run_builder.build().start(dut).await?;
+        run.end(TestStatus::Complete, TestResult::Pass).await?;
+
+        Ok(())
+    })
+    .await
+}
+
 #[tokio::test]
 async fn test_testrun_instantiation_with_new() -> Result<()> {
     let expected = [
@@ -133,6 +158,96 @@ async fn test_testrun_metadata() -> Result<()> {
     .await
 }
 
+#[tokio::test]
+async fn test_testrun_provenance() -> Result<()> {
+    let expected = [
+        json_schema_version(),
+        json!({
+            "testRunArtifact": {
+                "testRunStart": {
+                    "dutInfo": {
+                        "dutInfoId": "dut_id",
+                        "softwareInfos": [{
+                            "softwareInfoId": "sw0",
+                            "name": "ubuntu",
+                            "version": "22",
+                            "softwareType": "SYSTEM",
+                        }],
+                        "hardwareInfos": [{
+                            "hardwareInfoId": "hw0",
+                            "name": "fan",
+                            "location": "board0/fan"
+                        }]
+                    },
+                    "metadata": {
+                        "key": "value",
+                        "provenance": {
+                            "gitSha": "0123abcd",
+                            "gitDirty": false,
+                            "buildTime": "2024-05-01T12:00:00Z",
+                        }
+                    },
+                    "name": "run_name",
+                    "parameters": {},
+                    "version": "1.0",
+
+                    "commandLine": "",
+                }
+            },
+            "sequenceNumber": 1,
+            "timestamp": DATETIME_FORMATTED
+        }),
+        json_run_pass(2),
+    ];
+
+    check_output(&expected, |run_builder, dut| async {
+        let run = run_builder
+            .add_metadata("key", "value")
+            .provenance(Provenance {
+                git_sha: Some("0123abcd".to_owned()),
+                git_dirty: Some(false),
+                build_time: Some("2024-05-01T12:00:00Z".to_owned()),
+                ..Default::default()
+            })
+            .build()
+            .start(dut)
+            .await?;
+
+        run.end(TestStatus::Complete, TestResult::Pass).await?;
+        Ok(())
+    })
+    .await
+}
+
+#[tokio::test]
+async fn test_testrun_parameters_and_metadata_accessors() -> Result<()> {
+    let builder = TestRun::builder("run_name", "1.0")
+        .add_parameter("param1", "value1")
+        .add_parameter("param2", 42)
+        .add_metadata("key", "value");
+    assert_eq!(builder.parameters().len(), 2);
+    assert_eq!(builder.parameters()["param1"], "value1");
+    assert_eq!(builder.metadata()["key"], "value");
+
+    let run = builder.build();
+    assert_eq!(run.parameters()["param2"], 42);
+    assert_eq!(run.metadata()["key"], "value");
+
+    let dut = DutInfo::builder("dut_id").build();
+    let run = run.start(dut).await?;
+    assert_eq!(
+        run.parameters(),
+        &serde_json::Map::from_iter([
+            ("param1".to_owned(), json!("value1")),
+            ("param2".to_owned(), json!(42)),
+        ])
+    );
+    assert_eq!(run.metadata().len(), 1);
+    run.end(TestStatus::Complete, TestResult::Pass).await?;
+
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_testrun_builder() -> Result<()> {
     let expected = [
@@ -187,3 +302,1087 @@ async fn test_testrun_builder() -> Result<()> {
     })
     .await
 }
+
+#[tokio::test]
+async fn test_testrun_builder_with_checked_parameters() -> Result<()> {
+    let expected = [
+        json_schema_version(),
+        json!({
+            "testRunArtifact": {
+                "testRunStart": {
+                    "commandLine": "",
+                    "dutInfo": {
+                        "dutInfoId": "dut_id",
+                        "softwareInfos": [{
+                            "softwareInfoId": "sw0",
+                            "name": "ubuntu",
+                            "version": "22",
+                            "softwareType": "SYSTEM",
+                        }],
+                        "hardwareInfos": [{
+                            "hardwareInfoId": "hw0",
+                            "name": "fan",
+                            "location": "board0/fan"
+                        }]
+                    },
+                    "name": "run_name",
+                    "parameters": {
+                        "string": "value",
+                        "number": 4.5,
+                        "bool": true,
+                        "null": null
+                    },
+                    "version": "1.0"
+                }
+            },
+            "sequenceNumber": 1,
+            "timestamp": DATETIME_FORMATTED
+        }),
+        json_run_pass(2),
+    ];
+
+    check_output(&expected, |run_builder, dut| async {
+        let run = run_builder
+            .command_line("")
+            .add_parameter_checked("string", "value")?
+            .add_parameter_checked("number", 4.5)?
+            .add_parameter_checked("bool", true)?
+            .add_parameter_checked("null", serde_json::Value::Null)?
+            .build()
+            .start(dut)
+            .await?;
+
+        run.end(TestStatus::Complete, TestResult::Pass).await?;
+        Ok(())
+    })
+    .await
+}
+
+#[tokio::test]
+async fn test_testrun_builder_with_env_parameters() -> Result<()> {
+    let expected = [
+        json_schema_version(),
+        json!({
+            "testRunArtifact": {
+                "testRunStart": {
+                    "commandLine": "",
+                    "dutInfo": {
+                        "dutInfoId": "dut_id",
+                        "softwareInfos": [{
+                            "softwareInfoId": "sw0",
+                            "name": "ubuntu",
+                            "version": "22",
+                            "softwareType": "SYSTEM",
+                        }],
+                        "hardwareInfos": [{
+                            "hardwareInfoId": "hw0",
+                            "name": "fan",
+                            "location": "board0/fan"
+                        }]
+                    },
+                    "name": "run_name",
+                    "parameters": {
+                        "JOB_ID": "1234",
+                        "PIPELINE": "nightly"
+                    },
+                    "version": "1.0"
+                }
+            },
+            "sequenceNumber": 1,
+            "timestamp": DATETIME_FORMATTED
+        }),
+        json_run_pass(2),
+    ];
+
+    // prefix is unique to this test, since the environment is shared by the test threads
+    std::env::set_var("OCPTV_ENV_PARAMS_TEST_JOB_ID", "1234");
+    std::env::set_var("OCPTV_ENV_PARAMS_TEST_PIPELINE", "nightly");
+
+    check_output(&expected, |run_builder, dut| async {
+        let run = run_builder
+            .command_line("")
+            .add_parameters_from_env("OCPTV_ENV_PARAMS_TEST_")
+            .build()
+            .start(dut)
+            .await?;
+
+        run.end(TestStatus::Complete, TestResult::Pass).await?;
+        Ok(())
+    })
+    .await
+}
+
+#[test]
+fn test_testrun_builder_rejects_nested_parameters() {
+    let actual = TestRun::builder("run_name", "1.0").add_parameter_checked("list", vec![1, 2]);
+    assert!(matches!(actual, Err(OcptvError::Other(_))));
+
+    let actual =
+        TestRun::builder("run_name", "1.0").add_parameter_checked("map", json!({"key": "value"}));
+    assert!(matches!(actual, Err(OcptvError::Other(_))));
+}
+
+#[tokio::test]
+async fn test_testrun_with_raw_artifact() -> Result<()> {
+    let expected = [
+        json_schema_version(),
+        json_run_default_start(),
+        json!({
+            "testRunArtifact": {
+                "log": {
+                    "message": "first",
+                    "severity": "INFO"
+                }
+            },
+            "sequenceNumber": 2,
+            "timestamp": DATETIME_FORMATTED
+        }),
+        json!({
+            "testRunArtifact": {
+                "log": {
+                    "message": "raw",
+                    "severity": "DEBUG"
+                }
+            },
+            "sequenceNumber": 3,
+            "timestamp": DATETIME_FORMATTED
+        }),
+        json!({
+            "testRunArtifact": {
+                "log": {
+                    "message": "second",
+                    "severity": "INFO"
+                }
+            },
+            "sequenceNumber": 4,
+            "timestamp": DATETIME_FORMATTED
+        }),
+        json_run_pass(5),
+    ];
+
+    check_output_run(&expected, |r, _| async move {
+        r.add_log(LogSeverity::Info, "first").await?;
+        r.emit_raw(json!({
+            "testRunArtifact": {
+                "log": {
+                    "message": "raw",
+                    "severity": "DEBUG"
+                }
+            },
+            // stale values from the producing library are replaced
+            "sequenceNumber": 42,
+            "timestamp": "2000-01-01T00:00:00.000Z"
+        }))
+        .await?;
+        r.add_log(LogSeverity::Info, "second").await?;
+
+        Ok(())
+    })
+    .await
+}
+
+#[tokio::test]
+async fn test_testrun_with_raw_artifact_not_object() -> Result<()> {
+    let expected = [
+        json_schema_version(),
+        json_run_default_start(),
+        json_run_pass(2),
+    ];
+
+    check_output_run(&expected, |r, _| async move {
+        let result = r.emit_raw(json!(["not", "an", "object"])).await;
+        assert!(matches!(result, Err(OcptvError::Format(_))));
+
+        Ok(())
+    })
+    .await
+}
+
+#[tokio::test]
+async fn test_testrun_with_raw_artifact_checked_by_config() -> Result<()> {
+    let expected = [
+        json_schema_version(),
+        json_run_default_start(),
+        json!({
+            "testRunArtifact": {
+                "error": {
+                    "symptom": "fan_stuck"
+                }
+            },
+            "sequenceNumber": 2,
+            "timestamp": DATETIME_FORMATTED
+        }),
+        json_run_pass(3),
+    ];
+
+    let pattern = regex::Regex::new(r"^[a-z][a-z0-9]*(_[a-z0-9]+)*$")?;
+    check_output_config(
+        &expected,
+        |config| {
+            config
+                .min_severity(LogSeverity::Info)
+                .symptom_pattern(pattern)
+                .strict_extensions(ExtensionRegistry::new().register("FanCurve/v2"))
+        },
+        |run_builder, dut| async move {
+            let run = run_builder.build().start(dut).await?;
+
+            // filtered out by the min severity
+            run.emit_raw(json!({
+                "testRunArtifact": {
+                    "log": { "message": "raw", "severity": "DEBUG" }
+                }
+            }))
+            .await?;
+
+            let result = run
+                .emit_raw(json!({
+                    "testRunArtifact": { "error": { "symptom": "fan-stuck" } }
+                }))
+                .await;
+            assert!(matches!(result, Err(OcptvError::IoError(_))));
+            run.emit_raw(json!({
+                "testRunArtifact": { "error": { "symptom": "fan_stuck" } }
+            }))
+            .await?;
+
+            let result = run
+                .emit_raw(json!({
+                    "testStepArtifact": {
+                        "testStepId": "step0",
+                        "extension": {
+                            "name": "fan_curve",
+                            "content": { "@type": "FanCurve/v1" }
+                        }
+                    }
+                }))
+                .await;
+            assert!(matches!(result, Err(OcptvError::IoError(_))));
+
+            run.end(TestStatus::Complete, TestResult::Pass).await?;
+            Ok(())
+        },
+    )
+    .await
+}
+
+#[tokio::test]
+async fn test_testrun_dut_snapshot_before_start() -> Result<()> {
+    let mut start = json_run_default_start();
+    start["sequenceNumber"] = 2.into();
+    let dut_info = start["testRunArtifact"]["testRunStart"]["dutInfo"].clone();
+
+    let expected = [
+        json_schema_version(),
+        json!({
+            "testRunArtifact": {
+                "log": {
+                    "message": json!({ "dutInfo": dut_info }).to_string(),
+                    "severity": "INFO"
+                }
+            },
+            "sequenceNumber": 1,
+            "timestamp": DATETIME_FORMATTED
+        }),
+        start,
+        json_run_pass(3),
+    ];
+
+    check_output(&expected, |run_builder, dut| async move {
+        let run = run_builder.build();
+        run.emit_dut_snapshot(&dut).await?;
+
+        let run = run.start(dut).await?;
+        run.end(TestStatus::Complete, TestResult::Pass).await?;
+        Ok(())
+    })
+    .await
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_testrun_spawn_steps() -> Result<()> {
+    use ocptv::output::SpawnedStep;
+
+    let buffer = Arc::new(Mutex::new(Vec::<u8>::new()));
+    let dut = DutInfo::builder("dut_id").build();
+    let run = TestRun::builder("run_name", "1.0")
+        .config(
+            Config::builder()
+                .with_bytes_buffer_output(Arc::clone(&buffer))
+                .build(),
+        )
+        .build()
+        .start(dut)
+        .await?;
+
+    let steps = ["first", "second"]
+        .map(|name| {
+            SpawnedStep::new(name, move |s| async move {
+                for i in 0..10 {
+                    s.add_measurement(name, i).await?;
+                }
+                Ok(TestStatus::Complete)
+            })
+        })
+        .into_iter()
+        .collect();
+    run.spawn_steps(steps).await?;
+    run.end(TestStatus::Complete, TestResult::Pass).await?;
+
+    let output = String::from_utf8(buffer.lock().await.clone())?;
+    let artifacts = output
+        .lines()
+        .map(serde_json::from_str::<serde_json::Value>)
+        .collect::<Result<Vec<_>, _>>()?;
+    // version, run start and end, and for each step: start, end and 10 measurements
+    assert_eq!(artifacts.len(), 3 + 2 * 12);
+
+    for (i, artifact) in artifacts.iter().enumerate() {
+        assert_eq!(artifact["sequenceNumber"], i);
+    }
+    for id in ["step0", "step1"] {
+        let step_artifacts = artifacts
+            .iter()
+            .filter(|a| a["testStepArtifact"]["testStepId"] == id)
+            .collect::<Vec<_>>();
+        assert_eq!(step_artifacts.len(), 12);
+        assert!(step_artifacts[0]["testStepArtifact"]["testStepStart"].is_object());
+        assert_eq!(
+            step_artifacts[11]["testStepArtifact"]["testStepEnd"]["status"],
+            "COMPLETE"
+        );
+    }
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_testrun_spawn_steps_with_panicking_step() -> Result<()> {
+    use ocptv::output::{OcptvError, SpawnedStep};
+
+    let expected = [
+        json_schema_version(),
+        json_run_default_start(),
+        json_step_default_start(),
+        json!({
+            "testStepArtifact": {
+                "testStepId": "step0",
+                "testStepEnd": {
+                    "status": "ERROR"
+                }
+            },
+            "sequenceNumber": 3,
+            "timestamp": DATETIME_FORMATTED
+        }),
+        json!({
+            "testRunArtifact": {
+                "testRunEnd": {
+                    "result": "FAIL",
+                    "status": "COMPLETE"
+                }
+            },
+            "sequenceNumber": 4,
+            "timestamp": DATETIME_FORMATTED
+        }),
+    ];
+
+    check_output(&expected, |run_builder, dut| async {
+        let run = run_builder.build().start(dut).await?;
+
+        let result = run
+            .spawn_steps(vec![SpawnedStep::new("first step", |_| async move {
+                panic!("fan controller crashed");
+            })])
+            .await;
+        assert!(matches!(result, Err(OcptvError::Other(_))));
+
+        let result = run.result_from_steps();
+        run.end(TestStatus::Complete, result).await?;
+        Ok(())
+    })
+    .await
+}
+
+#[tokio::test]
+async fn test_testrun_end_from_ok_result() -> Result<()> {
+    let expected = [
+        json_schema_version(),
+        json_run_default_start(),
+        json_run_pass(2),
+    ];
+
+    check_output(&expected, |run_builder, dut| async {
+        let run = run_builder.build().start(dut).await?;
+
+        run.end_from_result(Ok::<(), String>(())).await?;
+        Ok(())
+    })
+    .await
+}
+
+#[tokio::test]
+async fn test_testrun_end_from_err_result() -> Result<()> {
+    let expected = [
+        json_schema_version(),
+        json_run_default_start(),
+        json!({
+            "testRunArtifact": {
+                "error": {
+                    "message": "fan-stuck",
+                    "symptom": "run_failed"
+                }
+            },
+            "sequenceNumber": 2,
+            "timestamp": DATETIME_FORMATTED
+        }),
+        json!({
+            "testRunArtifact": {
+                "testRunEnd": {
+                    "result": "FAIL",
+                    "status": "COMPLETE"
+                }
+            },
+            "sequenceNumber": 3,
+            "timestamp": DATETIME_FORMATTED
+        }),
+    ];
+
+    check_output(&expected, |run_builder, dut| async {
+        let run = run_builder.build().start(dut).await?;
+
+        run.end_from_result(Err("fan-stuck")).await?;
+        Ok(())
+    })
+    .await
+}
+
+#[tokio::test]
+async fn test_testrun_end_from_err_result_ends_if_error_fails() -> Result<()> {
+    let expected = [
+        json_schema_version(),
+        json_run_default_start(),
+        json!({
+            "testRunArtifact": {
+                "testRunEnd": {
+                    "result": "FAIL",
+                    "status": "COMPLETE"
+                }
+            },
+            "sequenceNumber": 2,
+            "timestamp": DATETIME_FORMATTED
+        }),
+    ];
+
+    // rejects the failed run symptom, so only the end is written
+    let pattern = regex::Regex::new(r"^[a-z]+$")?;
+    check_output_config(
+        &expected,
+        |config| config.symptom_pattern(pattern),
+        |run_builder, dut| async move {
+            let run = run_builder.build().start(dut).await?;
+
+            let result = run.end_from_result(Err("fan-stuck")).await;
+            assert!(result.is_err());
+            Ok(())
+        },
+    )
+    .await
+}
+
+#[tokio::test]
+async fn test_testrun_stats() -> Result<()> {
+    let mut stats = None;
+    let artifacts = collect_output(
+        |config| config,
+        |run_builder, dut| async {
+            let run = run_builder.build().start(dut).await?;
+            run.add_log(LogSeverity::Info, "log message").await?;
+            let step = run.add_step("first step").start().await?;
+            step.end(TestStatus::Complete).await?;
+
+            stats = Some(run.stats());
+            run.end(TestStatus::Complete, TestResult::Pass).await?;
+            Ok(())
+        },
+    )
+    .await?;
+
+    // schemaVersion, testRunStart, log, testStepStart, testStepEnd;
+    // the buffer output takes no line terminators
+    let expected_bytes = artifacts[..5]
+        .iter()
+        .map(|artifact| artifact.to_string().len() as u64)
+        .sum();
+    assert_eq!(
+        stats,
+        Some(RunStats {
+            artifacts: 5,
+            bytes: expected_bytes,
+            dropped: 0,
+        })
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_testrun_with_transform() -> Result<()> {
+    let mut run_start = json_run_default_start();
+    run_start["testRunArtifact"]["testRunStart"]["dutInfo"]["dutInfoId"] = json!("<redacted>");
+
+    let expected = [json_schema_version(), run_start, json_run_pass(2)];
+
+    check_output_config(
+        &expected,
+        |config| {
+            config.with_transform(Box::new(|value| {
+                if let Some(id) =
+                    value.pointer_mut("/testRunArtifact/testRunStart/dutInfo/dutInfoId")
+                {
+                    *id = json!("<redacted>");
+                }
+            }))
+        },
+        |run_builder, dut| async move {
+            let run = run_builder.build().start(dut).await?;
+            run.end(TestStatus::Complete, TestResult::Pass).await?;
+            Ok(())
+        },
+    )
+    .await
+}
+
+#[tokio::test]
+async fn test_testrun_with_summary() -> Result<()> {
+    let entries = collect_output(
+        |config| config.emit_summary(true),
+        |run_builder, dut| async move {
+            let run = run_builder.build().start(dut).await?;
+            run.add_error("run_symptom").await?;
+            let step = run.add_step("first step").start().await?;
+            step.add_diagnosis("ok", DiagnosisType::Pass).await?;
+            step.add_diagnosis("ok", DiagnosisType::Pass).await?;
+            step.add_diagnosis("bad", DiagnosisType::Fail).await?;
+            step.add_error("step_symptom").await?;
+            step.end(TestStatus::Complete).await?;
+            run.end(TestStatus::Complete, TestResult::Fail).await?;
+            Ok(())
+        },
+    )
+    .await?;
+
+    let count = |f: &dyn Fn(&serde_json::Value) -> bool| entries.iter().filter(|e| f(e)).count();
+    let diagnoses = |kind: &str| count(&|e| e["testStepArtifact"]["diagnosis"]["type"] == kind);
+    let errors = count(&|e| {
+        e["testRunArtifact"]["error"].is_object() || e["testStepArtifact"]["error"].is_object()
+    });
+
+    // the summary log comes right before testRunEnd
+    let [.., summary, end] = entries.as_slice() else {
+        panic!("missing summary");
+    };
+    assert!(end["testRunArtifact"]["testRunEnd"].is_object());
+    assert_eq!(summary["testRunArtifact"]["log"]["severity"], "INFO");
+
+    let message = summary["testRunArtifact"]["log"]["message"]
+        .as_str()
+        .expect("summary message is a string");
+    assert_eq!(
+        serde_json::from_str::<serde_json::Value>(message)?,
+        json!({
+            "diagnoses": {
+                "pass": diagnoses("PASS"),
+                "fail": diagnoses("FAIL"),
+                "unknown": diagnoses("UNKNOWN"),
+            },
+            "errors": errors,
+        })
+    );
+    assert_eq!(errors, 2);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_testrun_with_summary_and_min_severity() -> Result<()> {
+    let entries = collect_output(
+        |config| config.emit_summary(true).min_severity(LogSeverity::Warning),
+        |run_builder, dut| async move {
+            let run = run_builder.build().start(dut).await?;
+            run.add_log(LogSeverity::Info, "dropped").await?;
+            run.add_error("run_symptom").await?;
+            run.end(TestStatus::Complete, TestResult::Fail).await?;
+            Ok(())
+        },
+    )
+    .await?;
+
+    // the info log is dropped, but not the summary
+    let logs = entries
+        .iter()
+        .filter_map(|e| e.pointer("/testRunArtifact/log/message"))
+        .collect::<Vec<_>>();
+    assert_eq!(logs.len(), 1);
+    let summary: serde_json::Value = serde_json::from_str(logs[0].as_str().unwrap())?;
+    assert_eq!(summary["errors"], 1);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_testrun_start_with_reloaded_dut() -> Result<()> {
+    async fn run_start(dut: DutInfo) -> Result<serde_json::Value> {
+        let artifacts = collect_output(
+            |config| config,
+            |run_builder, _| async move {
+                let run = run_builder.build().start(dut).await?;
+                run.end(TestStatus::Complete, TestResult::Pass).await?;
+                Ok(())
+            },
+        )
+        .await?;
+
+        Ok(artifacts[1].clone())
+    }
+
+    let mut dut = DutInfo::builder("dut_id")
+        .name("dut_name")
+        .add_platform_info(PlatformInfo::new("platform"))
+        .with_empty_hardware_infos()
+        .add_metadata("key", "value")
+        .build();
+    dut.add_software_info(
+        SoftwareInfo::builder("bmc")
+            .version("1.0")
+            .software_type(SoftwareType::Firmware)
+            .build(),
+    );
+
+    let original = run_start(dut).await?;
+    let snapshot = original["testRunArtifact"]["testRunStart"]["dutInfo"].to_string();
+
+    let reloaded = DutInfo::from_json(serde_json::from_str(&snapshot)?)?;
+    assert_eq!(run_start(reloaded).await?, original);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_testrun_start_with_dangling_computer_system() -> Result<()> {
+    let mut dut = DutInfo::builder("dut_id")
+        .add_computer_system("host0")
+        .build();
+    dut.add_software_info(
+        SoftwareInfo::builder("bmc")
+            .id(Ident::Exact("sw0".to_owned()))
+            .computer_system("host1")
+            .build(),
+    );
+
+    let artifacts = collect_output(
+        |config| config,
+        |run_builder, _| async move {
+            let run = run_builder.build().start(dut).await?;
+            run.end(TestStatus::Complete, TestResult::Pass).await?;
+            Ok(())
+        },
+    )
+    .await?;
+
+    assert_eq!(
+        artifacts[2],
+        json!({
+            "testRunArtifact": {
+                "log": {
+                    "severity": "WARNING",
+                    "message": "software info 'sw0' references undeclared computer system 'host1'"
+                }
+            },
+            "sequenceNumber": 2,
+            "timestamp": DATETIME_FORMATTED
+        })
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_testrun_result_from_steps() -> Result<()> {
+    let expected = [
+        json_schema_version(),
+        json_run_default_start(),
+        json_step_default_start(),
+        json_step_complete(3),
+        json!({
+            "testStepArtifact": {
+                "testStepId": "step1",
+                "testStepStart": {
+                    "name": "second step"
+                }
+            },
+            "sequenceNumber": 4,
+            "timestamp": DATETIME_FORMATTED
+        }),
+        json!({
+            "testStepArtifact": {
+                "testStepId": "step1",
+                "testStepEnd": {
+                    "status": "ERROR"
+                }
+            },
+            "sequenceNumber": 5,
+            "timestamp": DATETIME_FORMATTED
+        }),
+        json!({
+            "testRunArtifact": {
+                "testRunEnd": {
+                    "result": "FAIL",
+                    "status": "COMPLETE"
+                }
+            },
+            "sequenceNumber": 6,
+            "timestamp": DATETIME_FORMATTED
+        }),
+    ];
+
+    check_output(&expected, |run_builder, dut| async {
+        let run = run_builder.build().start(dut).await?;
+        assert_eq!(run.result_from_steps(), TestResult::Pass);
+
+        let step = run.add_step("first step").start().await?;
+        step.end(TestStatus::Complete).await?;
+        assert_eq!(run.result_from_steps(), TestResult::Pass);
+
+        let step = run.add_step("second step").start().await?;
+        step.end(TestStatus::Error).await?;
+
+        let result = run.result_from_steps();
+        assert_eq!(result, TestResult::Fail);
+        run.end(TestStatus::Complete, result).await?;
+
+        Ok(())
+    })
+    .await
+}
+
+#[test]
+fn test_dut_from_invalid_json() {
+    let actual = DutInfo::from_json(json!({ "name": "dut_name" }));
+    match actual {
+        Err(OcptvError::Format(e)) => assert!(e.to_string().contains("dutInfoId")),
+        _ => panic!("expected a format error"),
+    }
+
+    let actual = DutInfo::from_json(json!({ "dutInfoId": "dut_id", "softwareInfos": {} }));
+    assert!(matches!(actual, Err(OcptvError::Format(_))));
+}
+
+#[cfg(feature = "tokio-util")]
+#[tokio::test]
+async fn test_testrun_end_on_cancel() -> Result<()> {
+    use tokio_util::sync::CancellationToken;
+
+    let expected = [
+        json_schema_version(),
+        json_run_default_start(),
+        json!({
+            "testRunArtifact": {
+                "log": {
+                    "message": "working",
+                    "severity": "INFO"
+                }
+            },
+            "sequenceNumber": 2,
+            "timestamp": DATETIME_FORMATTED
+        }),
+        json!({
+            "testRunArtifact": {
+                "testRunEnd": {
+                    "result": "NOT_APPLICABLE",
+                    "status": "SKIP"
+                }
+            },
+            "sequenceNumber": 3,
+            "timestamp": DATETIME_FORMATTED
+        }),
+    ];
+
+    check_output(&expected, |run_builder, dut| async move {
+        let run = Arc::new(run_builder.build().start(dut).await?);
+        let token = CancellationToken::new();
+
+        let ender = tokio::spawn({
+            let run = Arc::clone(&run);
+            let token = token.clone();
+            async move {
+                run.end_on_cancel(token, TestStatus::Skip, TestResult::NotApplicable)
+                    .await
+            }
+        });
+
+        run.add_log(LogSeverity::Info, "working").await?;
+        token.cancel();
+        ender.await??;
+
+        Ok(())
+    })
+    .await
+}
+
+#[cfg(feature = "run-guard")]
+#[tokio::test(flavor = "multi_thread")]
+async fn test_testrun_guard_ends_on_early_return() -> Result<()> {
+    use ocptv::output::{LogSeverity, OcptvError, RunGuard};
+
+    let expected = [
+        json_schema_version(),
+        json_run_default_start(),
+        json!({
+            "testRunArtifact": {
+                "testRunEnd": {
+                    "result": "FAIL",
+                    "status": "ERROR"
+                }
+            },
+            "sequenceNumber": 2,
+            "timestamp": DATETIME_FORMATTED
+        }),
+    ];
+
+    async fn diag(run: RunGuard, fail: bool) -> Result<(), OcptvError> {
+        if fail {
+            // the guard is dropped here without an explicit end
+            return Err(OcptvError::Other("early".into()));
+        }
+
+        run.add_log(LogSeverity::Info, "unreachable").await?;
+        run.end(TestStatus::Complete, TestResult::Pass).await
+    }
+
+    check_output(&expected, |run_builder, dut| async move {
+        let run = run_builder
+            .build()
+            .start(dut)
+            .await?
+            .guard(TestStatus::Error, TestResult::Fail);
+
+        assert!(diag(run, true).await.is_err());
+        Ok(())
+    })
+    .await
+}
+
+#[cfg(feature = "run-guard")]
+#[tokio::test(flavor = "multi_thread")]
+async fn test_testrun_library_symptoms_match_snake_case() -> Result<()> {
+    let symptoms = |artifacts: Vec<serde_json::Value>| {
+        artifacts
+            .iter()
+            .filter_map(|a| a.pointer("/testRunArtifact/error/symptom"))
+            .cloned()
+            .collect::<Vec<_>>()
+    };
+    let snake_case = || regex::Regex::new(r"^[a-z][a-z0-9]*(_[a-z0-9]+)*$").unwrap();
+
+    let artifacts = collect_output(
+        |config| config.symptom_pattern(snake_case()),
+        |run_builder, dut| async move {
+            let run = run_builder.build().start(dut).await?.start_scope();
+            drop(run);
+            Ok(())
+        },
+    )
+    .await?;
+    assert_eq!(symptoms(artifacts), [ABANDONED_RUN_SYMPTOM]);
+
+    let artifacts = collect_output(
+        |config| config.symptom_pattern(snake_case()),
+        |run_builder, dut| async move {
+            let run = run_builder.build().start(dut).await?;
+            run.end_from_result(Err("fan-stuck")).await?;
+            Ok(())
+        },
+    )
+    .await?;
+    assert_eq!(symptoms(artifacts), [FAILED_RUN_SYMPTOM]);
+
+    Ok(())
+}
+
+#[cfg(feature = "run-guard")]
+#[tokio::test]
+#[should_panic(expected = "multi-threaded")]
+async fn test_testrun_guard_on_current_thread_runtime() {
+    let run = TestRun::new("run_name", "1.0")
+        .start(DutInfo::new("dut_id"))
+        .await
+        .unwrap();
+
+    run.guard(TestStatus::Error, TestResult::Fail);
+}
+
+#[cfg(feature = "run-guard")]
+#[tokio::test(flavor = "multi_thread")]
+async fn test_testrun_guard_explicit_end() -> Result<()> {
+    let expected = [
+        json_schema_version(),
+        json_run_default_start(),
+        json_run_pass(2),
+    ];
+
+    check_output(&expected, |run_builder, dut| async {
+        let run = run_builder
+            .build()
+            .start(dut)
+            .await?
+            .guard(TestStatus::Error, TestResult::Fail);
+
+        run.end(TestStatus::Complete, TestResult::Pass).await?;
+        Ok(())
+    })
+    .await
+}
+
+#[tokio::test]
+async fn test_testrun_start_scope_completed() -> Result<()> {
+    let expected = [
+        json_schema_version(),
+        json_run_default_start(),
+        json_run_pass(2),
+    ];
+
+    check_output(&expected, |run_builder, dut| async {
+        let run = run_builder.build().start(dut).await?.start_scope();
+
+        run.complete(TestStatus::Complete, TestResult::Pass).await?;
+        Ok(())
+    })
+    .await
+}
+
+#[cfg(feature = "run-guard")]
+#[tokio::test(flavor = "multi_thread")]
+async fn test_testrun_start_scope_abandoned() -> Result<()> {
+    use ocptv::output::ABANDONED_RUN_SYMPTOM;
+
+    let expected = [
+        json_schema_version(),
+        json_run_default_start(),
+        json!({
+            "testRunArtifact": {
+                "error": {
+                    "message": "test run was dropped without being completed",
+                    "symptom": ABANDONED_RUN_SYMPTOM
+                }
+            },
+            "sequenceNumber": 2,
+            "timestamp": DATETIME_FORMATTED
+        }),
+        json!({
+            "testRunArtifact": {
+                "testRunEnd": {
+                    "result": "FAIL",
+                    "status": "ERROR"
+                }
+            },
+            "sequenceNumber": 3,
+            "timestamp": DATETIME_FORMATTED
+        }),
+    ];
+
+    check_output(&expected, |run_builder, dut| async move {
+        let run = run_builder.build().start(dut).await?.start_scope();
+
+        drop(run);
+        Ok(())
+    })
+    .await
+}
+
+#[cfg(feature = "run-guard")]
+#[tokio::test]
+async fn test_testrun_start_scope_abandoned_on_current_thread_runtime() -> Result<()> {
+    use ocptv::output::ABANDONED_RUN_SYMPTOM;
+
+    let expected = [
+        json_schema_version(),
+        json_run_default_start(),
+        json!({
+            "testRunArtifact": {
+                "error": {
+                    "message": "test run was dropped without being completed",
+                    "symptom": ABANDONED_RUN_SYMPTOM
+                }
+            },
+            "sequenceNumber": 2,
+            "timestamp": DATETIME_FORMATTED
+        }),
+        json!({
+            "testRunArtifact": {
+                "testRunEnd": {
+                    "result": "FAIL",
+                    "status": "ERROR"
+                }
+            },
+            "sequenceNumber": 3,
+            "timestamp": DATETIME_FORMATTED
+        }),
+    ];
+
+    check_output(&expected, |run_builder, dut| async move {
+        let run = run_builder.build().start(dut).await?.start_scope();
+
+        drop(run);
+        // the end is written by a task spawned from the drop
+        tokio::task::yield_now().await;
+        Ok(())
+    })
+    .await
+}
+
+#[cfg(not(feature = "run-guard"))]
+#[tokio::test]
+async fn test_testrun_start_scope_dropped_without_run_guard() -> Result<()> {
+    let expected = [json_schema_version(), json_run_default_start()];
+
+    check_output(&expected, |run_builder, dut| async move {
+        let run = run_builder.build().start(dut).await?.start_scope();
+
+        drop(run);
+        Ok(())
+    })
+    .await
+}
+
+#[cfg(feature = "run-guard")]
+#[tokio::test(flavor = "multi_thread")]
+async fn test_testrun_start_scope_abandoned_ends_if_error_fails() -> Result<()> {
+    let expected = [
+        json_schema_version(),
+        json_run_default_start(),
+        json!({
+            "testRunArtifact": {
+                "testRunEnd": {
+                    "result": "FAIL",
+                    "status": "ERROR"
+                }
+            },
+            "sequenceNumber": 2,
+            "timestamp": DATETIME_FORMATTED
+        }),
+    ];
+
+    // rejects the abandoned run symptom, so only the end is written
+    let pattern = regex::Regex::new(r"^[a-z]+$")?;
+    check_output_config(
+        &expected,
+        |config| config.symptom_pattern(pattern),
+        |run_builder, dut| async move {
+            let run = run_builder.build().start(dut).await?.start_scope();
+
+            drop(run);
+            Ok(())
+        },
+    )
+    .await
+}
@@ -10,7 +10,10 @@ use anyhow::Result;
 use serde_json::json;
 use tokio::sync::Mutex;
 
-use ocptv::output::{Config, DutInfo, OcptvError, TestRun, TestStatus};
+use ocptv::output::{
+    Config, DutInfo, Extension, ExtensionRegistry, LogSeverity, OcptvError, TestResult, TestRun,
+    TestStatus, TimestampProvider,
+};
 
 use super::fixture::*;
 
@@ -51,6 +54,69 @@ async fn test_testrun_with_step() -> Result<()> {
     .await
 }
 
+#[tokio::test]
+async fn test_step_id_matches_emitted_id() -> Result<()> {
+    let mut ids = vec![];
+    let artifacts = collect_output(
+        |config| config,
+        |run_builder, dut| async {
+            let run = run_builder.build().start(dut).await?;
+            let first = run.add_step("first step").start().await?;
+            let second = run.add_step("second step").start().await?;
+            assert_eq!(first.id(), "step0");
+            assert_eq!(second.id(), "step1");
+
+            ids = vec![first.id().to_owned(), second.id().to_owned()];
+            Ok(())
+        },
+    )
+    .await?;
+
+    let emitted = artifacts
+        .iter()
+        .filter_map(|value| {
+            value["testStepArtifact"]["testStepId"]
+                .as_str()
+                .map(str::to_owned)
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(emitted, ids);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_step_emits_nothing_until_started() -> Result<()> {
+    let artifacts = collect_output(
+        |config| config,
+        |run_builder, dut| async move {
+            let run = run_builder.build().start(dut).await?;
+            // schemaVersion and testRunStart
+            assert_eq!(run.stats().artifacts, 2);
+
+            let unstarted = run.add_step("unstarted step");
+            drop(unstarted);
+            let step = run.add_step("step_name");
+            assert_eq!(run.stats().artifacts, 2);
+
+            let step = step.start().await?;
+            assert_eq!(run.stats().artifacts, 3);
+            assert_eq!(step.id(), "step1");
+            Ok(())
+        },
+    )
+    .await?;
+
+    assert_eq!(artifacts.len(), 3);
+    assert_eq!(artifacts[2]["testStepArtifact"]["testStepId"], "step1");
+    assert_eq!(
+        artifacts[2]["testStepArtifact"]["testStepStart"]["name"],
+        "step_name"
+    );
+
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_testrun_step_scope_log() -> Result<()> {
     use ocptv::output::{LogSeverity, TestStatus};
@@ -141,6 +207,137 @@ async fn test_step_with_extension() -> Result<()> {
     .await
 }
 
+#[tokio::test]
+async fn test_step_with_typed_extension() -> Result<()> {
+    let expected = [
+        json_schema_version(),
+        json_run_default_start(),
+        json_step_default_start(),
+        json!({
+            "testStepArtifact": {
+                "testStepId": "step0",
+                "extension": {
+                    "name": "TestExtension/v2",
+                    "content": {
+                        "@type": "TestExtension/v2",
+                        "stringField": "string",
+                        "numberField": 42
+                    }
+                }
+            },
+            "sequenceNumber": 3,
+            "timestamp": DATETIME_FORMATTED
+        }),
+        json_step_complete(4),
+        json_run_pass(5),
+    ];
+
+    #[derive(serde::Serialize)]
+    struct Ext {
+        #[serde(rename = "stringField")]
+        string_field: String,
+        #[serde(rename = "numberField")]
+        number_field: u32,
+    }
+
+    impl Extension for Ext {
+        const TYPE: &'static str = "TestExtension/v2";
+    }
+
+    check_output_step(&expected, |s, _| async move {
+        s.add_extension_typed(Ext {
+            string_field: "string".to_owned(),
+            number_field: 42,
+        })
+        .await?;
+
+        Ok(())
+    })
+    .await
+}
+
+#[tokio::test]
+async fn test_step_with_typed_extension_not_object() -> Result<()> {
+    #[derive(serde::Serialize)]
+    struct Ext(u32);
+
+    impl Extension for Ext {
+        const TYPE: &'static str = "TestExtension/v1";
+    }
+
+    let expected = [
+        json_schema_version(),
+        json_run_default_start(),
+        json_step_default_start(),
+        json_step_complete(3),
+        json_run_pass(4),
+    ];
+
+    check_output_step(&expected, |s, _| async move {
+        let result = s.add_extension_typed(Ext(42)).await;
+        assert!(matches!(result, Err(OcptvError::Other(_))));
+
+        Ok(())
+    })
+    .await
+}
+
+#[tokio::test]
+async fn test_step_with_strict_extensions() -> Result<()> {
+    #[derive(serde::Serialize)]
+    struct Known {
+        i: u32,
+    }
+
+    impl Extension for Known {
+        const TYPE: &'static str = "Known/v1";
+    }
+
+    #[derive(serde::Serialize)]
+    struct Unknown {
+        i: u32,
+    }
+
+    impl Extension for Unknown {
+        const TYPE: &'static str = "Unknown/v1";
+    }
+
+    let artifacts = collect_output(
+        |config| config.strict_extensions(ExtensionRegistry::new().register_type::<Known>()),
+        |run_builder, dut| async move {
+            let run = run_builder.build().start(dut).await?;
+            let step = run.add_step("step_name").start().await?;
+
+            step.add_extension_typed(Known { i: 1 }).await?;
+            step.add_extension("untyped_known", json!({ "@type": "Known/v1" }))
+                .await?;
+
+            let actual = step.add_extension_typed(Unknown { i: 2 }).await;
+            match actual {
+                Err(OcptvError::IoError(e)) => {
+                    assert_eq!(e.kind(), std::io::ErrorKind::InvalidInput)
+                }
+                _ => panic!("expected an invalid input error"),
+            }
+            let actual = step.add_extension("untyped", json!({ "i": 3 })).await;
+            assert!(matches!(actual, Err(OcptvError::IoError(_))));
+
+            step.end(TestStatus::Complete).await?;
+            run.end(TestStatus::Complete, TestResult::Pass).await?;
+            Ok(())
+        },
+    )
+    .await?;
+
+    let names = artifacts
+        .iter()
+        .filter_map(|a| a.pointer("/testStepArtifact/extension/name"))
+        .collect::<Vec<_>>();
+    assert_eq!(names, ["Known/v1", "untyped_known"]);
+
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_step_with_extension_which_fails() -> Result<()> {
     #[derive(thiserror::Error, Debug, PartialEq)]
@@ -180,12 +377,246 @@ async fn test_step_with_extension_which_fails() -> Result<()> {
 
     match result {
         Err(OcptvError::Format(e)) => {
-            // `to_string` is the only way to check this error. `serde_json::Error` only
-            // implements source/cause for io errors, and this is a string
-            assert_eq!(e.to_string(), "test_error_fail");
+            // `serde_json::Error` only implements source/cause for io errors, and this is
+            // a custom message, so check the concrete error type and its message
+            let serde_err = e
+                .downcast_ref::<serde_json::Error>()
+                .expect("format error keeps the serde_json::Error");
+            assert!(serde_err.is_data());
+            assert_eq!(serde_err.to_string(), "test_error_fail");
         }
         _ => panic!("unexpected ocptv error type"),
     }
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_scoped_step_with_auto_timing() -> Result<()> {
+    use std::sync::atomic::{AtomicI64, Ordering};
+
+    // each call advances the clock by 250us, so the step duration is below a millisecond
+    struct TickingTsProvider {
+        ticks: AtomicI64,
+    }
+
+    impl TimestampProvider for TickingTsProvider {
+        fn now(&self) -> chrono::DateTime<chrono_tz::Tz> {
+            let ticks = self.ticks.fetch_add(1, Ordering::AcqRel);
+            (DATETIME + chrono::Duration::microseconds(250 * ticks)).with_timezone(&chrono_tz::UTC)
+        }
+    }
+
+    let emitted = collect_output(
+        |config| {
+            config
+                .with_timestamp_provider(Box::new(TickingTsProvider {
+                    ticks: AtomicI64::new(0),
+                }))
+                .auto_step_timing(true)
+        },
+        |run_builder, dut| async move {
+            let run = run_builder.build().start(dut).await?;
+            run.add_step("first step")
+                .scope(|s| async move {
+                    s.add_log(LogSeverity::Info, "working").await?;
+                    Ok(TestStatus::Complete)
+                })
+                .await?;
+            Ok(())
+        },
+    )
+    .await?;
+
+    // schemaVersion, testRunStart, testStepStart, log, step_duration, testStepEnd
+    assert_eq!(emitted.len(), 6);
+    let measurement = &emitted[4]["testStepArtifact"]["measurement"];
+    assert_eq!(measurement["name"], "step_duration");
+    assert_eq!(measurement["unit"], "s");
+    let duration = measurement["value"].as_f64().unwrap();
+    assert!(duration > 0.0 && duration < 0.001);
+    assert!(emitted[5]["testStepArtifact"]["testStepEnd"].is_object());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_testrun_with_skipped_step() -> Result<()> {
+    let expected = [
+        json_schema_version(),
+        json_run_default_start(),
+        json_step_default_start(),
+        json!({
+            "testStepArtifact": {
+                "testStepId": "step0",
+                "log": {
+                    "message": "no fans detected",
+                    "severity": "INFO"
+                }
+            },
+            "sequenceNumber": 3,
+            "timestamp": DATETIME_FORMATTED
+        }),
+        json!({
+            "testStepArtifact": {
+                "testStepId": "step0",
+                "testStepEnd": {
+                    "status": "SKIP"
+                }
+            },
+            "sequenceNumber": 4,
+            "timestamp": DATETIME_FORMATTED
+        }),
+        json_run_pass(5),
+    ];
+
+    check_output_run(&expected, |r, _| async move {
+        let step = r.add_step("first step").start().await?;
+        step.skip("no fans detected").await?;
+
+        Ok(())
+    })
+    .await
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_step_measurements_from_concurrent_handles() -> Result<()> {
+    const PER_TASK: usize = 50;
+
+    let artifacts = collect_output(
+        |config| config,
+        |run_builder, dut| async move {
+            let run = run_builder.build().start(dut).await?;
+            let step = run.add_step("step_name").start().await?;
+
+            let tasks = ["fan0", "fan1"].map(|name| {
+                let handle = step.clone_handle();
+                tokio::spawn(async move {
+                    let series = handle.add_measurement_series(name).start().await?;
+                    series.end().await?;
+                    for i in 0..PER_TASK {
+                        handle.add_measurement(name, i as u64).await?;
+                    }
+                    Ok::<(), OcptvError>(())
+                })
+            });
+            for task in tasks {
+                task.await??;
+            }
+            step.end(TestStatus::Complete).await?;
+            Ok(())
+        },
+    )
+    .await?;
+
+    let seqnos = artifacts
+        .iter()
+        .map(|a| a["sequenceNumber"].as_u64())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        seqnos,
+        (0..artifacts.len() as u64).map(Some).collect::<Vec<_>>()
+    );
+
+    let measurements = artifacts
+        .iter()
+        .filter(|a| a["testStepArtifact"]["measurement"].is_object())
+        .collect::<Vec<_>>();
+    assert_eq!(measurements.len(), 2 * PER_TASK);
+    assert!(measurements
+        .iter()
+        .all(|a| a["testStepArtifact"]["testStepId"] == "step0"));
+
+    let mut series_ids = artifacts
+        .iter()
+        .filter_map(|a| {
+            a["testStepArtifact"]["measurementSeriesStart"]["measurementSeriesId"].as_str()
+        })
+        .collect::<Vec<_>>();
+    series_ids.sort();
+    assert_eq!(series_ids, ["step0_series0", "step0_series1"]);
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_interleaved_steps_sequence_numbers() -> Result<()> {
+    const STEPS: usize = 8;
+    const LOGS_PER_STEP: usize = 100;
+
+    let artifacts = collect_output(
+        |config| config,
+        |run_builder, dut| async move {
+            let run = Arc::new(run_builder.build().start(dut).await?);
+
+            let tasks = (0..STEPS)
+                .map(|i| {
+                    let run = Arc::clone(&run);
+                    tokio::spawn(async move {
+                        let step = run.add_step(&format!("step {}", i)).start().await?;
+                        for j in 0..LOGS_PER_STEP {
+                            step.add_log(LogSeverity::Info, &format!("log {}", j))
+                                .await?;
+                            tokio::task::yield_now().await;
+                        }
+                        step.end(TestStatus::Complete).await
+                    })
+                })
+                .collect::<Vec<_>>();
+            for task in tasks {
+                task.await??;
+            }
+            Ok(())
+        },
+    )
+    .await?;
+
+    let seqnos = artifacts
+        .iter()
+        .map(|value| value["sequenceNumber"].as_u64().unwrap())
+        .collect::<Vec<_>>();
+
+    // schemaVersion, run start, and start/end/logs for each step
+    let expected_len = 2 + STEPS * (LOGS_PER_STEP + 2);
+    assert_eq!(seqnos, (0..expected_len as u64).collect::<Vec<_>>());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_step_with_heartbeat() -> Result<()> {
+    let artifacts = collect_output(
+        |config| config,
+        |run_builder, dut| async move {
+            let run = run_builder.build().start(dut).await?;
+            let step = run
+                .add_step("first step")
+                .start()
+                .await?
+                .with_heartbeat(std::time::Duration::from_millis(10));
+            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+            step.end(TestStatus::Complete).await?;
+
+            // give a leaked heartbeat task the chance to emit after the step end
+            tokio::time::sleep(std::time::Duration::from_millis(30)).await;
+            Ok(())
+        },
+    )
+    .await?;
+
+    let heartbeats = artifacts
+        .iter()
+        .filter(|a| a["testStepArtifact"]["log"]["message"] == "heartbeat")
+        .collect::<Vec<_>>();
+    assert!(!heartbeats.is_empty());
+    for heartbeat in heartbeats {
+        assert_eq!(heartbeat["testStepArtifact"]["testStepId"], "step0");
+        assert_eq!(heartbeat["testStepArtifact"]["log"]["severity"], "DEBUG");
+    }
+    assert_eq!(
+        artifacts.last().unwrap()["testStepArtifact"]["testStepEnd"]["status"],
+        "COMPLETE"
+    );
+
+    Ok(())
+}